@@ -0,0 +1,441 @@
+//! Building programs from Rust without going through source text.
+//!
+//! ```
+//! use sl::{
+//!     builder::ProgramBuilder,
+//!     parser::{Expr, Pattern, TypeExpr},
+//!     type_checker::TypeChecker,
+//! };
+//!
+//! let top_levels = ProgramBuilder::new()
+//!     .data("Nat")
+//!         .constructor("Zero", [])
+//!         .constructor("Succ", [TypeExpr::word("Nat")])
+//!     .def("pred", [TypeExpr::word("Nat")], [TypeExpr::word("Nat")])
+//!         .branch([Pattern::constructor("Zero", [])], [Expr::word("Zero")])
+//!         .branch([Pattern::constructor("Succ", [Pattern::all("n")])], [Expr::word("n")])
+//!     .build();
+//!
+//! let mut diagnostics = vec![];
+//! assert!(TypeChecker::new().type_check(top_levels, &mut diagnostics).is_some());
+//! ```
+//!
+//! Everything built has the default span, as there is no source for
+//! diagnostics to point into.
+
+use crate::{
+    parser::{
+        Branch, Constructor, Expr, ExprKind, Pattern, PatternKind, Signature, TopLevel, TypeExpr, TypeExprKind,
+        BOOL_CONSTRUCTORS, WILDCARD, is_variable_name,
+    },
+    source_map::Span,
+    symbol::Symbol,
+};
+
+#[derive(Default)]
+pub struct ProgramBuilder {
+    top_levels: Vec<TopLevel>,
+}
+
+impl ProgramBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn data(self, name: &str) -> DataBuilder {
+        DataBuilder {
+            program: self,
+            name: Symbol::intern(name),
+            parameters: vec![],
+            constructors: vec![],
+            linear: false,
+            derives: vec![],
+            doc: None,
+        }
+    }
+
+    pub fn def(
+        self,
+        name: &str,
+        inputs: impl IntoIterator<Item = TypeExpr>,
+        outputs: impl IntoIterator<Item = TypeExpr>,
+    ) -> DefBuilder {
+        DefBuilder {
+            program: self,
+            name: Symbol::intern(name),
+            inputs: inputs.into_iter().collect(),
+            outputs: outputs.into_iter().collect(),
+            rows: None,
+            io: false,
+            branches: vec![],
+            total: false,
+            doc: None,
+        }
+    }
+
+    /// Adds `type name = definition`.
+    pub fn alias(mut self, name: &str, definition: TypeExpr) -> Self {
+        self.top_levels.push(TopLevel::Type { name: Symbol::intern(name), definition, span: Span::default() });
+        self
+    }
+
+    pub fn build(self) -> Vec<TopLevel> {
+        self.top_levels
+    }
+}
+
+/// A `data` declaration being built, finished by starting the next top
+/// level or by building the program.
+pub struct DataBuilder {
+    program: ProgramBuilder,
+    name: Symbol,
+    parameters: Vec<Symbol>,
+    constructors: Vec<Constructor>,
+    linear: bool,
+    derives: Vec<Symbol>,
+    doc: Option<String>,
+}
+
+impl DataBuilder {
+    /// Adds a type parameter, referred to from constructor argument types
+    /// with [`TypeExpr::word`].
+    ///
+    /// Panics if `name` doesn't start with a lowercase letter, which the
+    /// parser would reject as well.
+    pub fn parameter(mut self, name: &str) -> Self {
+        assert!(
+            is_variable_name(name),
+            "type parameter `{name}` must start with a lowercase letter",
+        );
+        self.parameters.push(Symbol::intern(name));
+        self
+    }
+
+    /// Makes values of the type have to be used exactly once, as
+    /// `linear data` does.
+    pub fn linear(mut self) -> Self {
+        self.linear = true;
+        self
+    }
+
+    /// Implements `trait_name` from how the values are built, as
+    /// `derives (Eq)` does.
+    pub fn derives(mut self, trait_name: &str) -> Self {
+        self.derives.push(Symbol::intern(trait_name));
+        self
+    }
+
+    /// Documents the type, as `---` comments before it do.
+    pub fn doc(mut self, doc: &str) -> Self {
+        self.doc = Some(doc.to_string());
+        self
+    }
+
+    /// Panics if `name` starts with a lowercase letter, which the parser
+    /// would reject as well.
+    pub fn constructor(mut self, name: &str, argument_types: impl IntoIterator<Item = TypeExpr>) -> Self {
+        assert_constructor_name(name);
+        self.constructors.push(Constructor {
+            name: Symbol::intern(name),
+            argument_types: argument_types.into_iter().collect(),
+            output: None,
+            span: Span::default(),
+        });
+        self
+    }
+
+    /// A constructor building a more specific type than the declared one,
+    /// as in `IntLit(Int - Expr(Int))`.
+    ///
+    /// Panics if `name` starts with a lowercase letter, which the parser
+    /// would reject as well.
+    pub fn refined_constructor(
+        mut self,
+        name: &str,
+        argument_types: impl IntoIterator<Item = TypeExpr>,
+        output: TypeExpr,
+    ) -> Self {
+        assert_constructor_name(name);
+        self.constructors.push(Constructor {
+            name: Symbol::intern(name),
+            argument_types: argument_types.into_iter().collect(),
+            output: Some(output),
+            span: Span::default(),
+        });
+        self
+    }
+
+    pub fn data(self, name: &str) -> DataBuilder {
+        self.finish().data(name)
+    }
+
+    pub fn def(
+        self,
+        name: &str,
+        inputs: impl IntoIterator<Item = TypeExpr>,
+        outputs: impl IntoIterator<Item = TypeExpr>,
+    ) -> DefBuilder {
+        self.finish().def(name, inputs, outputs)
+    }
+
+    pub fn alias(self, name: &str, definition: TypeExpr) -> ProgramBuilder {
+        self.finish().alias(name, definition)
+    }
+
+    pub fn build(self) -> Vec<TopLevel> {
+        self.finish().build()
+    }
+
+    fn finish(self) -> ProgramBuilder {
+        let Self { mut program, name, parameters, constructors, linear, derives, doc } = self;
+        program.top_levels.push(TopLevel::Data { name, parameters, constructors, linear, derives, doc, span: Span::default() });
+        program
+    }
+}
+
+/// A `def` being built, finished by starting the next top level or by
+/// building the program.
+pub struct DefBuilder {
+    program: ProgramBuilder,
+    name: Symbol,
+    inputs: Vec<TypeExpr>,
+    outputs: Vec<TypeExpr>,
+    rows: Option<(Symbol, Symbol)>,
+    io: bool,
+    branches: Vec<Branch>,
+    total: bool,
+    doc: Option<String>,
+}
+
+impl DefBuilder {
+    /// Names the rows the inputs sit on and the outputs are left on, as in
+    /// `(..a inputs - ..b outputs)`.
+    pub fn rows(mut self, input_row: &str, output_row: &str) -> Self {
+        self.rows = Some((Symbol::intern(input_row), Symbol::intern(output_row)));
+        self
+    }
+
+    /// Lets the def perform IO, as `!io` after its outputs does.
+    pub fn io(mut self) -> Self {
+        self.io = true;
+        self
+    }
+
+    /// Makes the def have to terminate, as `total def` does.
+    pub fn total(mut self) -> Self {
+        self.total = true;
+        self
+    }
+
+    /// Documents the def, as `---` comments before it do.
+    pub fn doc(mut self, doc: &str) -> Self {
+        self.doc = Some(doc.to_string());
+        self
+    }
+
+    pub fn branch(
+        mut self,
+        patterns: impl IntoIterator<Item = Pattern>,
+        body: impl IntoIterator<Item = Expr>,
+    ) -> Self {
+        self.branches.push(Branch {
+            patterns: patterns.into_iter().collect(),
+            body: body.into_iter().collect(),
+            span: Span::default(),
+        });
+        self
+    }
+
+    pub fn data(self, name: &str) -> DataBuilder {
+        self.finish().data(name)
+    }
+
+    pub fn def(
+        self,
+        name: &str,
+        inputs: impl IntoIterator<Item = TypeExpr>,
+        outputs: impl IntoIterator<Item = TypeExpr>,
+    ) -> DefBuilder {
+        self.finish().def(name, inputs, outputs)
+    }
+
+    pub fn alias(self, name: &str, definition: TypeExpr) -> ProgramBuilder {
+        self.finish().alias(name, definition)
+    }
+
+    pub fn build(self) -> Vec<TopLevel> {
+        self.finish().build()
+    }
+
+    fn finish(self) -> ProgramBuilder {
+        let Self { mut program, name, inputs, outputs, rows, io, branches, total, doc } = self;
+        program.top_levels.push(TopLevel::Def {
+            name,
+            signature: Some(Signature { inputs, outputs, rows, io }),
+            branches,
+            total,
+            doc,
+            span: Span::default(),
+        });
+        program
+    }
+}
+
+impl TypeExpr {
+    pub fn word(name: &str) -> Self {
+        Self { kind: TypeExprKind::Word(Symbol::intern(name)), span: Span::default() }
+    }
+
+    pub fn application(name: &str, arguments: impl IntoIterator<Item = TypeExpr>) -> Self {
+        Self {
+            kind: TypeExprKind::Application {
+                name: Symbol::intern(name),
+                arguments: arguments.into_iter().collect(),
+            },
+            span: Span::default(),
+        }
+    }
+
+    pub fn quotation(
+        inputs: impl IntoIterator<Item = TypeExpr>,
+        outputs: impl IntoIterator<Item = TypeExpr>,
+    ) -> Self {
+        Self {
+            kind: TypeExprKind::Quotation {
+                inputs: inputs.into_iter().collect(),
+                outputs: outputs.into_iter().collect(),
+                rows: None,
+                io: false,
+                forall: vec![],
+            },
+            span: Span::default(),
+        }
+    }
+
+    /// A quotation type that has to be polymorphic over `variables`, as in
+    /// `[a | a - a]`.
+    pub fn polymorphic_quotation<'variable>(
+        variables: impl IntoIterator<Item = &'variable str>,
+        inputs: impl IntoIterator<Item = TypeExpr>,
+        outputs: impl IntoIterator<Item = TypeExpr>,
+    ) -> Self {
+        Self {
+            kind: TypeExprKind::Quotation {
+                inputs: inputs.into_iter().collect(),
+                outputs: outputs.into_iter().collect(),
+                rows: None,
+                io: false,
+                forall: variables.into_iter().map(Symbol::intern).collect(),
+            },
+            span: Span::default(),
+        }
+    }
+
+    pub fn tuple(elements: impl IntoIterator<Item = TypeExpr>) -> Self {
+        Self { kind: TypeExprKind::Tuple(elements.into_iter().collect()), span: Span::default() }
+    }
+
+    /// A quotation type with named rows, as in `[..a inputs - ..b outputs]`.
+    pub fn quotation_with_rows(
+        input_row: &str,
+        inputs: impl IntoIterator<Item = TypeExpr>,
+        output_row: &str,
+        outputs: impl IntoIterator<Item = TypeExpr>,
+    ) -> Self {
+        Self {
+            kind: TypeExprKind::Quotation {
+                inputs: inputs.into_iter().collect(),
+                outputs: outputs.into_iter().collect(),
+                rows: Some((Symbol::intern(input_row), Symbol::intern(output_row))),
+                io: false,
+                forall: vec![],
+            },
+            span: Span::default(),
+        }
+    }
+}
+
+impl Expr {
+    pub fn word(name: &str) -> Self {
+        Self { kind: ExprKind::Word(Symbol::intern(name)), span: Span::default() }
+    }
+
+    pub fn quotation(
+        inputs: impl IntoIterator<Item = TypeExpr>,
+        quotation: impl IntoIterator<Item = Expr>,
+    ) -> Self {
+        Self {
+            kind: ExprKind::Quotation {
+                inputs: Some((inputs.into_iter().collect(), Span::default())),
+                quotation: quotation.into_iter().collect(),
+            },
+            span: Span::default(),
+        }
+    }
+
+    /// A tuple of what each element pushes.
+    pub fn tuple<E: IntoIterator<Item = Expr>>(elements: impl IntoIterator<Item = E>) -> Self {
+        Self {
+            kind: ExprKind::Tuple(elements.into_iter().map(|element| element.into_iter().collect()).collect()),
+            span: Span::default(),
+        }
+    }
+
+    /// A quotation whose inputs are inferred from its body.
+    pub fn inferred_quotation(quotation: impl IntoIterator<Item = Expr>) -> Self {
+        Self {
+            kind: ExprKind::Quotation {
+                inputs: None,
+                quotation: quotation.into_iter().collect(),
+            },
+            span: Span::default(),
+        }
+    }
+}
+
+impl Pattern {
+    /// Panics if `name` starts with an uppercase letter or is a `Bool`,
+    /// which the parser would read as a constructor.
+    pub fn all(name: &str) -> Self {
+        assert!(
+            is_variable_name(name) && !BOOL_CONSTRUCTORS.contains(&name),
+            "binding `{name}` must start with a lowercase letter and not be a Bool",
+        );
+        Self { kind: PatternKind::All(Symbol::intern(name)), span: Span::default() }
+    }
+
+    /// `_`, matching anything without binding it.
+    pub fn wildcard() -> Self {
+        Self { kind: PatternKind::All(Symbol::intern(WILDCARD)), span: Span::default() }
+    }
+
+    /// Panics if `name` starts with a lowercase letter and isn't a `Bool`,
+    /// which the parser would read as a binding.
+    pub fn constructor(name: &str, arguments: impl IntoIterator<Item = Pattern>) -> Self {
+        if !BOOL_CONSTRUCTORS.contains(&name) {
+            assert_constructor_name(name);
+        }
+        Self {
+            kind: PatternKind::Constructor {
+                name: Symbol::intern(name),
+                arguments: arguments.into_iter().collect(),
+            },
+            span: Span::default(),
+        }
+    }
+
+    pub fn tuple(elements: impl IntoIterator<Item = Pattern>) -> Self {
+        Self { kind: PatternKind::Tuple(elements.into_iter().collect()), span: Span::default() }
+    }
+
+    pub fn char(ch: char) -> Self {
+        Self { kind: PatternKind::Char(ch), span: Span::default() }
+    }
+}
+
+fn assert_constructor_name(name: &str) {
+    assert!(
+        !is_variable_name(name),
+        "constructor `{name}` must not start with a lowercase letter",
+    );
+}
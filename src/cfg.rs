@@ -0,0 +1,70 @@
+//! Conditional compilation of top levels.
+//!
+//! A top level preceded by `#[cfg(...)]` is only kept when the predicate
+//! holds for the [`Cfg`] the parser was given:
+//!
+//! ```text
+//! #[cfg(feature = "debug")]
+//! def log(Nat - Nat)
+//! | n = n
+//!
+//! #[cfg(all(target = "wasm", not(feature = "threads")))]
+//! def spawn(Nat - Nat)
+//! | n = n
+//! ```
+
+use std::collections::HashSet;
+
+use crate::symbol::Symbol;
+
+#[derive(Clone, Debug)]
+pub enum CfgPredicate {
+    Feature(Symbol),
+    Target(Symbol),
+    Not(Box<CfgPredicate>),
+    All(Vec<CfgPredicate>),
+    Any(Vec<CfgPredicate>),
+}
+
+/// The enabled features and the target being compiled for, `interpreter`
+/// unless told otherwise.
+#[derive(Clone, Debug)]
+pub struct Cfg {
+    features: HashSet<Symbol>,
+    target: Symbol,
+}
+
+impl Cfg {
+    pub fn new() -> Self {
+        Self {
+            features: HashSet::new(),
+            target: Symbol::intern("interpreter"),
+        }
+    }
+
+    pub fn feature(mut self, name: &str) -> Self {
+        self.features.insert(Symbol::intern(name));
+        self
+    }
+
+    pub fn target(mut self, name: &str) -> Self {
+        self.target = Symbol::intern(name);
+        self
+    }
+
+    pub fn is_enabled(&self, predicate: &CfgPredicate) -> bool {
+        match predicate {
+            CfgPredicate::Feature(feature) => self.features.contains(feature),
+            CfgPredicate::Target(target) => self.target == *target,
+            CfgPredicate::Not(predicate) => !self.is_enabled(predicate),
+            CfgPredicate::All(predicates) => predicates.iter().all(|predicate| self.is_enabled(predicate)),
+            CfgPredicate::Any(predicates) => predicates.iter().any(|predicate| self.is_enabled(predicate)),
+        }
+    }
+}
+
+impl Default for Cfg {
+    fn default() -> Self {
+        Self::new()
+    }
+}
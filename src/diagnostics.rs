@@ -0,0 +1,306 @@
+//! Diagnostics reported while parsing and type checking, and the sinks
+//! they are reported to.
+
+use std::{collections::HashMap, error::Error, fmt, ops::Deref, sync::Arc};
+
+use self::catalog::Catalog;
+use crate::{
+    parser::{ParseError, WILDCARD},
+    source_map::Span,
+    type_checker::{TypeCheckError, TypeCheckWarning, TypeTrace},
+};
+
+pub mod catalog;
+pub mod explain;
+pub mod json;
+pub mod render;
+pub mod sarif;
+pub mod short;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Severity {
+    /// The program doesn't check.
+    Error,
+    Warning,
+    /// Only informs, as traces do. Levels don't change it.
+    Note,
+}
+
+/// What a diagnostic is about, for embedders to match on.
+#[derive(Debug)]
+pub enum DiagnosticKind {
+    Parse(ParseError),
+    TypeCheck(TypeCheckError),
+    TypeCheckWarning(TypeCheckWarning),
+    TypeTrace(TypeTrace),
+}
+
+impl From<ParseError> for DiagnosticKind {
+    fn from(error: ParseError) -> Self {
+        DiagnosticKind::Parse(error)
+    }
+}
+
+impl From<TypeCheckError> for DiagnosticKind {
+    fn from(error: TypeCheckError) -> Self {
+        DiagnosticKind::TypeCheck(error)
+    }
+}
+
+impl From<TypeCheckWarning> for DiagnosticKind {
+    fn from(warning: TypeCheckWarning) -> Self {
+        DiagnosticKind::TypeCheckWarning(warning)
+    }
+}
+
+impl From<TypeTrace> for DiagnosticKind {
+    fn from(trace: TypeTrace) -> Self {
+        DiagnosticKind::TypeTrace(trace)
+    }
+}
+
+/// Every diagnostic comes with the span of the source it is about: the
+/// token parsing stopped at, or the innermost expression, pattern or top
+/// level being checked.
+#[derive(Debug)]
+pub struct Diagnostic {
+    /// That of its kind unless [`Levels`] changed it.
+    pub severity: Severity,
+    /// The code of the error or warning, as in `E0001`.
+    pub code: &'static str,
+    pub span: Span,
+    /// Written with the text of its catalog, English unless localized.
+    pub message: String,
+    /// Changes to the source fixing it, the likeliest to be right first.
+    pub suggestions: Vec<Suggestion>,
+    pub kind: DiagnosticKind,
+    catalog: Arc<Catalog>,
+}
+
+/// A change to the source that fixes what a diagnostic is about, for
+/// editors to offer applying.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Suggestion {
+    /// What the change does, as in ``replace with `inc` ``.
+    pub message: String,
+    /// The source replaced, which is inserted at when empty.
+    pub span: Span,
+    pub replacement: String,
+}
+
+impl Diagnostic {
+    pub fn new(kind: impl Into<DiagnosticKind>, span: Span) -> Self {
+        let kind = kind.into();
+        let (severity, code) = match &kind {
+            DiagnosticKind::Parse(error) => (Severity::Error, error.code()),
+            DiagnosticKind::TypeCheck(error) => (Severity::Error, error.code()),
+            DiagnosticKind::TypeCheckWarning(warning) => (Severity::Warning, warning.code()),
+            DiagnosticKind::TypeTrace(trace) => (Severity::Note, trace.code()),
+        };
+        let mut diagnostic = Self {
+            severity,
+            code,
+            span,
+            message: String::new(),
+            suggestions: vec![],
+            kind,
+            catalog: Catalog::english(),
+        };
+        diagnostic.localize(Catalog::english());
+        diagnostic
+    }
+
+    /// Writes the message and suggestions, and what renderers show along
+    /// with them, with the text of `catalog`.
+    pub fn localize(&mut self, catalog: Arc<Catalog>) {
+        self.message = match &self.kind {
+            DiagnosticKind::Parse(error) => error.message(&catalog),
+            DiagnosticKind::TypeCheck(error) => error.message(&catalog),
+            DiagnosticKind::TypeCheckWarning(warning) => warning.message(&catalog),
+            DiagnosticKind::TypeTrace(trace) => trace.message(&catalog),
+        };
+        self.suggestions = suggestions(&self.kind, self.span, &catalog);
+        self.catalog = catalog;
+    }
+
+    /// The catalog the diagnostic is written with.
+    pub fn catalog(&self) -> &Catalog {
+        &self.catalog
+    }
+
+    pub fn is_error(&self) -> bool {
+        self.severity == Severity::Error
+    }
+
+    /// How the severity is written in the output of every format.
+    pub fn level(&self) -> &'static str {
+        match self.severity {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+            Severity::Note => "note",
+        }
+    }
+}
+
+/// The code and message, as in ``error[E0001]: cannot find `hello` in `main` ``.
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}[{}]: {}", self.level(), self.code, self.message)
+    }
+}
+
+impl Error for Diagnostic {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match &self.kind {
+            DiagnosticKind::Parse(error) => Some(error),
+            DiagnosticKind::TypeCheck(error) => Some(error),
+            DiagnosticKind::TypeCheckWarning(_) | DiagnosticKind::TypeTrace(_) => None,
+        }
+    }
+}
+
+/// Every diagnostic reported while checking a program that doesn't check,
+/// in the order they were reported.
+#[derive(Debug)]
+pub struct Diagnostics(pub Vec<Diagnostic>);
+
+impl Deref for Diagnostics {
+    type Target = [Diagnostic];
+
+    fn deref(&self) -> &[Diagnostic] {
+        &self.0
+    }
+}
+
+impl IntoIterator for Diagnostics {
+    type Item = Diagnostic;
+    type IntoIter = std::vec::IntoIter<Diagnostic>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+/// Every diagnostic on a line of its own.
+impl fmt::Display for Diagnostics {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (index, diagnostic) in self.0.iter().enumerate() {
+            if index > 0 {
+                writeln!(f)?;
+            }
+            write!(f, "{diagnostic}")?;
+        }
+        Ok(())
+    }
+}
+
+impl Error for Diagnostics {}
+
+/// The suggestions for a diagnostic of `kind` reported at `span`: the
+/// similarly named words for a word that can't be found, a branch for
+/// what isn't matched and a wildcard for an unused local.
+fn suggestions(kind: &DiagnosticKind, span: Span, catalog: &Catalog) -> Vec<Suggestion> {
+    match kind {
+        DiagnosticKind::TypeCheck(TypeCheckError::UnboundSymbol { suggestions, .. }) => suggestions
+            .iter()
+            .map(|suggestion| Suggestion {
+                message: catalog.text("suggestion.replace", &[("replacement", suggestion.to_string())]),
+                span,
+                replacement: suggestion.to_string(),
+            })
+            .collect(),
+        DiagnosticKind::TypeCheck(TypeCheckError::NonExhaustivePatterns { missing, .. }) => vec![Suggestion {
+            message: catalog.text("suggestion.branch", &[("patterns", missing.clone())]),
+            span: Span::new(span.end, span.end),
+            replacement: format!("\n| {missing} = ?"),
+        }],
+        DiagnosticKind::TypeCheckWarning(TypeCheckWarning::UnusedLocal { .. }) => vec![Suggestion {
+            message: catalog.text("suggestion.replace", &[("replacement", WILDCARD.to_string())]),
+            span,
+            replacement: WILDCARD.to_string(),
+        }],
+        _ => vec![],
+    }
+}
+
+/// What is done with a warning, as set with `-A`, `-W` and `-D`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Level {
+    /// It isn't reported.
+    Allow,
+    Warn,
+    /// It is reported as an error.
+    Deny,
+}
+
+/// The level of warnings by code, `Warn` for those without one. Errors
+/// can't be allowed or turned into warnings.
+#[derive(Clone, Debug, Default)]
+pub struct Levels {
+    codes: HashMap<String, Level>,
+    /// The level of every warning without one of its own, as set for
+    /// `warnings`.
+    warnings: Option<Level>,
+}
+
+impl Levels {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the level of the warning with `code`, or of every warning that
+    /// doesn't have one for `warnings`. Whichever is set last wins.
+    pub fn set(mut self, code: &str, level: Level) -> Self {
+        if code == "warnings" {
+            self.codes.clear();
+            self.warnings = Some(level);
+        } else {
+            self.codes.insert(code.to_string(), level);
+        }
+        self
+    }
+
+    /// `diagnostic` with the severity it is reported with, if it is at all.
+    pub fn apply(&self, mut diagnostic: Diagnostic) -> Option<Diagnostic> {
+        if diagnostic.severity != Severity::Warning {
+            return Some(diagnostic);
+        }
+        match self.codes.get(diagnostic.code).copied().or(self.warnings).unwrap_or(Level::Warn) {
+            Level::Allow => None,
+            Level::Warn => Some(diagnostic),
+            Level::Deny => {
+                diagnostic.severity = Severity::Error;
+                Some(diagnostic)
+            }
+        }
+    }
+}
+
+/// Receives diagnostics as they are produced, which leaves it to the
+/// embedder whether they are collected, printed or forwarded elsewhere.
+pub trait DiagnosticSink {
+    fn report(&mut self, diagnostic: Diagnostic);
+}
+
+/// Collects every reported diagnostic.
+impl DiagnosticSink for Vec<Diagnostic> {
+    fn report(&mut self, diagnostic: Diagnostic) {
+        self.push(diagnostic);
+    }
+}
+
+/// Forwards every reported diagnostic to the closure.
+impl<F: FnMut(Diagnostic)> DiagnosticSink for F {
+    fn report(&mut self, diagnostic: Diagnostic) {
+        self(diagnostic);
+    }
+}
+
+/// Prints every reported diagnostic to stderr as soon as it arrives.
+pub struct StderrSink;
+
+impl DiagnosticSink for StderrSink {
+    fn report(&mut self, diagnostic: Diagnostic) {
+        eprintln!("{diagnostic}");
+    }
+}
@@ -0,0 +1,107 @@
+//! The text diagnostics are written in, looked up by the code of the
+//! diagnostic or the name of a part of one so that it can be translated:
+//!
+//! ```
+//! use std::sync::Arc;
+//!
+//! use sl::{diagnostics::catalog::Catalog, engine::Engine};
+//!
+//! let catalog = Catalog::parse("E0001 = `{name}` introuvable dans `{def}`").unwrap();
+//! let engine = Engine::new().catalog(Arc::new(catalog));
+//! let diagnostics = engine.check("def main(- Int)\n| = 1 hello\n").err().unwrap();
+//!
+//! assert_eq!(diagnostics[0].message, "`hello` introuvable dans `main`");
+//! // What the catalog doesn't translate is in English.
+//! assert_eq!(diagnostics[0].catalog().text("label.unbound", &[]), "not a local or a global word");
+//! ```
+//!
+//! A catalog has an entry on each line, as in `E0009 = the type `{name}` is
+//! defined more than once`, where `{name}` stands for an argument of the
+//! diagnostic. Lines starting with `#` are comments.
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, OnceLock},
+};
+
+const ENGLISH: &str = include_str!("locales/en.catalog");
+
+#[derive(Debug)]
+pub struct Catalog {
+    messages: HashMap<String, String>,
+}
+
+impl Catalog {
+    /// Parses the entries of `source`, failing with the number of the first
+    /// line that isn't an entry or a comment.
+    pub fn parse(source: &str) -> Result<Catalog, String> {
+        let mut messages = HashMap::new();
+        for (index, line) in source.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let Some((key, template)) = line.split_once(" = ").or_else(|| line.strip_suffix(" =").map(|key| (key, ""))) else {
+                return Err(format!("line {} isn't of the form `key = text`", index + 1));
+            };
+            messages.insert(key.trim().to_string(), template.to_string());
+        }
+        Ok(Catalog { messages })
+    }
+
+    /// The catalog every diagnostic is written with unless told otherwise,
+    /// and which the text missing from others is taken from.
+    pub fn english() -> Arc<Catalog> {
+        static ENGLISH_CATALOG: OnceLock<Arc<Catalog>> = OnceLock::new();
+        ENGLISH_CATALOG
+            .get_or_init(|| Arc::new(Catalog::parse(ENGLISH).expect("the English catalog parses")))
+            .clone()
+    }
+
+    /// The catalog shipped for the locale `name`, as in `en`.
+    pub fn builtin(name: &str) -> Option<Arc<Catalog>> {
+        match name {
+            "en" => Some(Catalog::english()),
+            _ => None,
+        }
+    }
+
+    /// Whether the catalog, or the English one it falls back to, has text
+    /// for `key`.
+    pub fn contains(&self, key: &str) -> bool {
+        self.messages.contains_key(key) || Catalog::english().messages.contains_key(key)
+    }
+
+    /// The text for `key` with each `{name}` in it replaced by the argument
+    /// of that name, or the key itself if no catalog has it.
+    pub fn text(&self, key: &str, arguments: &[(&str, String)]) -> String {
+        let template = match self.messages.get(key) {
+            Some(template) => template.clone(),
+            None if !std::ptr::eq(self, &*Catalog::english()) => return Catalog::english().text(key, arguments),
+            None => key.to_string(),
+        };
+        // Replaced in one pass, so that braces in the arguments are kept.
+        let mut text = String::new();
+        let mut rest = &template[..];
+        while let Some(start) = rest.find('{') {
+            text.push_str(&rest[..start]);
+            rest = &rest[start..];
+            let argument = rest.find('}').and_then(|end| {
+                let value = arguments.iter().find(|(name, _)| *name == &rest[1..end])?;
+                Some((value, end))
+            });
+            match argument {
+                Some(((_, value), end)) => {
+                    text.push_str(value);
+                    rest = &rest[end + 1..];
+                }
+                None => {
+                    text.push('{');
+                    rest = &rest[1..];
+                }
+            }
+        }
+        text.push_str(rest);
+        text
+    }
+}
@@ -0,0 +1,496 @@
+//! Longer descriptions of every error, warning and trace by code, with an
+//! example of source reporting it, which `sl explain` prints.
+
+/// The description of the diagnostic with `code`, if there is one with it.
+pub fn explain(code: &str) -> Option<&'static str> {
+    let explanation = match code {
+        "E0001" => r#"A word is used that is neither a local bound by the patterns of the branch
+nor a global word: a def, constructor, method or builtin.
+
+    def main(- Int)
+    | = 1 inc
+
+Define the word, or correct its name if it is a typo of one that exists."#,
+        "E0002" => r#"A word is used on a stack whose top doesn't have the types of its inputs.
+Without a word it is `&` that is given a quotation whose inputs don't fit,
+or the patterns of a branch that don't fit the inputs of the def.
+
+    def main(- Int)
+    | = "one" 1 +"#,
+        "E0003" => r#"A word takes more values than there are on the part of the stack it can
+consume. Inside a def that is what its inputs leave, inside a quotation
+or tuple element what was pushed in it.
+
+    def main(- Int)
+    | = 1 +"#,
+        "E0004" => r#"A branch leaves a stack that doesn't have the outputs of the signature of
+its def.
+
+    def main(- Int)
+    | = 1 2"#,
+        "E0005" => r#"A name shared by several defs is used on a stack none of them can be given.
+
+    def size(Int - Int)
+    | n = n
+
+    def size(Float - Int)
+    | _ = 1
+
+    def main(- Int)
+    | = "one" size"#,
+        "E0006" => r#"A name shared by several defs is used where too little is known about the
+stack to tell which of them is meant.
+
+    def size(Int - Int)
+    | n = n
+
+    def size(String - Int)
+    | _ = 1
+
+    def measure
+    | n = n size
+
+Giving `measure` the signature `(Int - Int)` picks one."#,
+        "E0007" => r#"Some stack could be given to more than one of the defs sharing a name, so
+uses of it couldn't always be resolved to one of them.
+
+    def size(Int - Int)
+    | n = n
+
+    def size(a - Int)
+    | _ = 1"#,
+        "E0008" => r#"A global word is defined more than once: as two defs, constructors or
+methods, or as one of them and a builtin. Defs sharing a name are
+overloads, which only other defs can be.
+
+    data Nat
+    | Zero
+
+    def Zero(- Int)
+    | = 0"#,
+        "E0009" => r#"A data type or type alias has the name of one that is already declared,
+or of a type built into the language.
+
+    data Nat
+    | Zero
+
+    data Nat
+    | Z"#,
+        "E0010" => r#"A pattern binds the name of a global word while the checker forbids
+shadowing, which embedders can ask it to.
+
+    def show(Int - Int)
+    | print = print
+
+Unless shadowing is forbidden this is only warned about, as W0002."#,
+        "E0011" => r#"Quotations and tuples are nested deeper than the checker's limit, 256
+unless the embedder set another one. Splitting the nested quotations into
+defs of their own keeps each of them below it."#,
+        "E0012" => r#"An element of a tuple has to leave exactly one value, and can't consume
+anything pushed before the tuple.
+
+    def main(- (Int, Int))
+    | = (1 2, 3)"#,
+        "E0013" => r#"`&` is used on a value that isn't a quotation.
+
+    def main(- Int)
+    | = "one" &"#,
+        "E0014" => r#"A numeric literal is used as a value of a type other than `Int` or `Float`.
+
+    def main(- String)
+    | = 1"#,
+        "E0015" => r#"Some inputs of a def aren't matched by any of its branches.
+
+    data Nat
+    | Zero
+    | Succ(Nat)
+
+    def pred(Nat - Nat)
+    | Succ(n) = n
+
+Add a branch for the inputs left out, as in `| Zero = Zero`."#,
+        "E0016" => r#"The signature of a def has to be given: it shares its name with other
+defs, uses unannotated defs that use it, or its stack effect couldn't be
+told from its branches.
+
+    def even
+    | n = n odd
+
+    def odd
+    | n = n even"#,
+        "E0017" => r#"A constructor declared with an output type builds a type other than the
+one it is declared in.
+
+    data Nat
+    | Zero
+
+    data Box(a)
+    | Box(a - Nat)"#,
+        "E0018" => r#"A pattern names something that isn't a constructor.
+
+    def main(Int - Int)
+    | Zero = 1"#,
+        "E0019" => r#"A constructor pattern matches a different number of arguments than the
+constructor takes.
+
+    data Pair
+    | Pair(Int Int)
+
+    def first(Pair - Int)
+    | Pair(a) = a"#,
+        "E0020" => r#"A constructor pattern matches an input of another type than the one the
+constructor builds.
+
+    data Nat
+    | Zero
+
+    def main(Int - Int)
+    | Zero = 1"#,
+        "E0021" => r#"A tuple pattern matches an input that isn't a tuple of as many elements.
+
+    def first(Int - Int)
+    | (a, b) = a"#,
+        "E0022" => r#"A name bound more than once in the patterns of a branch, which compares
+the values with `Eq`, is bound to values of different types.
+
+    trait Eq(a)
+    | eq(a a - Bool)
+
+    def same(Int String - Bool)
+    | a a = true"#,
+        "E0023" => r#"A name is bound more than once in the patterns of a branch without an `Eq`
+trait to compare the values with.
+
+    def same(Int Int - Int)
+    | a a = a"#,
+        "E0024" => r#"A constructor of a type that isn't linear takes a linear value, which
+matching the constructor could drop.
+
+    linear data File
+    | File(Int)
+
+    data Box
+    | Box(File)
+
+Declaring the type `linear data Box` too fixes it."#,
+        "E0025" => r#"A local of a linear type is used more than once.
+
+    linear data File
+    | File(Int)
+
+    def twice(File - File File)
+    | f = f f"#,
+        "E0026" => r#"A local of a linear type isn't used.
+
+    linear data File
+    | File(Int)
+
+    def forget(File - Int)
+    | f = 1"#,
+        "E0027" => r#"A local of a linear type is used in a quotation, which could be unquoted
+any number of times.
+
+    linear data File
+    | File(Int)
+
+    def later(File - [- File])
+    | f = [- f]"#,
+        "E0028" => r#"A linear value is given to a word where its type is a type variable, so
+the word could duplicate or drop it.
+
+    linear data File
+    | File(Int)
+
+    def drop(a - )
+    | _ =
+
+    def main(- )
+    | = 1 File drop"#,
+        "E0029" => r#"A `total` def uses a word that isn't known to terminate: a def that isn't
+total or is declared after it, or a method.
+
+    def loop(Int - Int)
+    | n = n loop
+
+    total def main(- Int)
+    | = 1 loop"#,
+        "E0030" => r#"A `total` def uses itself without one of its inputs being given a value
+matched inside that input in every use, which could recurse forever.
+
+    data Nat
+    | Zero
+    | Succ(Nat)
+
+    total def grow(Nat - Nat)
+    | n = n Succ grow
+
+Shrinking some input each time isn't enough, as the inputs can take turns
+growing back:
+
+    total def swap(Nat Nat - Nat)
+    | Succ(a) y = a y Succ Succ swap
+    | Zero Succ(b) = Zero Succ b swap
+    | Zero Zero = Zero"#,
+        "E0031" => r#"A type annotation names a type that isn't declared.
+
+    def main(- Nat)
+    | = 1"#,
+        "E0032" => r#"A type is applied to a different number of arguments than it has
+parameters. Type variables and aliases take none.
+
+    data Box(a)
+    | Box(a)
+
+    def main(- Box)
+    | = 1 Box"#,
+        "E0033" => r#"A type alias is defined in terms of itself, directly or through other
+aliases.
+
+    type Loop = [Loop - ]"#,
+        "E0034" => r#"A def performs IO, by using a word that does or unquoting a quotation that
+does, without `!io` at the end of its signature.
+
+    def main( - )
+    | = "hello" print
+
+Writing the signature as `( - !io)` fixes it."#,
+        "E0035" => r#"A `?` is left in a branch. The error shows the stack there and what the
+rest of the branch expects it to be, which makes holes useful to find
+what is missing while writing a def.
+
+    def main(- Int)
+    | = 1 ? +"#,
+        "E0036" => r#"A trait has the name of one that is already declared.
+
+    trait Show(a)
+    | show(a - String)
+
+    trait Show(a)
+    | display(a - String)"#,
+        "E0037" => r#"An impl or `derives` names a trait that isn't declared.
+
+    impl Show(Int)
+    | show = int_show"#,
+        "E0038" => r#"No input of a method is of the parameter of its trait, so nothing could
+pick the impl to use.
+
+    trait Default(a)
+    | default(- a)"#,
+        "E0039" => r#"A trait is implemented for something other than a data or primitive type,
+like a quotation or a tuple.
+
+    trait Show(a)
+    | show(a - String)
+
+    impl Show((Int, Int))
+    | show = pair_show"#,
+        "E0040" => r#"A trait is implemented more than once for the same type, by impls or by
+`derives`.
+
+    trait Show(a)
+    | show(a - String)
+
+    def int_show(Int - String)
+    | _ = "int"
+
+    impl Show(Int)
+    | show = int_show
+
+    impl Show(Int)
+    | show = int_show"#,
+        "E0041" => r#"An impl gives a word for a method its trait doesn't have.
+
+    trait Show(a)
+    | show(a - String)
+
+    def int_show(Int - String)
+    | _ = "int"
+
+    impl Show(Int)
+    | show = int_show
+    | display = int_show"#,
+        "E0042" => r#"An impl gives more than one word for the same method.
+
+    trait Show(a)
+    | show(a - String)
+
+    def int_show(Int - String)
+    | _ = "int"
+
+    impl Show(Int)
+    | show = int_show
+    | show = int_show"#,
+        "E0043" => r#"An impl doesn't give a word for every method of its trait.
+
+    trait Show(a)
+    | show(a - String)
+    | debug(a - String)
+
+    def int_show(Int - String)
+    | _ = "int"
+
+    impl Show(Int)
+    | show = int_show"#,
+        "E0044" => r#"An impl gives a method a word that isn't a global word.
+
+    trait Show(a)
+    | show(a - String)
+
+    impl Show(Int)
+    | show = int_show"#,
+        "E0045" => r#"An impl gives a method a word that can't be used as the method is at the
+type of the impl.
+
+    trait Show(a)
+    | show(a - String)
+
+    def int_show(Int - Int)
+    | n = n
+
+    impl Show(Int)
+    | show = int_show"#,
+        "E0046" => r#"A method is used at a type its trait isn't implemented for.
+
+    trait Show(a)
+    | show(a - String)
+
+    def main(- String)
+    | = 1.5 show"#,
+        "E0047" => r#"A data type derives a trait there is no known way to derive. Only an `Eq`
+with a single `(a a - Bool)` method and a `Show` with a single
+`(a - String)` one can be.
+
+    trait Ord(a)
+    | compare(a a - Int)
+
+    data Nat derives (Ord)
+    | Zero"#,
+        "E0048" => r#"A data type derives a trait that isn't implemented for the type of an
+argument of one of its constructors.
+
+    trait Show(a)
+    | show(a - String)
+
+    data Box derives (Show)
+    | Box(Float)"#,
+        "E0049" => r#"A branch has more patterns than its def has inputs. Every pattern matches
+one input, the last pattern the input on top of the stack.
+
+    def first(Int - Int)
+    | a b = a"#,
+        "E0050" => r#"A character pattern matches an input that isn't a `Char`.
+
+    def first(Int - Int)
+    | 'a' = 1"#,
+        "E0101" => r#"A token appears where it can't be parsed, like a closing parenthesis
+without an opening one.
+
+    def main(- Int)
+    | = )"#,
+        "E0102" => r#"The source ends in the middle of a top level.
+
+    def main(- Int"#,
+        "E0103" => r#"A constructor's name starts with a lowercase letter, or a letter without
+case like `数`, which names locals in patterns.
+
+    data Nat
+    | zero"#,
+        "E0104" => r#"A type parameter starts with an uppercase letter, which names types.
+
+    data Box(A)
+    | Box(A)"#,
+        "E0105" => r#"Only one side of a stack effect has a row, as in `(..a Int - Int)`. The
+rest of the stack has to be named on both sides or on neither.
+
+    def main(..a - Int)
+    | = 1"#,
+        "E0106" => r#"Quotations, tuples, patterns or types are nested more than 256 deep, too
+deep to parse. Splitting the nested quotations into defs of their own
+keeps each of them below it."#,
+        "E0107" => r#"A string literal isn't closed before the end of the file.
+
+    def main(- String)
+    | = "hello"#,
+        "E0108" => r#"An integer literal doesn't fit in an `Int`, a 64-bit signed integer.
+
+    def main(- Int)
+    | = 99999999999999999999"#,
+        "E0109" => r#"A top level is preceded by an attribute other than `#[cfg(...)]`.
+
+    #[inline]
+    def main(- Int)
+    | = 1"#,
+        "E0110" => r#"A `#[cfg(...)]` attribute has a predicate other than `feature = "..."`,
+`target = "..."`, `not(...)`, `all(...)` and `any(...)`.
+
+    #[cfg(os = "linux")]
+    def main(- Int)
+    | = 1"#,
+        "E0111" => r#"A block comment isn't closed before the end of the file. Block comments
+nest, so every `{-` in one needs a `-}` of its own.
+
+    {- outer {- inner -}
+    def main(- Int)
+    | = 1"#,
+        "E0112" => r##"A string or character literal has a `\` followed by a character it
+doesn't escape. The escapes are `\n`, `\t`, `\r`, `\0`, `\"`, `\'` and `\\`.
+
+    def main(- String)
+    | = "a\qb""##,
+        "E0113" => r#"A character literal doesn't have exactly one character or escape between
+its `'`s, as in `''` and `'ab'`, or isn't closed on its line.
+
+    def main(- Char)
+    | = 'ab'"#,
+        "E0114" => r#"A word isn't a name. Names starting with a letter or `_` can only have
+letters, digits and `_` after it, and only numbers start with a digit.
+Names starting with a symbol, like `+` or `→`, can have anything after it.
+
+    def main(- Int)
+    | = 1st"#,
+        "W0001" => r#"A branch only matches inputs the branches before it already do, so it is
+never run.
+
+    def main(Int - Int)
+    | _ = 1
+    | n = n"#,
+        "W0002" => r#"A pattern binds the name of a global word, which the body of the branch
+then can't use.
+
+    def main(Int - Int)
+    | print = print"#,
+        "W0003" => r#"No value of a data type can be built, as every constructor of it takes a
+value of a type no value can be built of.
+
+    data Loop
+    | Loop(Loop)"#,
+        "W0004" => r#"A def is used by nothing run from `main`. Programs without `main` aren't
+warned about. An overload is named along with its type, telling it apart
+from the other defs of its name.
+
+    def unused(- Int)
+    | = 1
+
+    def main(- Int)
+    | = 2"#,
+        "W0005" => r#"A constructor is neither used nor matched by anything run from `main`.
+
+    data Answer
+    | Yes
+    | No
+
+    def main(- Answer)
+    | = Yes"#,
+        "W0006" => r#"A branch binds a local it doesn't use, which `_` would match the same.
+
+    def main(Int - Int)
+    | n = 1"#,
+        "T0001" => r#"The stack after an expression of the def traced with `--trace-types`, the
+part of it the expression can see. A value whose type isn't known is `_`.
+
+    def main(- Int)
+    | = 1 2 +"#,
+        _ => return None,
+    };
+    Some(explanation)
+}
@@ -0,0 +1,118 @@
+//! Diagnostics as JSON objects, printed one per line so that editors and
+//! CI can parse them:
+//!
+//! ```
+//! use sl::{diagnostics::json::to_json, engine::Engine, source_map::SourceMap};
+//!
+//! let mut source_map = SourceMap::new();
+//! let file = source_map.add("main.sl", "def main(- Int)\n| = 1 hello\n");
+//! let diagnostics = Engine::new().check(source_map.source(file)).err().unwrap();
+//!
+//! assert_eq!(
+//!     to_json(&diagnostics[0], &source_map, file),
+//!     r#"{"code":"E0001","severity":"error","message":"cannot find `hello` in `main`","#.to_owned()
+//!         + r#""span":{"file":"main.sl","start":22,"end":27,"line":2,"column":7,"end_line":2,"end_column":12},"#
+//!         + r#""notes":["not a local or a global word"],"suggestions":[]}"#,
+//! );
+//! ```
+
+use std::fmt::Write;
+
+use super::{
+    render::{annotations, previous_definition},
+    Diagnostic, DiagnosticKind, DiagnosticSink,
+};
+use crate::{
+    source_map::{FileId, Location, SourceMap, Span},
+    type_checker::TypeCheckError,
+};
+
+/// `diagnostic`, reported for `file`, as a JSON object. Lines and columns
+/// are 1-based, the column counted in characters, and `start` and `end`
+/// are byte offsets. The label of the span comes first in `notes`. A
+/// redefinition error has the span of the previous definition as
+/// `previous_span`, `null` if it is built in. Each of `suggestions`
+/// replaces the source in its `span` with its `replacement`.
+pub fn to_json(diagnostic: &Diagnostic, source_map: &SourceMap, file: FileId) -> String {
+    let (label, notes) = annotations(diagnostic);
+    let notes: Vec<_> = label.into_iter().chain(notes).map(|note| string(&note)).collect();
+
+    let mut json = String::new();
+    write!(
+        json,
+        r#"{{"code":{},"severity":{},"message":{},"#,
+        string(diagnostic.code),
+        string(diagnostic.level()),
+        string(&diagnostic.message),
+    )
+    .unwrap();
+    write!(json, r#""span":{},"#, span(diagnostic.span, source_map, file)).unwrap();
+    if let DiagnosticKind::TypeCheck(TypeCheckError::TypeAlreadyDefined { .. } | TypeCheckError::SymbolAlreadyDefined { .. }) =
+        &diagnostic.kind
+    {
+        let previous = previous_definition(diagnostic).map(|previous| span(previous, source_map, file));
+        write!(json, r#""previous_span":{},"#, previous.as_deref().unwrap_or("null")).unwrap();
+    }
+    write!(json, r#""notes":[{}],"#, notes.join(",")).unwrap();
+    let suggestions: Vec<_> = diagnostic
+        .suggestions
+        .iter()
+        .map(|suggestion| {
+            format!(
+                r#"{{"message":{},"span":{},"replacement":{}}}"#,
+                string(&suggestion.message),
+                span(suggestion.span, source_map, file),
+                string(&suggestion.replacement),
+            )
+        })
+        .collect();
+    write!(json, r#""suggestions":[{}]}}"#, suggestions.join(",")).unwrap();
+    json
+}
+
+/// `span` of `file` as a JSON object.
+fn span(span: Span, source_map: &SourceMap, file: FileId) -> String {
+    let Location { line, column } = source_map.location(file, span.start);
+    let Location { line: end_line, column: end_column } = source_map.location(file, span.end);
+    format!(
+        r#"{{"file":{},"start":{},"end":{},"line":{line},"column":{column},"end_line":{end_line},"end_column":{end_column}}}"#,
+        string(source_map.name(file)),
+        span.start,
+        span.end,
+    )
+}
+
+/// `text` as a JSON string literal.
+pub(super) fn string(text: &str) -> String {
+    let mut literal = String::from('"');
+    for character in text.chars() {
+        match character {
+            '"' => literal.push_str("\\\""),
+            '\\' => literal.push_str("\\\\"),
+            '\n' => literal.push_str("\\n"),
+            '\t' => literal.push_str("\\t"),
+            character if character.is_control() => write!(literal, "\\u{:04x}", character as u32).unwrap(),
+            character => literal.push(character),
+        }
+    }
+    literal.push('"');
+    literal
+}
+
+/// Prints every reported diagnostic to stdout as a line of JSON.
+pub struct JsonSink<'map> {
+    source_map: &'map SourceMap,
+    file: FileId,
+}
+
+impl<'map> JsonSink<'map> {
+    pub fn new(source_map: &'map SourceMap, file: FileId) -> Self {
+        Self { source_map, file }
+    }
+}
+
+impl DiagnosticSink for JsonSink<'_> {
+    fn report(&mut self, diagnostic: Diagnostic) {
+        println!("{}", to_json(&diagnostic, self.source_map, self.file));
+    }
+}
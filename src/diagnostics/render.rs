@@ -0,0 +1,175 @@
+//! Rendering diagnostics with the source they are about, the way a
+//! terminal shows them:
+//!
+//! ```
+//! use sl::{diagnostics::render::render, engine::Engine, source_map::SourceMap};
+//!
+//! let mut source_map = SourceMap::new();
+//! let file = source_map.add("main.sl", "def main(- Int)\n| = 1 hello\n");
+//! let diagnostics = Engine::new().check(source_map.source(file)).err().unwrap();
+//!
+//! assert_eq!(
+//!     render(&diagnostics[0], &source_map, file),
+//!     "error[E0001]: cannot find `hello` in `main`\n \
+//!       --> main.sl:2:7\n  |\n2 | | = 1 hello\n  |       ^^^^^ not a local or a global word",
+//! );
+//! ```
+
+use std::fmt::Write;
+
+use super::{catalog::Catalog, Diagnostic, DiagnosticKind, DiagnosticSink};
+use crate::{
+    source_map::{FileId, SourceMap, Span},
+    type_checker::{Type, TypeCheckError},
+};
+
+/// Renders `diagnostic`, reported for `file`, as its code and message
+/// followed by the first line of its span with the span underlined and
+/// labeled, that of the previous definition for a redefinition, and any
+/// notes and suggestions under it.
+pub fn render(diagnostic: &Diagnostic, source_map: &SourceMap, file: FileId) -> String {
+    let (label, notes) = annotations(diagnostic);
+    let catalog = diagnostic.catalog();
+
+    let span = diagnostic.span;
+    let mut rendered = format!("{diagnostic}\n{}", source_map.snippet(file, span));
+    if let Some(label) = label {
+        write!(rendered, " {label}").unwrap();
+    }
+    if let Some(previous) = previous_definition(diagnostic) {
+        let label = catalog.text("label.previous-definition", &[]);
+        write!(rendered, "\n{} {label}", source_map.snippet(file, previous)).unwrap();
+    }
+    let gutter = " ".repeat(source_map.location(file, span.start).line.to_string().len());
+    if !notes.is_empty() || !diagnostic.suggestions.is_empty() {
+        write!(rendered, "\n{gutter} |").unwrap();
+    }
+    for note in notes {
+        // The lines of a note after the first are indented past `= note:`.
+        let note = note.replace('\n', &format!("\n{gutter}         "));
+        write!(rendered, "\n{gutter} = note: {note}").unwrap();
+    }
+    for suggestion in &diagnostic.suggestions {
+        write!(rendered, "\n{gutter} = help: {}", suggestion.message).unwrap();
+    }
+    rendered
+}
+
+/// The label of the underlined span and the notes under it, the stacks
+/// involved for the errors about them. Notes can span several lines.
+pub(super) fn annotations(diagnostic: &Diagnostic) -> (Option<String>, Vec<String>) {
+    let catalog = diagnostic.catalog();
+    let stack = |key: &str, types: &[Type]| catalog.text(key, &[("stack", stack_types(types, catalog))]);
+    let error = match &diagnostic.kind {
+        DiagnosticKind::TypeCheck(error) => error,
+        DiagnosticKind::TypeTrace(trace) => return (Some(stack("label.hole", &trace.stack)), vec![]),
+        _ => return (None, vec![]),
+    };
+    match error {
+        TypeCheckError::TypeMismatch { inputs, stack: actual, differs, .. } => (
+            Some(stack("label.expected-stack", inputs)),
+            vec![stack_diff(inputs, actual, *differs, catalog)],
+        ),
+        TypeCheckError::StackUnderflow { inputs, stack: actual, .. } => {
+            (Some(stack("label.takes", inputs)), vec![stack("label.actual-stack", actual)])
+        }
+        TypeCheckError::OutputMismatch { outputs, stack: actual, differs, .. } => (
+            Some(stack("label.actual-stack", actual)),
+            vec![stack_diff(outputs, actual, *differs, catalog)],
+        ),
+        TypeCheckError::NoMatchingOverload { stack: actual, .. } => (Some(stack("label.actual-stack", actual)), vec![]),
+        TypeCheckError::InvalidTupleElement { stack: actual, .. } => (Some(stack("label.leaves", actual)), vec![]),
+        TypeCheckError::Hole { before, after, .. } => {
+            (Some(stack("label.hole", before)), vec![stack("note.hole", after)])
+        }
+        TypeCheckError::UnboundSymbol { .. } => (Some(catalog.text("label.unbound", &[])), vec![]),
+        TypeCheckError::PatternArity { expected, .. } => {
+            (Some(catalog.text("label.pattern-arity", &[("expected", expected.to_string())])), vec![])
+        }
+        TypeCheckError::TooManyPatterns { def, inputs, .. } => {
+            let arguments = [("def", def.to_string()), ("inputs", inputs.to_string())];
+            (Some(catalog.text("label.extra-patterns", &arguments)), vec![])
+        }
+        _ => (None, vec![]),
+    }
+}
+
+/// Where what a redefinition error is about was first defined, unless it
+/// is built in.
+pub(super) fn previous_definition(diagnostic: &Diagnostic) -> Option<Span> {
+    match &diagnostic.kind {
+        DiagnosticKind::TypeCheck(
+            TypeCheckError::TypeAlreadyDefined { previous_span, .. }
+            | TypeCheckError::SymbolAlreadyDefined { previous_span, .. },
+        ) => *previous_span,
+        _ => None,
+    }
+}
+
+/// The `expected` and `actual` stacks one above the other, aligned at the
+/// top so that the values as deep are in the same column, with the one
+/// `differs` below the top underlined.
+fn stack_diff(expected: &[Type], actual: &[Type], differs: Option<usize>, catalog: &Catalog) -> String {
+    let depth = expected.len().max(actual.len());
+    // From the bottom of the deeper one, empty where the other one ends.
+    let column = |types: &[Type]| -> Vec<String> {
+        let missing = depth - types.len();
+        (0..missing).map(|_| String::new()).chain(types.iter().map(Type::to_string)).collect()
+    };
+    let (expected, actual) = (column(expected), column(actual));
+    let widths: Vec<_> = expected
+        .iter()
+        .zip(&actual)
+        .map(|(expected, actual)| expected.chars().count().max(actual.chars().count()))
+        .collect();
+    let line = |cells: &[String]| -> String {
+        let cells: Vec<_> = cells.iter().zip(&widths).map(|(cell, width)| format!("{cell:<width$}")).collect();
+        cells.join(" ").trim_end().to_string()
+    };
+
+    // The names of the lines are right aligned, so that the stacks start
+    // in the same column.
+    let (expected_name, actual_name) = (catalog.text("diff.expected", &[]), catalog.text("diff.actual", &[]));
+    let name_width = expected_name.chars().count().max(actual_name.chars().count());
+    let mut diff = format!(
+        "{expected_name:>name_width$}: {}\n{actual_name:>name_width$}: {}",
+        line(&expected),
+        line(&actual)
+    );
+    if let Some(differs) = differs.filter(|differs| *differs < depth) {
+        let index = depth - 1 - differs;
+        let offset: usize = widths[..index].iter().map(|width| width + 1).sum();
+        let indent = " ".repeat(name_width + 2 + offset);
+        write!(diff, "\n{indent}{}", "^".repeat(widths[index].max(1))).unwrap();
+    }
+    diff
+}
+
+/// The types of a stack as they would be written in a signature, bottom
+/// first.
+fn stack_types(types: &[Type], catalog: &Catalog) -> String {
+    if types.is_empty() {
+        return catalog.text("stack.nothing", &[]);
+    }
+    let types: Vec<_> = types.iter().map(Type::to_string).collect();
+    format!("`{}`", types.join(" "))
+}
+
+/// Prints every reported diagnostic to stderr, rendered with the source of
+/// the file it was reported for.
+pub struct RenderingSink<'map> {
+    source_map: &'map SourceMap,
+    file: FileId,
+}
+
+impl<'map> RenderingSink<'map> {
+    pub fn new(source_map: &'map SourceMap, file: FileId) -> Self {
+        Self { source_map, file }
+    }
+}
+
+impl DiagnosticSink for RenderingSink<'_> {
+    fn report(&mut self, diagnostic: Diagnostic) {
+        eprintln!("{}\n", render(&diagnostic, self.source_map, self.file));
+    }
+}
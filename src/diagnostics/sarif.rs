@@ -0,0 +1,107 @@
+//! Diagnostics as a SARIF 2.1.0 log, which code scanning tools take
+//! without a converter:
+//!
+//! ```
+//! use sl::{diagnostics::sarif::to_sarif, engine::Engine, source_map::SourceMap};
+//!
+//! let mut source_map = SourceMap::new();
+//! let file = source_map.add("main.sl", "def main(- Int)\n| = 1 hello\n");
+//! let diagnostics = Engine::new().check(source_map.source(file)).err().unwrap();
+//!
+//! let log = to_sarif(&diagnostics, &source_map, file);
+//! assert!(log.contains(r#""ruleId":"E0001","level":"error""#));
+//! assert!(log.contains(r#""region":{"startLine":2,"startColumn":7,"endLine":2,"endColumn":12}"#));
+//! assert!(log.contains(r#""columnKind":"unicodeCodePoints""#));
+//! ```
+
+use std::fmt::Write;
+
+use super::{explain::explain, json::string, render::previous_definition, Diagnostic, Suggestion};
+use crate::source_map::{FileId, Location, SourceMap, Span};
+
+/// A log of one run of sl reporting `diagnostics` for `file`, with a rule
+/// for every code among them described by the first paragraph of its
+/// explanation. Columns are counted in characters, which the run says.
+pub fn to_sarif(diagnostics: &[Diagnostic], source_map: &SourceMap, file: FileId) -> String {
+    let mut codes: Vec<_> = diagnostics.iter().map(|diagnostic| diagnostic.code).collect();
+    codes.sort_unstable();
+    codes.dedup();
+    let rules: Vec<_> = codes
+        .into_iter()
+        .map(|code| {
+            let description = explain(code).unwrap_or_default();
+            let summary = description.split("\n\n").next().unwrap().replace('\n', " ");
+            format!(r#"{{"id":{},"fullDescription":{{"text":{}}}}}"#, string(code), string(&summary))
+        })
+        .collect();
+    let results: Vec<_> = diagnostics.iter().map(|diagnostic| result(diagnostic, source_map, file)).collect();
+
+    let mut log = String::new();
+    write!(
+        log,
+        r#"{{"$schema":"https://json.schemastore.org/sarif-2.1.0.json","version":"2.1.0","runs":[{{"#,
+    )
+    .unwrap();
+    write!(
+        log,
+        r#""tool":{{"driver":{{"name":"sl","version":{},"rules":[{}]}}}},"#,
+        string(env!("CARGO_PKG_VERSION")),
+        rules.join(","),
+    )
+    .unwrap();
+    write!(log, r#""columnKind":"unicodeCodePoints","results":[{}]}}]}}"#, results.join(",")).unwrap();
+    log
+}
+
+fn result(diagnostic: &Diagnostic, source_map: &SourceMap, file: FileId) -> String {
+    let mut result = format!(
+        r#"{{"ruleId":{},"level":{},"message":{{"text":{}}},"locations":[{}]"#,
+        string(diagnostic.code),
+        string(diagnostic.level()),
+        string(&diagnostic.message),
+        location(diagnostic.span, source_map, file, None),
+    );
+    if !diagnostic.suggestions.is_empty() {
+        let fixes: Vec<_> = diagnostic.suggestions.iter().map(|suggestion| fix(suggestion, source_map, file)).collect();
+        write!(result, r#","fixes":[{}]"#, fixes.join(",")).unwrap();
+    }
+    if let Some(previous) = previous_definition(diagnostic) {
+        write!(
+            result,
+            r#","relatedLocations":[{}]"#,
+            location(previous, source_map, file, Some("previously defined here")),
+        )
+        .unwrap();
+    }
+    result.push('}');
+    result
+}
+
+/// `suggestion` as a fix, the source it replaces given in bytes.
+fn fix(suggestion: &Suggestion, source_map: &SourceMap, file: FileId) -> String {
+    let Suggestion { message, span, replacement } = suggestion;
+    format!(
+        r#"{{"description":{{"text":{}}},"artifactChanges":[{{"artifactLocation":{{"uri":{}}},"replacements":[{{"deletedRegion":{{"byteOffset":{},"byteLength":{}}},"insertedContent":{{"text":{}}}}}]}}]}}"#,
+        string(message),
+        string(source_map.name(file)),
+        span.start,
+        span.end - span.start,
+        string(replacement),
+    )
+}
+
+/// `span` of `file` as a location, lines and columns 1-based and columns
+/// counted in characters.
+fn location(span: Span, source_map: &SourceMap, file: FileId, message: Option<&str>) -> String {
+    let Location { line, column } = source_map.location(file, span.start);
+    let Location { line: end_line, column: end_column } = source_map.location(file, span.end);
+    let mut location = format!(
+        r#"{{"physicalLocation":{{"artifactLocation":{{"uri":{}}},"region":{{"startLine":{line},"startColumn":{column},"endLine":{end_line},"endColumn":{end_column}}}}}"#,
+        string(source_map.name(file)),
+    );
+    if let Some(message) = message {
+        write!(location, r#","message":{{"text":{}}}"#, string(message)).unwrap();
+    }
+    location.push('}');
+    location
+}
@@ -0,0 +1,43 @@
+//! Diagnostics on one line each, prefixed with where they are, for grep
+//! and editors matching on `file:line:column`:
+//!
+//! ```
+//! use sl::{diagnostics::short::to_short, engine::Engine, source_map::SourceMap};
+//!
+//! let mut source_map = SourceMap::new();
+//! let file = source_map.add("main.sl", "def main(- Int)\n| = 1 hello\n");
+//! let diagnostics = Engine::new().check(source_map.source(file)).err().unwrap();
+//!
+//! assert_eq!(
+//!     to_short(&diagnostics[0], &source_map, file),
+//!     "main.sl:2:7: error[E0001]: cannot find `hello` in `main`",
+//! );
+//! ```
+
+use super::{Diagnostic, DiagnosticSink};
+use crate::source_map::{FileId, Location, SourceMap};
+
+/// `diagnostic`, reported for `file`, as the location of the start of its
+/// span followed by its code and message. Lines and columns are 1-based.
+pub fn to_short(diagnostic: &Diagnostic, source_map: &SourceMap, file: FileId) -> String {
+    let Location { line, column } = source_map.location(file, diagnostic.span.start);
+    format!("{}:{line}:{column}: {diagnostic}", source_map.name(file))
+}
+
+/// Prints every reported diagnostic to stderr on a line of its own.
+pub struct ShortSink<'map> {
+    source_map: &'map SourceMap,
+    file: FileId,
+}
+
+impl<'map> ShortSink<'map> {
+    pub fn new(source_map: &'map SourceMap, file: FileId) -> Self {
+        Self { source_map, file }
+    }
+}
+
+impl DiagnosticSink for ShortSink<'_> {
+    fn report(&mut self, diagnostic: Diagnostic) {
+        eprintln!("{}", to_short(&diagnostic, self.source_map, self.file));
+    }
+}
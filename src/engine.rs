@@ -0,0 +1,203 @@
+//! Checking and running programs with host provided words.
+//!
+//! ```
+//! use std::sync::Arc;
+//!
+//! use sl::{
+//!     engine::{Builtin, Engine, WordPack},
+//!     evaluator::Value,
+//!     symbol::Symbol,
+//!     type_checker::Type,
+//! };
+//!
+//! struct Nats;
+//!
+//! impl WordPack for Nats {
+//!     fn builtins(&self) -> Vec<Builtin> {
+//!         let nat = Type::basic(Symbol::intern("Nat"));
+//!         vec![Builtin {
+//!             name: "twice",
+//!             ty: Type::Function {
+//!                 inputs: Arc::new([nat.clone()]),
+//!                 outputs: Arc::new([nat.clone(), nat]),
+//!                 rows: None,
+//!                 io: false,
+//!             },
+//!             run: Arc::new(|stack: &mut Vec<Value>| {
+//!                 stack.push(stack.last().unwrap().clone());
+//!                 Ok(())
+//!             }),
+//!             doc: "Duplicates a Nat.",
+//!         }]
+//!     }
+//! }
+//!
+//! let engine = Engine::new().word_pack(&Nats);
+//! let program = engine.check("
+//! data Nat
+//! | Zero
+//!
+//! def main(- Nat Nat)
+//! | = Zero twice
+//! ").unwrap();
+//!
+//! assert_eq!(engine.doc("twice"), Some("Duplicates a Nat."));
+//! engine.run(&program).unwrap();
+//! ```
+
+use std::sync::Arc;
+
+use crate::{
+    cfg::Cfg,
+    diagnostics::{catalog::Catalog, Diagnostic, DiagnosticSink, Diagnostics, Levels},
+    evaluator::{BuiltinFn, Evaluator, RuntimeResult},
+    logging::{LogLevel, LogSink, Logging},
+    parser::Parser,
+    prelude::Prelude,
+    symbol::Symbol,
+    tokens::Tokens,
+    type_checker::{CheckedProgram, Type, TypeChecker},
+};
+
+/// A word implemented by the host: what the checker sees of it, what the
+/// evaluator runs for it and what it is documented as.
+pub struct Builtin {
+    pub name: &'static str,
+    pub ty: Type,
+    pub run: BuiltinFn,
+    pub doc: &'static str,
+}
+
+/// A group of builtins registered together, typically provided by a crate
+/// extending the language.
+pub trait WordPack {
+    fn builtins(&self) -> Vec<Builtin>;
+}
+
+/// Owns the builtins available to programs, registering each of them with
+/// both the type checker and the evaluator so the two can't disagree.
+pub struct Engine {
+    builtins: Vec<Builtin>,
+    cfg: Cfg,
+    levels: Levels,
+    catalog: Arc<Catalog>,
+    /// The def traced while checking, if any.
+    traced: Option<String>,
+}
+
+impl Engine {
+    /// An engine providing the words of the [`Prelude`].
+    pub fn new() -> Self {
+        Self::bare().word_pack(&Prelude)
+    }
+
+    /// An engine without any builtins.
+    pub fn bare() -> Self {
+        Self {
+            builtins: vec![],
+            cfg: Cfg::default(),
+            levels: Levels::default(),
+            catalog: Catalog::english(),
+            traced: None,
+        }
+    }
+
+    pub fn word_pack(mut self, pack: &dyn WordPack) -> Self {
+        self.builtins.extend(pack.builtins());
+        self
+    }
+
+    /// Provides the words of [`Logging`], sending what programs log at
+    /// `level` or above to `sink`.
+    pub fn logging(self, level: LogLevel, sink: impl LogSink + 'static) -> Self {
+        self.word_pack(&Logging::new(level, sink))
+    }
+
+    /// Sets what `#[cfg(...)]` attributes in checked sources are resolved against.
+    pub fn cfg(mut self, cfg: Cfg) -> Self {
+        self.cfg = cfg;
+        self
+    }
+
+    /// Sets which warnings are reported, and which of them as errors.
+    pub fn levels(mut self, levels: Levels) -> Self {
+        self.levels = levels;
+        self
+    }
+
+    /// Sets the text diagnostics are written with.
+    pub fn catalog(mut self, catalog: Arc<Catalog>) -> Self {
+        self.catalog = catalog;
+        self
+    }
+
+    /// Reports the stack after every expression of the def `name` while
+    /// checking, as notes.
+    pub fn trace(mut self, name: &str) -> Self {
+        self.traced = Some(name.to_string());
+        self
+    }
+
+    pub fn doc(&self, name: &str) -> Option<&'static str> {
+        self.builtins
+            .iter()
+            .find(|builtin| builtin.name == name)
+            .map(|builtin| builtin.doc)
+    }
+
+    fn type_checker(&self) -> TypeChecker {
+        let builder = self
+            .builtins
+            .iter()
+            .fold(TypeChecker::builder(), |builder, builtin| builder.word(builtin.name, builtin.ty.clone()));
+        match &self.traced {
+            Some(name) => builder.trace(name).build(),
+            None => builder.build(),
+        }
+    }
+
+    /// Parses and type checks `source`, returning every diagnostic reported
+    /// along the way if it doesn't check.
+    pub fn check(&self, source: &str) -> Result<CheckedProgram, Diagnostics> {
+        let mut diagnostics = vec![];
+        self.check_reporting(source, &mut diagnostics).ok_or(Diagnostics(diagnostics))
+    }
+
+    /// Parses and type checks `source`, reporting errors and warnings to
+    /// `sink` as they come, written with the catalog of the engine. A
+    /// warning denied by the levels of the engine fails the check like an
+    /// error.
+    pub fn check_reporting(&self, source: &str, sink: &mut dyn DiagnosticSink) -> Option<CheckedProgram> {
+        let mut denied = false;
+        let mut leveled = |diagnostic: Diagnostic| {
+            if let Some(mut diagnostic) = self.levels.apply(diagnostic) {
+                denied |= diagnostic.is_error();
+                if !Arc::ptr_eq(&self.catalog, &Catalog::english()) {
+                    diagnostic.localize(self.catalog.clone());
+                }
+                sink.report(diagnostic);
+            }
+        };
+        let program = Parser::new(Tokens::new(source))
+            .with_cfg(self.cfg.clone())
+            .top_levels(&mut leveled)
+            .and_then(|top_levels| self.type_checker().type_check(top_levels, &mut leveled));
+        program.filter(|_| !denied)
+    }
+
+    /// Runs `program` from its `main` def, stopping at the first builtin
+    /// that fails.
+    pub fn run(&self, program: &CheckedProgram) -> RuntimeResult<()> {
+        let mut evaluator = Evaluator::new();
+        for builtin in &self.builtins {
+            evaluator.register(Symbol::intern(builtin.name), builtin.run.clone());
+        }
+        evaluator.eval_from_main(program)
+    }
+}
+
+impl Default for Engine {
+    fn default() -> Self {
+        Self::new()
+    }
+}
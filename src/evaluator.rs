@@ -1,10 +1,21 @@
-use std::collections::HashMap;
+//! Tree-walking interpreter running a checked program from its `main` def.
 
-use crate::parser::{Branch, Constructor, Expr, Pattern, TopLevel};
+use std::{collections::HashMap, error::Error, fmt, sync::Arc};
+
+use crate::{
+    parser::{Branch, Constructor, Expr, ExprKind, Pattern, PatternKind, TopLevel, BOOL_CONSTRUCTORS},
+    symbol::Symbol,
+    type_checker::{CheckedProgram, Derivable, Type},
+};
 
 pub struct Evaluator {
-    ctx: HashMap<String, Value>,
-    locals: Vec<(String, Value)>,
+    ctx: HashMap<Symbol, Value>,
+    /// The data type of every constructor, which is what methods dispatch on.
+    constructor_types: HashMap<Symbol, Symbol>,
+    /// The method of the `Eq` trait, comparing the values a name is bound
+    /// to more than once in a branch's patterns.
+    eq: Option<Symbol>,
+    locals: Vec<(Symbol, Value)>,
 
     stack: Vec<Value>,
 }
@@ -12,27 +23,49 @@ pub struct Evaluator {
 impl Evaluator {
     pub fn new() -> Self {
         Self {
-            ctx: HashMap::new(),
+            ctx: BOOL_CONSTRUCTORS
+                .iter()
+                .map(|name| (Symbol::intern(name), Value::Constructor(0)))
+                .collect(),
+            constructor_types: HashMap::new(),
+            eq: None,
             locals: vec![],
             stack: vec![],
         }
     }
 
-    fn resolve_word(&self, word: &str) -> Value {
-        match self.locals.iter().rev().find(|(name, _)| name == word) {
+    /// Makes `name` run `builtin` on the stack when evaluated.
+    pub fn register(&mut self, name: Symbol, builtin: BuiltinFn) {
+        self.ctx.insert(name, Value::Builtin(builtin));
+    }
+
+    fn resolve_word(&self, word: Symbol) -> Value {
+        match self.locals.iter().rev().find(|(name, _)| *name == word) {
             Some((_, ty)) => ty.clone(),
-            None => self.ctx.get(word).unwrap().clone(),
+            None => self.ctx.get(&word).unwrap().clone(),
+        }
+    }
+
+    /// The name of the type of `value`, for the ones impls can be given for.
+    fn type_name(&self, value: &Value) -> Symbol {
+        match value {
+            Value::Basic { constructor, .. } => self.constructor_types[constructor],
+            Value::Int(_) => Symbol::intern("Int"),
+            Value::Float(_) => Symbol::intern("Float"),
+            Value::String(_) => Symbol::intern("String"),
+            Value::Char(_) => Symbol::intern("Char"),
+            _ => unreachable!(),
         }
     }
 
     fn fits_pattern(&self, value: &Value, pattern: &Pattern) -> bool {
-        match (value, pattern) {
+        match (value, &pattern.kind) {
             (
                 Value::Basic {
                     constructor,
                     values,
                 },
-                Pattern::Constructor { name, arguments },
+                PatternKind::Constructor { name, arguments },
             ) => {
                 if constructor != name {
                     return false;
@@ -48,77 +81,161 @@ impl Evaluator {
                     .zip(arguments)
                     .all(|(value, argument)| self.fits_pattern(value, argument))
             }
-            (_, Pattern::All(_)) => true,
-            (Value::Function(_), Pattern::Constructor { .. }) => false,
-            (Value::Constructor(_), Pattern::Constructor { .. }) => false,
-            (Value::Quotation { .. }, Pattern::Constructor { .. }) => false,
+            (Value::Tuple(values), PatternKind::Tuple(elements)) => values
+                .iter()
+                .zip(elements)
+                .all(|(value, element)| self.fits_pattern(value, element)),
+            (Value::Char(ch), PatternKind::Char(expected)) => ch == expected,
+            (_, PatternKind::All(_)) => true,
+            (_, PatternKind::Tuple(_) | PatternKind::Char(_)) => false,
+            (Value::Tuple(_), PatternKind::Constructor { .. }) => false,
+            (Value::Function(_), PatternKind::Constructor { .. }) => false,
+            (Value::Builtin(_), PatternKind::Constructor { .. }) => false,
+            (Value::Int(_), PatternKind::Constructor { .. }) => false,
+            (Value::Float(_), PatternKind::Constructor { .. }) => false,
+            (Value::String(_), PatternKind::Constructor { .. }) => false,
+            (Value::Char(_), PatternKind::Constructor { .. }) => false,
+            (Value::Constructor(_), PatternKind::Constructor { .. }) => false,
+            (Value::Quotation { .. }, PatternKind::Constructor { .. }) => false,
+            (Value::Method { .. }, PatternKind::Constructor { .. }) => false,
+            (Value::Derived { .. }, PatternKind::Constructor { .. }) => false,
         }
     }
 
+    /// Whether the values bound to a name more than once by matching the
+    /// top of the stack against the patterns of `branch` are equal.
+    fn bindings_equal(&mut self, branch: &Branch) -> RuntimeResult<bool> {
+        if !branch.compares_bindings() {
+            return Ok(true);
+        }
+        let mut bindings = vec![];
+        for (value, pattern) in self.stack[self.stack.len() - branch.patterns.len()..].iter().zip(&branch.patterns) {
+            pattern_bindings(value, pattern, &mut bindings);
+        }
+        for (index, (name, value)) in bindings.iter().enumerate() {
+            if let Some((_, first)) = bindings[..index].iter().find(|(earlier, _)| earlier == name) {
+                if !self.run_eq(self.eq.unwrap(), first.clone(), value.clone())? {
+                    return Ok(false);
+                }
+            }
+        }
+        Ok(true)
+    }
+
     fn define_pattern_locals(&mut self, value: Value, pattern: Pattern) {
         // Maybe quotation patterns
-        match (value, pattern) {
-            (value, Pattern::All(name)) => self.locals.push((name, value)),
+        match (value, pattern.kind) {
+            (value, PatternKind::All(name)) => self.locals.push((name, value)),
             (
                 Value::Basic {
                     constructor: _,
                     values,
                 },
-                Pattern::Constructor { name: _, arguments },
+                PatternKind::Constructor { name: _, arguments },
             ) => {
                 for (value, argument) in values.into_iter().zip(arguments) {
                     self.define_pattern_locals(value, argument);
                 }
             }
-            (Value::Function(_), Pattern::Constructor { .. }) => (),
-            (Value::Constructor(_), Pattern::Constructor { .. }) => (),
-            (Value::Quotation { .. }, Pattern::Constructor { .. }) => (),
+            (Value::Tuple(values), PatternKind::Tuple(elements)) => {
+                for (value, element) in values.into_iter().zip(elements) {
+                    self.define_pattern_locals(value, element);
+                }
+            }
+            (_, PatternKind::Tuple(_) | PatternKind::Char(_)) => (),
+            (Value::Tuple(_), PatternKind::Constructor { .. }) => (),
+            (Value::Function(_), PatternKind::Constructor { .. }) => (),
+            (Value::Builtin(_), PatternKind::Constructor { .. }) => (),
+            (Value::Int(_), PatternKind::Constructor { .. }) => (),
+            (Value::Float(_), PatternKind::Constructor { .. }) => (),
+            (Value::String(_), PatternKind::Constructor { .. }) => (),
+            (Value::Char(_), PatternKind::Constructor { .. }) => (),
+            (Value::Constructor(_), PatternKind::Constructor { .. }) => (),
+            (Value::Quotation { .. }, PatternKind::Constructor { .. }) => (),
+            (Value::Method { .. }, PatternKind::Constructor { .. }) => (),
+            (Value::Derived { .. }, PatternKind::Constructor { .. }) => (),
         }
     }
 
-    fn eval_expr(&mut self, expr: &Expr) {
-        match expr {
-            Expr::Word(word) => match self.resolve_word(word) {
-                Value::Constructor(arity) => {
-                    let values = self.stack.split_off(self.stack.len() - arity);
-                    self.stack.push(Value::Basic {
-                        constructor: word.clone(),
-                        values,
-                    })
-                }
-                Value::Function(branches) => {
-                    for Branch { patterns, body } in branches {
-                        if self.stack[self.stack.len() - patterns.len()..]
-                            .iter()
-                            .zip(&patterns)
-                            .all(|(value, pattern)| self.fits_pattern(value, pattern))
-                            {
-                            let locals_len = self.locals.len();
-                            let values = self.stack.split_off(self.stack.len() - patterns.len());
-                            for (value, pattern) in values.into_iter().zip(patterns) {
-                                self.define_pattern_locals(value, pattern)
-                            }
-                            for expr in body {
-                                self.eval_expr(&expr);
-                            }
-                            self.locals.truncate(locals_len);
-                            return;
+    fn eval_word(&mut self, word: Symbol) -> RuntimeResult<()> {
+        match self.resolve_word(word) {
+            Value::Constructor(arity) => {
+                let values = self.stack.split_off(self.stack.len() - arity);
+                self.stack.push(Value::Basic {
+                    constructor: word,
+                    values,
+                })
+            }
+            Value::Function(branches) => {
+                for branch in branches {
+                    if self.stack[self.stack.len() - branch.patterns.len()..]
+                        .iter()
+                        .zip(&branch.patterns)
+                        .all(|(value, pattern)| self.fits_pattern(value, pattern))
+                        && self.bindings_equal(&branch)?
+                        {
+                        let Branch { patterns, body, span: _ } = branch;
+                        let locals_len = self.locals.len();
+                        let values = self.stack.split_off(self.stack.len() - patterns.len());
+                        for (value, pattern) in values.into_iter().zip(patterns) {
+                            self.define_pattern_locals(value, pattern)
+                        }
+                        for expr in body {
+                            self.eval_expr(&expr)?;
                         }
+                        self.locals.truncate(locals_len);
+                        return Ok(());
                     }
-                    panic!("Non exhaustive patterns")
-                }
-                literal => {
-                    self.stack.push(literal);
                 }
-            },
-            Expr::Quotation { inputs: _, quotation } => {
+                unreachable!("the checker makes sure that some branch matches")
+            }
+            Value::Builtin(builtin) => builtin(&mut self.stack)?,
+            Value::Method { depth, implementations, .. } => {
+                let type_name = self.type_name(&self.stack[self.stack.len() - depth]);
+                self.eval_word(implementations[&type_name])?;
+            }
+            Value::Derived { derivable: Derivable::Eq, method } => {
+                let (Some(right), Some(left)) = (self.stack.pop(), self.stack.pop()) else {
+                    unreachable!()
+                };
+                let equal = self.derived_eq(method, left, right)?;
+                self.stack.push(Value::bool(equal));
+            }
+            Value::Derived { derivable: Derivable::Show, method } => {
+                let value = self.stack.pop().unwrap();
+                let shown = self.derived_show(method, value)?;
+                self.stack.push(Value::String(shown.into()));
+            }
+            literal => {
+                self.stack.push(literal);
+            }
+        }
+        Ok(())
+    }
+
+    fn eval_expr(&mut self, expr: &Expr) -> RuntimeResult<()> {
+        match &expr.kind {
+            ExprKind::Word(word) => self.eval_word(*word)?,
+            ExprKind::Hole => unreachable!(),
+            ExprKind::IntLit(integer) => self.stack.push(Value::Int(*integer)),
+            ExprKind::FloatLit(float) => self.stack.push(Value::Float(*float)),
+            ExprKind::StringLit(string) => self.stack.push(Value::String(string.as_str().into())),
+            ExprKind::CharLit(ch) => self.stack.push(Value::Char(*ch)),
+            ExprKind::Quotation { inputs: _, quotation } => {
                 let closure = self.locals.clone();
                 self.stack.push(Value::Quotation {
                     quotation: quotation.clone(),
                     closure,
                 })
             },
-            Expr::Unquote => {
+            ExprKind::Tuple(elements) => {
+                for expr in elements.iter().flatten() {
+                    self.eval_expr(expr)?;
+                }
+                let values = self.stack.split_off(self.stack.len() - elements.len());
+                self.stack.push(Value::Tuple(values));
+            }
+            ExprKind::Unquote => {
                 let Some(Value::Quotation { quotation, closure }) = self.stack.pop() else {
                     unreachable!()
                 };
@@ -126,71 +243,254 @@ impl Evaluator {
                 let locals_len = self.locals.len();
                 self.locals.extend(closure);
                 for expr in quotation {
-                    self.eval_expr(&expr);
+                    self.eval_expr(&expr)?;
                 }
                 self.locals.truncate(locals_len);
             },
         }
+        Ok(())
     }
 
-    fn eval_top_levels(&mut self, top_levels: &[TopLevel]) {
+    /// Whether `left` and `right` are built the same way, comparing the
+    /// values they are built from with `method`.
+    fn derived_eq(&mut self, method: Symbol, left: Value, right: Value) -> RuntimeResult<bool> {
+        match (left, right) {
+            (Value::Basic { constructor: left, values: left_values }, Value::Basic { constructor: right, values: right_values }) => {
+                Ok(left == right && self.derived_eq_fields(method, left_values, right_values)?)
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    fn derived_eq_fields(&mut self, method: Symbol, left: Vec<Value>, right: Vec<Value>) -> RuntimeResult<bool> {
+        for (left, right) in left.into_iter().zip(right) {
+            let equal = match (left, right) {
+                (Value::Tuple(left), Value::Tuple(right)) => self.derived_eq_fields(method, left, right)?,
+                (left, right) => self.run_eq(method, left, right)?,
+            };
+            if !equal {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    /// Runs the `Eq` method `method` on `left` and `right`.
+    fn run_eq(&mut self, method: Symbol, left: Value, right: Value) -> RuntimeResult<bool> {
+        self.stack.extend([left, right]);
+        self.eval_word(method)?;
+        let Some(Value::Basic { constructor, .. }) = self.stack.pop() else {
+            unreachable!()
+        };
+        Ok(constructor.as_str() == BOOL_CONSTRUCTORS[1])
+    }
+
+    /// `value` as the constructor building it, followed by the values it is
+    /// built from shown with `method`.
+    fn derived_show(&mut self, method: Symbol, value: Value) -> RuntimeResult<String> {
+        let Value::Basic { constructor, values } = value else {
+            unreachable!()
+        };
+        if values.is_empty() {
+            return Ok(constructor.to_string());
+        }
+        let fields = values
+            .into_iter()
+            .map(|value| self.derived_show_field(method, value))
+            .collect::<RuntimeResult<Vec<_>>>()?;
+        Ok(format!("{constructor}({})", fields.join(" ")))
+    }
+
+    fn derived_show_field(&mut self, method: Symbol, value: Value) -> RuntimeResult<String> {
+        match value {
+            Value::Tuple(values) => {
+                let elements = values
+                    .into_iter()
+                    .map(|value| self.derived_show_field(method, value))
+                    .collect::<RuntimeResult<Vec<_>>>()?;
+                Ok(format!("({})", elements.join(", ")))
+            }
+            value => {
+                self.stack.push(value);
+                self.eval_word(method)?;
+                let Some(Value::String(shown)) = self.stack.pop() else {
+                    unreachable!()
+                };
+                Ok(shown.to_string())
+            }
+        }
+    }
+
+    fn eval_top_levels(&mut self, top_levels: &[TopLevel], implementations: &HashMap<Symbol, HashMap<Symbol, Symbol>>) {
         for top_level in top_levels {
             match top_level {
                 TopLevel::Data {
                     name: _,
+                    parameters: _,
                     constructors,
+                    linear: _,
+                    derives: _,
+                    doc: _,
+                    span: _,
                 } => {
                     for Constructor {
                         name,
                         argument_types,
+                        output: _,
+                        span: _,
                     } in constructors
                     {
                         self.ctx
-                            .insert(name.clone(), Value::Constructor(argument_types.len()));
+                            .insert(*name, Value::Constructor(argument_types.len()));
                     }
                 }
                 TopLevel::Def {
                     name,
-                    inputs: _,
-                    outputs: _,
+                    signature: _,
                     branches,
+                    total: _,
+                    doc: _,
+                    span: _,
                 } => {
                     self.ctx
-                        .insert(name.clone(), Value::Function(branches.clone()));
+                        .insert(*name, Value::Function(branches.clone()));
                 }
+                TopLevel::Trait { name, parameter, methods, span: _ } => {
+                    if name.as_str() == "Eq" {
+                        self.eq = methods.first().map(|method| method.name);
+                    }
+                    for method in methods {
+                        let input = method.dispatch_input(*parameter).unwrap();
+                        self.ctx.insert(method.name, Value::Method {
+                            name: method.name,
+                            depth: method.signature.inputs.len() - input,
+                            implementations: implementations.get(&method.name).cloned().unwrap_or_default(),
+                        });
+                    }
+                }
+                TopLevel::Type { .. } | TopLevel::Impl { .. } => (),
             }
         }
     }
 
-    pub fn eval_from_main(&mut self, top_levels: &[TopLevel]) {
-        self.eval_top_levels(top_levels);
+    /// Runs `main` and prints the stack it leaves, unless running it fails.
+    pub fn eval_from_main(&mut self, program: &CheckedProgram) -> RuntimeResult<()> {
+        for (type_name, constructors) in &program.types {
+            for constructor in constructors {
+                self.constructor_types.insert(*constructor, *type_name);
+            }
+        }
+        self.eval_top_levels(&program.top_levels, &program.implementations);
+        for (word, &(derivable, method)) in &program.derived {
+            self.ctx.insert(*word, Value::Derived { derivable, method });
+        }
 
-        let Some(Value::Function(branches)) = self.ctx.get("main").cloned() else {
-            panic!();
+        let main = Symbol::intern("main");
+        let Some((_, ty)) = program.defs().find(|(name, _)| *name == main) else {
+            return Err(RuntimeError::NoMain);
         };
+        if matches!(ty, Type::Function { inputs, .. } if !inputs.is_empty()) {
+            return Err(RuntimeError::MainTakesInputs);
+        }
 
-        let [Branch { patterns: _, body }] = &branches[..] else {
-            panic!()
-        };
+        self.eval_word(main)?;
+        println!("{:?}", self.stack);
+        Ok(())
+    }
+}
 
-        for expr in body {
-            self.eval_expr(expr);
+/// The values the locals `pattern` binds are bound to when it matches
+/// `value`, in order.
+fn pattern_bindings(value: &Value, pattern: &Pattern, bindings: &mut Vec<(Symbol, Value)>) {
+    match (value, &pattern.kind) {
+        (value, PatternKind::All(name)) => bindings.push((*name, value.clone())),
+        (Value::Basic { values, .. }, PatternKind::Constructor { arguments, .. }) | (Value::Tuple(values), PatternKind::Tuple(arguments)) => {
+            for (value, pattern) in values.iter().zip(arguments) {
+                pattern_bindings(value, pattern, bindings);
+            }
         }
-        println!("{:?}", self.stack);
+        (_, PatternKind::Char(_)) => (),
+        _ => unreachable!(),
     }
 }
 
+impl Default for Evaluator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A word implemented by the host, operating directly on the stack. It can
+/// hold on to what the host gave it, as where to send what it logs.
+pub type BuiltinFn = Arc<dyn Fn(&mut Vec<Value>) -> RuntimeResult<()> + Send + Sync>;
+
+pub type RuntimeResult<T> = Result<T, RuntimeError>;
+
+/// Why running a program that checked stopped before the end of `main`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RuntimeError {
+    /// `/` or `mod` with a zero divisor.
+    DivisionByZero,
+    /// The program has no `main` def to run.
+    NoMain,
+    /// `main` takes inputs, which the empty stack it starts on lacks.
+    MainTakesInputs,
+    /// `hash` was given a quotation, or a value with one in it.
+    Unhashable,
+}
+
+impl fmt::Display for RuntimeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RuntimeError::DivisionByZero => f.write_str("division by zero"),
+            RuntimeError::NoMain => f.write_str("there is no `main` def to run"),
+            RuntimeError::MainTakesInputs => f.write_str("`main` takes inputs, but is run on an empty stack"),
+            RuntimeError::Unhashable => f.write_str("quotations can't be hashed"),
+        }
+    }
+}
+
+impl Error for RuntimeError {}
+
 #[derive(Clone)]
 pub enum Value {
     Basic {
-        constructor: String,
+        constructor: Symbol,
         values: Vec<Value>,
     },
     Function(Vec<Branch>),
+    Builtin(BuiltinFn),
     Constructor(usize),
+    Int(i64),
+    Float(f64),
+    String(Arc<str>),
+    Char(char),
+    Tuple(Vec<Value>),
+    /// The method `name` of a trait, running the word given for the type
+    /// of the value `depth` from the top of the stack.
+    Method {
+        name: Symbol,
+        depth: usize,
+        implementations: HashMap<Symbol, Symbol>,
+    },
+    /// The word of a derived impl, running `method` on the values a value
+    /// is built from.
+    Derived {
+        derivable: Derivable,
+        method: Symbol,
+    },
     Quotation {
         quotation: Vec<Expr>,
-        closure: Vec<(String, Value)>
+        closure: Vec<(Symbol, Value)>
+    }
+}
+
+impl Value {
+    pub fn bool(value: bool) -> Self {
+        Value::Basic {
+            constructor: Symbol::intern(BOOL_CONSTRUCTORS[value as usize]),
+            values: vec![],
+        }
     }
 }
 
@@ -207,8 +507,25 @@ impl std::fmt::Debug for Value {
                     write!(f, "{constructor}{values:?}")
                 }
             },
-            Value::Function(_) => todo!(),
-            Value::Constructor(_) => todo!(),
+            Value::Function(_) => write!(f, "<def>"),
+            Value::Builtin(_) => write!(f, "<builtin>"),
+            Value::Constructor(arity) => write!(f, "<constructor of {arity}>"),
+            Value::Method { name, .. } => write!(f, "<method {name}>"),
+            Value::Derived { derivable, method } => write!(f, "<derived {derivable:?} using {method}>"),
+            Value::Int(integer) => write!(f, "{integer}"),
+            Value::Float(float) => write!(f, "{float:?}"),
+            Value::String(string) => write!(f, "{string:?}"),
+            Value::Char(ch) => write!(f, "{ch:?}"),
+            Value::Tuple(values) => {
+                write!(f, "(")?;
+                for (index, value) in values.iter().enumerate() {
+                    if index > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{value:?}")?;
+                }
+                write!(f, ")")
+            }
             Value::Quotation { quotation, .. } => write!(f, "Quot:{quotation:?}"),
         }
     }
@@ -0,0 +1,207 @@
+//! Words hashing values, for caching, deduplicating and addressing content.
+//!
+//! ```
+//! use sl::{engine::Engine, hashing::Hashing};
+//!
+//! let engine = Engine::new().word_pack(&Hashing);
+//! let program = engine.check("
+//! def main(- Int)
+//! | = (1, \"one\") hash
+//! ").unwrap();
+//! engine.run(&program).unwrap();
+//! ```
+
+use std::sync::Arc;
+
+use crate::{
+    engine::{Builtin, WordPack},
+    evaluator::{RuntimeError, RuntimeResult, Value},
+    symbol::Symbol,
+    type_checker::Type,
+};
+
+/// `hash`, `(a - Int)`, and with the `sha256` feature `sha256`,
+/// `(String - String)`.
+pub struct Hashing;
+
+impl WordPack for Hashing {
+    fn builtins(&self) -> Vec<Builtin> {
+        vec![
+            Builtin {
+                name: "hash",
+                ty: Type::Function {
+                    inputs: Arc::new([Type::Variable(Symbol::intern("a"))]),
+                    outputs: Arc::new([Type::int()]),
+                    rows: None,
+                    io: false,
+                },
+                run: Arc::new(hash),
+                doc: "A hash of a value made of what it is built from, the same for equal values on every run. Fails for quotations.",
+            },
+            #[cfg(feature = "sha256")]
+            Builtin {
+                name: "sha256",
+                ty: Type::Function {
+                    inputs: Arc::new([Type::string()]),
+                    outputs: Arc::new([Type::string()]),
+                    rows: None,
+                    io: false,
+                },
+                run: Arc::new(sha256::digest_word),
+                doc: "The SHA-256 digest of the UTF-8 bytes of a String, in lowercase hexadecimal.",
+            },
+        ]
+    }
+}
+
+fn hash(stack: &mut Vec<Value>) -> RuntimeResult<()> {
+    let value = stack.pop().unwrap();
+    let mut hasher = Fnv::new();
+    hasher.value(&value)?;
+    stack.push(Value::Int(hasher.0 as i64));
+    Ok(())
+}
+
+/// The 64-bit FNV-1a hash, which unlike the hasher of the standard library
+/// is the same on every platform and version.
+struct Fnv(u64);
+
+impl Fnv {
+    fn new() -> Self {
+        Self(0xcbf2_9ce4_8422_2325)
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 = (self.0 ^ u64::from(*byte)).wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+
+    /// Writes a byte telling the kind of value apart, then what it is built
+    /// from. Lengths go before what they count, so that values built from
+    /// the same bytes in different ways don't write the same.
+    fn value(&mut self, value: &Value) -> RuntimeResult<()> {
+        match value {
+            Value::Basic { constructor, values } => {
+                self.write(&[0]);
+                self.bytes(constructor.as_str().as_bytes());
+                self.values(values)?;
+            }
+            Value::Int(integer) => {
+                self.write(&[1]);
+                self.write(&integer.to_le_bytes());
+            }
+            Value::Float(float) => {
+                self.write(&[2]);
+                self.write(&float.to_bits().to_le_bytes());
+            }
+            Value::String(string) => {
+                self.write(&[3]);
+                self.bytes(string.as_bytes());
+            }
+            Value::Char(ch) => {
+                self.write(&[4]);
+                self.write(&u32::from(*ch).to_le_bytes());
+            }
+            Value::Tuple(values) => {
+                self.write(&[5]);
+                self.values(values)?;
+            }
+            Value::Quotation { .. }
+            | Value::Function(_)
+            | Value::Builtin(_)
+            | Value::Constructor(_)
+            | Value::Method { .. }
+            | Value::Derived { .. } => return Err(RuntimeError::Unhashable),
+        }
+        Ok(())
+    }
+
+    fn bytes(&mut self, bytes: &[u8]) {
+        self.write(&(bytes.len() as u64).to_le_bytes());
+        self.write(bytes);
+    }
+
+    fn values(&mut self, values: &[Value]) -> RuntimeResult<()> {
+        self.write(&(values.len() as u64).to_le_bytes());
+        values.iter().try_for_each(|value| self.value(value))
+    }
+}
+
+#[cfg(feature = "sha256")]
+mod sha256 {
+    use crate::evaluator::{RuntimeResult, Value};
+
+    const ROUND_CONSTANTS: [u32; 64] = [
+        0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1, 0x923f82a4, 0xab1c5ed5,
+        0xd807aa98, 0x12835b01, 0x243185be, 0x550c7dc3, 0x72be5d74, 0x80deb1fe, 0x9bdc06a7, 0xc19bf174,
+        0xe49b69c1, 0xefbe4786, 0x0fc19dc6, 0x240ca1cc, 0x2de92c6f, 0x4a7484aa, 0x5cb0a9dc, 0x76f988da,
+        0x983e5152, 0xa831c66d, 0xb00327c8, 0xbf597fc7, 0xc6e00bf3, 0xd5a79147, 0x06ca6351, 0x14292967,
+        0x27b70a85, 0x2e1b2138, 0x4d2c6dfc, 0x53380d13, 0x650a7354, 0x766a0abb, 0x81c2c92e, 0x92722c85,
+        0xa2bfe8a1, 0xa81a664b, 0xc24b8b70, 0xc76c51a3, 0xd192e819, 0xd6990624, 0xf40e3585, 0x106aa070,
+        0x19a4c116, 0x1e376c08, 0x2748774c, 0x34b0bcb5, 0x391c0cb3, 0x4ed8aa4a, 0x5b9cca4f, 0x682e6ff3,
+        0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208, 0x90befffa, 0xa4506ceb, 0xbef9a3f7, 0xc67178f2,
+    ];
+
+    const INITIAL_STATE: [u32; 8] = [
+        0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab, 0x5be0cd19,
+    ];
+
+    pub(super) fn digest_word(stack: &mut Vec<Value>) -> RuntimeResult<()> {
+        let Some(Value::String(string)) = stack.pop() else {
+            unreachable!()
+        };
+        let hex: String = digest(string.as_bytes()).iter().map(|byte| format!("{byte:02x}")).collect();
+        stack.push(Value::String(hex.into()));
+        Ok(())
+    }
+
+    /// The SHA-256 digest of `message`, as in FIPS 180-4.
+    fn digest(message: &[u8]) -> [u8; 32] {
+        // A one bit, zeros up to 8 bytes short of a whole block, and the
+        // length in bits.
+        let mut padded = message.to_vec();
+        padded.push(0x80);
+        while padded.len() % 64 != 56 {
+            padded.push(0);
+        }
+        padded.extend_from_slice(&((message.len() as u64).wrapping_mul(8)).to_be_bytes());
+
+        let mut state = INITIAL_STATE;
+        for block in padded.chunks_exact(64) {
+            let mut schedule = [0u32; 64];
+            for (word, bytes) in schedule.iter_mut().zip(block.chunks_exact(4)) {
+                *word = u32::from_be_bytes(bytes.try_into().unwrap());
+            }
+            for index in 16..64 {
+                let [before_15, before_2] = [schedule[index - 15], schedule[index - 2]];
+                let small_sigma_0 = before_15.rotate_right(7) ^ before_15.rotate_right(18) ^ (before_15 >> 3);
+                let small_sigma_1 = before_2.rotate_right(17) ^ before_2.rotate_right(19) ^ (before_2 >> 10);
+                schedule[index] = schedule[index - 16]
+                    .wrapping_add(small_sigma_0)
+                    .wrapping_add(schedule[index - 7])
+                    .wrapping_add(small_sigma_1);
+            }
+
+            let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut h] = state;
+            for (constant, word) in ROUND_CONSTANTS.iter().zip(schedule) {
+                let sigma_1 = e.rotate_right(6) ^ e.rotate_right(11) ^ e.rotate_right(25);
+                let choice = (e & f) ^ (!e & g);
+                let first = h.wrapping_add(sigma_1).wrapping_add(choice).wrapping_add(*constant).wrapping_add(word);
+                let sigma_0 = a.rotate_right(2) ^ a.rotate_right(13) ^ a.rotate_right(22);
+                let majority = (a & b) ^ (a & c) ^ (b & c);
+                let second = sigma_0.wrapping_add(majority);
+                [h, g, f, e, d, c, b, a] = [g, f, e, d.wrapping_add(first), c, b, a, first.wrapping_add(second)];
+            }
+            for (state, working) in state.iter_mut().zip([a, b, c, d, e, f, g, h]) {
+                *state = state.wrapping_add(working);
+            }
+        }
+
+        let mut digest = [0; 32];
+        for (bytes, word) in digest.chunks_exact_mut(4).zip(state) {
+            bytes.copy_from_slice(&word.to_be_bytes());
+        }
+        digest
+    }
+}
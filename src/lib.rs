@@ -0,0 +1,47 @@
+//! sl is a small, statically typed concatenative language.
+//!
+//! Source text goes through [`tokens::Tokens`], is parsed into top levels by
+//! [`parser::Parser`], checked by [`type_checker::TypeChecker`] and finally
+//! run by [`evaluator::Evaluator`]. [`check_source`] does everything up to
+//! running in one call, [`engine::Engine`] does the same with host provided
+//! words.
+//!
+//! ```
+//! # fn main() -> Result<(), sl::diagnostics::Diagnostics> {
+//! let source = "
+//! data Nat
+//! | Zero
+//! | Succ(Nat)
+//!
+//! def main(- Nat)
+//! | = Zero Succ
+//! ";
+//!
+//! let program = sl::check_source(source)?;
+//! sl::evaluator::Evaluator::new().eval_from_main(&program).unwrap();
+//! # Ok(())
+//! # }
+//! ```
+
+pub mod builder;
+pub mod cfg;
+pub mod diagnostics;
+pub mod engine;
+pub mod evaluator;
+pub mod hashing;
+pub mod logging;
+pub mod parser;
+pub mod path;
+pub mod prelude;
+pub mod source_map;
+pub mod symbol;
+pub mod tokens;
+pub mod type_checker;
+
+use crate::{diagnostics::Diagnostics, engine::Engine, type_checker::CheckedProgram};
+
+/// Parses and type checks `source` with no extra builtins, returning every
+/// diagnostic reported along the way if it doesn't check.
+pub fn check_source(source: &str) -> Result<CheckedProgram, Diagnostics> {
+    Engine::new().check(source)
+}
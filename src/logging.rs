@@ -0,0 +1,143 @@
+//! Words logging a message about a target, naming what it is about, at one
+//! of four levels. The host decides where the messages go and from which
+//! level on they are kept:
+//!
+//! ```
+//! use std::sync::{Arc, Mutex};
+//!
+//! use sl::{
+//!     engine::Engine,
+//!     logging::{LogLevel, LogRecord},
+//! };
+//!
+//! let records = Arc::new(Mutex::new(vec![]));
+//! let sink = records.clone();
+//! let engine = Engine::new().logging(LogLevel::Info, move |record: &LogRecord| sink.lock().unwrap().push(record.clone()));
+//! let program = engine.check("
+//! def main(- !io)
+//! | = \"db\" \"connecting\" log_debug \"db\" \"connected\" log_info
+//! ").unwrap();
+//! engine.run(&program).unwrap();
+//!
+//! assert_eq!(*records.lock().unwrap(), [LogRecord {
+//!     level: LogLevel::Info,
+//!     target: "db".into(),
+//!     message: "connected".into(),
+//! }]);
+//! ```
+
+use std::{fmt, sync::Arc};
+
+use crate::{
+    engine::{Builtin, WordPack},
+    evaluator::Value,
+    type_checker::Type,
+};
+
+/// How much a message matters, least first.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum LogLevel {
+    Debug,
+    Info,
+    Warn,
+    Error,
+}
+
+impl LogLevel {
+    const ALL: [LogLevel; 4] = [LogLevel::Debug, LogLevel::Info, LogLevel::Warn, LogLevel::Error];
+
+    /// The level named `name`, as in `warn`.
+    pub fn from_name(name: &str) -> Option<Self> {
+        LogLevel::ALL.into_iter().find(|level| level.name() == name)
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            LogLevel::Debug => "debug",
+            LogLevel::Info => "info",
+            LogLevel::Warn => "warn",
+            LogLevel::Error => "error",
+        }
+    }
+
+    /// The word logging at the level and what it is documented as.
+    fn word(&self) -> (&'static str, &'static str) {
+        match self {
+            LogLevel::Debug => ("log_debug", "Logs the message on top for the target below it, as a detail for debugging."),
+            LogLevel::Info => ("log_info", "Logs the message on top for the target below it, as information."),
+            LogLevel::Warn => ("log_warn", "Logs the message on top for the target below it, as a warning."),
+            LogLevel::Error => ("log_error", "Logs the message on top for the target below it, as an error."),
+        }
+    }
+}
+
+impl fmt::Display for LogLevel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// A message logged by a program.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LogRecord {
+    pub level: LogLevel,
+    pub target: Arc<str>,
+    pub message: Arc<str>,
+}
+
+/// Receives what programs log, as they log it.
+pub trait LogSink: Send + Sync {
+    fn log(&self, record: &LogRecord);
+}
+
+/// Forwards every record to the closure.
+impl<F: Fn(&LogRecord) + Send + Sync> LogSink for F {
+    fn log(&self, record: &LogRecord) {
+        self(record);
+    }
+}
+
+/// `log_debug`, `log_info`, `log_warn` and `log_error`, all of them
+/// `(String String - !io)`, sending the records at `level` or above to
+/// `sink` and dropping the others.
+pub struct Logging {
+    level: LogLevel,
+    sink: Arc<dyn LogSink>,
+}
+
+impl Logging {
+    pub fn new(level: LogLevel, sink: impl LogSink + 'static) -> Self {
+        Self { level, sink: Arc::new(sink) }
+    }
+}
+
+impl WordPack for Logging {
+    fn builtins(&self) -> Vec<Builtin> {
+        LogLevel::ALL
+            .into_iter()
+            .map(|level| {
+                let (name, doc) = level.word();
+                let sink = (level >= self.level).then(|| self.sink.clone());
+                Builtin {
+                    name,
+                    ty: Type::Function {
+                        inputs: Arc::new([Type::string(), Type::string()]),
+                        outputs: Arc::new([]),
+                        rows: None,
+                        io: true,
+                    },
+                    run: Arc::new(move |stack: &mut Vec<Value>| {
+                        let (Some(Value::String(message)), Some(Value::String(target))) = (stack.pop(), stack.pop()) else {
+                            unreachable!()
+                        };
+                        if let Some(sink) = &sink {
+                            sink.log(&LogRecord { level, target, message });
+                        }
+                        Ok(())
+                    }),
+                    doc,
+                }
+            })
+            .collect()
+    }
+}
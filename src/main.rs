@@ -1,65 +1,197 @@
-mod evaluator;
-mod parser;
-mod tokens;
-mod type_checker;
+use std::{env, fs, process, sync::Arc};
 
-fn main() {
-    let code = "
-data Nat
-| 0
-| Succ(Nat)
-
-data NatList
-| Nil
-| Cons(NatList Nat)
-
-def add(Nat Nat - Nat)
-| n 0       = n
-| n Succ(m) = n m add Succ
+use sl::{
+    cfg::Cfg,
+    diagnostics::{
+        catalog::Catalog, explain::explain, json::JsonSink, render::RenderingSink, sarif::to_sarif, short::ShortSink, Diagnostic,
+        DiagnosticSink, Level, Levels,
+    },
+    engine::Engine,
+    hashing::Hashing,
+    logging::{LogLevel, LogRecord},
+    path::Paths,
+    source_map::SourceMap,
+};
 
-def add2(Nat Nat - Nat)
-|      =     add
-| n    = n   add
-| n m  = n m add
+const USAGE: &str = "usage: sl [check] [--feature <name>]... [--target <name>] [--message-format <human|short|json|sarif>]
+          [-A <code>]... [-W <code>]... [-D <code>]... [--locale <name|file>] [--trace-types <def>]
+          [--log-level <debug|info|warn|error>] <file>
+       sl explain <code>
 
-def mul(Nat Nat - Nat)
-| n 0       = 0
-| n Succ(m) = n m mul n add
+check only checks <file> without running it, as the json and sarif formats always do.
+--trace-types reports the stack after every expression of <def>.
+--log-level writes what the program logs at that level or above to stderr, from `info` unless given.
 
-# def swap(a b - b a)
-# | x y = y x
+-A, -W and -D allow, warn about or deny the warnings with <code>, or all of them for `warnings`.
+--locale writes diagnostics with a built-in catalog, as `en`, or with the catalog in <file>.";
 
-def capture(Nat - [ - Nat])
-| n = [- n]
-
-def map([Nat - Nat] NatList - NatList)
-| f Nil          = Nil
-| f Cons(rest n) = f rest map n f & Cons
+/// How diagnostics are printed.
+enum MessageFormat {
+    /// Rendered with the source they are about, to stderr.
+    Human,
+    /// On a line each after where they are, to stderr.
+    Short,
+    /// As lines of JSON, to stdout. Implies `check`.
+    Json,
+    /// As one SARIF log once checking is done, to stdout. Implies `check`.
+    Sarif,
+}
 
-def main(- NatList)
-| = [Nat - 0 Succ add] Nil 0 Cons 0 Succ Cons map
+fn main() {
+    let mut cfg = Cfg::new();
+    let mut path = None;
+    let mut message_format = MessageFormat::Human;
+    let mut levels = Levels::new();
+    let mut catalog = Catalog::english();
+    let mut traced = None;
+    let mut log_level = LogLevel::Info;
+    let mut args = env::args().skip(1).peekable();
+    if args.next_if(|arg| arg == "explain").is_some() {
+        let (Some(code), None) = (args.next(), args.next()) else {
+            eprintln!("{USAGE}");
+            process::exit(1);
+        };
+        match explain(&code) {
+            Some(explanation) => println!("{explanation}"),
+            None => {
+                eprintln!("error: no diagnostic has the code `{code}`");
+                process::exit(1);
+            }
+        }
+        return;
+    }
+    let mut run = args.next_if(|arg| arg == "check").is_none();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--feature" | "--target" => {
+                let Some(name) = args.next() else {
+                    eprintln!("{USAGE}");
+                    process::exit(1);
+                };
+                cfg = if arg == "--feature" { cfg.feature(&name) } else { cfg.target(&name) };
+            }
+            "-A" | "-W" | "-D" => {
+                let Some(code) = args.next() else {
+                    eprintln!("{USAGE}");
+                    process::exit(1);
+                };
+                // The parts of the messages of diagnostics have keys of
+                // their own, with a `.` in them.
+                if code != "warnings" && !(code.starts_with('W') && !code.contains('.') && Catalog::english().contains(&code)) {
+                    eprintln!("error: no warning has the code `{code}`");
+                    eprintln!("{USAGE}");
+                    process::exit(1);
+                }
+                let level = match arg.as_str() {
+                    "-A" => Level::Allow,
+                    "-W" => Level::Warn,
+                    _ => Level::Deny,
+                };
+                levels = levels.set(&code, level);
+            }
+            "--trace-types" => {
+                let Some(def) = args.next() else {
+                    eprintln!("{USAGE}");
+                    process::exit(1);
+                };
+                traced = Some(def);
+            }
+            "--log-level" => {
+                let Some(level) = args.next().as_deref().and_then(LogLevel::from_name) else {
+                    eprintln!("{USAGE}");
+                    process::exit(1);
+                };
+                log_level = level;
+            }
+            "--locale" => {
+                let Some(locale) = args.next() else {
+                    eprintln!("{USAGE}");
+                    process::exit(1);
+                };
+                catalog = match Catalog::builtin(&locale) {
+                    Some(catalog) => catalog,
+                    None => match fs::read_to_string(&locale)
+                        .map_err(|error| error.to_string())
+                        .and_then(|source| Catalog::parse(&source))
+                    {
+                        Ok(catalog) => Arc::new(catalog),
+                        Err(error) => {
+                            eprintln!("error: could not load the catalog `{locale}`: {error}");
+                            process::exit(1);
+                        }
+                    },
+                };
+            }
+            "--message-format" => {
+                message_format = match args.next().as_deref() {
+                    Some("human") => MessageFormat::Human,
+                    Some("short") => MessageFormat::Short,
+                    Some("json") => MessageFormat::Json,
+                    Some("sarif") => MessageFormat::Sarif,
+                    _ => {
+                        eprintln!("{USAGE}");
+                        process::exit(1);
+                    }
+                };
+            }
+            _ if path.is_none() => path = Some(arg),
+            _ => {
+                eprintln!("{USAGE}");
+                process::exit(1);
+            }
+        }
+    }
 
-# def main(- Nat)
-# | = 0 capture &
+    let Some(path) = path else {
+        eprintln!("{USAGE}");
+        process::exit(1);
+    };
+    // What the program prints would follow the document on stdout.
+    if let MessageFormat::Json | MessageFormat::Sarif = message_format {
+        run = false;
+    }
 
-# def main(- Nat)
-# | = [- 0] [[- Nat] - &] &
+    let source = match fs::read_to_string(&path) {
+        Ok(source) => source,
+        Err(error) => {
+            eprintln!("error: could not read `{path}`: {error}");
+            process::exit(1);
+        }
+    };
 
-# def main(- Nat)
-# | = 0 Succ Succ Succ 0 Succ Succ mul
+    let mut source_map = SourceMap::new();
+    let file = source_map.add(path, source);
 
-# def main(- Nat Nat)
-# | = 0 0 Succ swap
-";
+    let mut engine = Engine::new()
+        .word_pack(&Paths)
+        .word_pack(&Hashing)
+        .logging(log_level, |record: &LogRecord| eprintln!("{} {}: {}", record.level, record.target, record.message))
+        .cfg(cfg)
+        .levels(levels)
+        .catalog(catalog);
+    if let Some(def) = &traced {
+        engine = engine.trace(def);
+    }
+    let mut sarif = vec![];
+    let mut sink: Box<dyn DiagnosticSink> = match message_format {
+        MessageFormat::Human => Box::new(RenderingSink::new(&source_map, file)),
+        MessageFormat::Short => Box::new(ShortSink::new(&source_map, file)),
+        MessageFormat::Json => Box::new(JsonSink::new(&source_map, file)),
+        MessageFormat::Sarif => Box::new(|diagnostic: Diagnostic| sarif.push(diagnostic)),
+    };
+    let program = engine.check_reporting(source_map.source(file), &mut *sink);
+    drop(sink);
+    if let MessageFormat::Sarif = message_format {
+        println!("{}", to_sarif(&sarif, &source_map, file));
+    }
+    let Some(program) = program else {
+        process::exit(1);
+    };
 
-    let tokens = tokens::Tokens::new(code);
-    // for token in tokens {
-    //     println!("{token:?}")
-    // }
-    let mut parser = parser::Parser::new(tokens);
-    let mut type_checker = type_checker::TypeChecker::new();
-    let top_levels = parser.top_levels().unwrap();
-    type_checker.type_check(&top_levels).unwrap();
-    let mut evaluator = evaluator::Evaluator::new();
-    evaluator.eval_from_main(&top_levels);
+    if run {
+        if let Err(error) = engine.run(&program) {
+            eprintln!("error: {error}");
+            process::exit(1);
+        }
+    }
 }
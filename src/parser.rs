@@ -1,16 +1,156 @@
-use std::iter::Peekable;
+//! Recursive descent parser producing the [`TopLevel`] items of a program.
+//!
+//! The `---` comments right before a def or data type document it:
+//!
+//! ```
+//! use sl::{parser::{Parser, TopLevel}, tokens::Tokens};
+//!
+//! let source = "--- The length of the empty list.\n--- It is always zero.\ndef size(- Int)\n| = 0\n";
+//! let top_levels = Parser::new(Tokens::new(source)).top_levels(&mut vec![]).unwrap();
+//! let TopLevel::Def { doc, .. } = &top_levels[0] else { unreachable!() };
+//! assert_eq!(doc.as_deref(), Some("The length of the empty list.\nIt is always zero."));
+//! ```
+
+use std::fmt;
+
+use crate::{
+    cfg::{Cfg, CfgPredicate},
+    diagnostics::{catalog::Catalog, Diagnostic, DiagnosticSink},
+    source_map::Span,
+    symbol::Symbol,
+    tokens::{Token, Tokens},
+};
+
+/// How deeply quotations, quotation types and constructor patterns may
+/// nest before parsing gives up, instead of overflowing the native stack.
+const NESTING_LIMIT: usize = 256;
+
+/// A token with its span and the doc comment before it.
+type SpannedToken = (Token, Span, Option<String>);
+
+/// The tokens being parsed, peekable like any iterator but keeping track
+/// of where the tokens are.
+#[derive(Clone)]
+struct TokenStream<'tokens> {
+    tokens: Tokens<'tokens>,
+    peeked: Option<Option<SpannedToken>>,
+    /// The span of the token read past last.
+    previous: Span,
+    /// The stream right after `next` returned the last token a top level
+    /// starts with, along with that token. Only the top levels themselves
+    /// expect those, so a syntax error reading one has read into the next
+    /// top level, which parsing goes on from.
+    resume: Option<(Tokens<'tokens>, SpannedToken)>,
+}
+
+impl TokenStream<'_> {
+    fn peek_spanned(&mut self) -> Option<&SpannedToken> {
+        let tokens = &mut self.tokens;
+        self.peeked
+            .get_or_insert_with(|| tokens.next().map(|token| (token, tokens.span(), tokens.doc())))
+            .as_ref()
+    }
+
+    fn peek(&mut self) -> Option<&Token> {
+        self.peek_spanned().map(|(token, ..)| token)
+    }
+
+    /// The doc comment right before the next token.
+    fn doc(&mut self) -> Option<String> {
+        self.peek_spanned().and_then(|(_, _, doc)| doc.clone())
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.advance()?;
+        if token.starts_top_level() {
+            self.resume = Some((self.tokens.clone(), (token.clone(), self.previous, self.tokens.doc())));
+        }
+        Some(token)
+    }
+
+    /// Like `next`, but meant to read what it expects, so it can't read
+    /// into the next top level unexpectedly.
+    fn next_if_eq(&mut self, expected: &Token) -> Option<Token> {
+        match self.peek() {
+            Some(token) if token == expected => self.advance(),
+            _ => None,
+        }
+    }
+
+    fn advance(&mut self) -> Option<Token> {
+        let (token, span, _) = match self.peeked.take() {
+            Some(peeked) => peeked,
+            None => self.tokens.next().map(|token| (token, self.tokens.span(), self.tokens.doc())),
+        }?;
+        self.previous = span;
+        Some(token)
+    }
+
+    /// Goes back to the last token a top level starts with that `next`
+    /// read, if it read one since `resume` was last reset.
+    fn resume(&mut self) -> bool {
+        let Some((tokens, peeked)) = self.resume.take() else {
+            return false;
+        };
+        self.tokens = tokens;
+        self.peeked = Some(Some(peeked));
+        true
+    }
 
-use crate::tokens::{Token, Tokens};
+    /// The span of the next token, or the empty one at the end of the
+    /// source if there is none.
+    fn span(&mut self) -> Span {
+        self.peek();
+        self.tokens.span()
+    }
+
+    /// The span of the token read from the source last, peeked or not,
+    /// which is where parsing stopped when it fails.
+    fn last_read(&self) -> Span {
+        self.tokens.span()
+    }
+}
 
 pub struct Parser<'tokens> {
-    tokens: Peekable<Tokens<'tokens>>,
+    tokens: TokenStream<'tokens>,
+    depth: usize,
+    cfg: Cfg,
 }
 
 impl<'tokens> Parser<'tokens> {
     pub fn new(tokens: Tokens<'tokens>) -> Self {
         Self {
-            tokens: tokens.peekable(),
+            tokens: TokenStream {
+                tokens,
+                peeked: None,
+                previous: Span::default(),
+                resume: None,
+            },
+            depth: 0,
+            cfg: Cfg::new(),
+        }
+    }
+
+    /// The span from `start` to the end of the token consumed last.
+    fn span_from(&self, start: Span) -> Span {
+        start.to(self.tokens.previous)
+    }
+
+    /// Sets what `#[cfg(...)]` attributes are resolved against.
+    pub fn with_cfg(mut self, cfg: Cfg) -> Self {
+        self.cfg = cfg;
+        self
+    }
+
+    fn nested<T>(&mut self, parse: fn(&mut Self) -> ParseResult<T>) -> ParseResult<T> {
+        if self.depth == NESTING_LIMIT {
+            return Err(ParseError::NestingTooDeep);
         }
+
+        self.depth += 1;
+        let result = parse(self);
+        self.depth -= 1;
+        result
     }
 
     fn expect(&mut self, expected: Token) -> ParseResult<()> {
@@ -23,10 +163,35 @@ impl<'tokens> Parser<'tokens> {
         Ok(())
     }
 
-    fn expect_word(&mut self) -> ParseResult<String> {
+    /// Expects a `(` whether or not it follows a word.
+    fn expect_opening_parenthesis(&mut self) -> ParseResult<()> {
+        match self.tokens.next() {
+            Some(Token::OpeningParenthesis | Token::DetachedOpeningParenthesis) => Ok(()),
+            Some(_) => Err(ParseError::UnexpectedToken),
+            None => Err(ParseError::UnexpectedEOF),
+        }
+    }
+
+    /// Parses the elements of a tuple after its `(`, at least two of them
+    /// separated by commas.
+    fn tuple<T>(&mut self, element: fn(&mut Self) -> ParseResult<T>) -> ParseResult<Vec<T>> {
+        let mut elements = vec![self.nested(element)?];
+        self.expect(Token::Comma)?;
+        loop {
+            elements.push(self.nested(element)?);
+            if self.tokens.next_if_eq(&Token::Comma).is_none() {
+                break;
+            }
+        }
+        self.expect(Token::ClosingParenthesis)?;
+        Ok(elements)
+    }
+
+    fn expect_word(&mut self) -> ParseResult<Symbol> {
         let next = self.tokens.next();
         let Some(Token::Word(word)) = next else {
             return Err(match next {
+                Some(Token::InvalidName) => ParseError::InvalidName,
                 Some(_) => ParseError::UnexpectedToken,
                 None    => ParseError::UnexpectedEOF,
             })
@@ -34,126 +199,335 @@ impl<'tokens> Parser<'tokens> {
         Ok(word)
     }
 
+    fn expect_string(&mut self) -> ParseResult<Symbol> {
+        match self.tokens.next() {
+            Some(Token::String(string)) => Ok(string),
+            Some(Token::UnterminatedString) => Err(ParseError::UnterminatedString),
+            Some(Token::InvalidEscape) => Err(ParseError::InvalidEscape),
+            Some(_) => Err(ParseError::UnexpectedToken),
+            None => Err(ParseError::UnexpectedEOF),
+        }
+    }
+
+    /// Parses the `..name` standing for the rest of the stack at the start of
+    /// either side of a stack effect, if there is one.
+    fn row(&mut self) -> Option<Symbol> {
+        let Some(Token::Word(word)) = self.tokens.peek() else {
+            return None;
+        };
+
+        let name = word.as_str().strip_prefix("..")?;
+        self.tokens.next();
+        Some(Symbol::intern(name))
+    }
+
+    /// The type variables a quotation type is polymorphic over, as the `a`
+    /// in `[a | a - a]`.
+    fn bound_variables(&mut self) -> ParseResult<Vec<Symbol>> {
+        let mut lookahead = self.tokens.clone();
+        let mut variables = vec![];
+        loop {
+            match lookahead.next() {
+                Some(Token::Word(word)) => variables.push(word),
+                Some(Token::Bar) if !variables.is_empty() => break,
+                _ => return Ok(vec![]),
+            }
+        }
+
+        if variables.iter().any(|variable| !is_variable_name(variable.as_str())) {
+            return Err(ParseError::MajusculeTypeParameter);
+        }
+        self.tokens = lookahead;
+        Ok(variables)
+    }
+
+    fn rows(input_row: Option<Symbol>, output_row: Option<Symbol>) -> ParseResult<Option<(Symbol, Symbol)>> {
+        match (input_row, output_row) {
+            (Some(input_row), Some(output_row)) => Ok(Some((input_row, output_row))),
+            (None, None) => Ok(None),
+            _ => Err(ParseError::UnbalancedRow),
+        }
+    }
+
+    fn at_io(&mut self) -> bool {
+        matches!(self.tokens.peek(), Some(Token::Word(word)) if word.as_str() == "!io")
+    }
+
+    /// Parses the `!io` marking a stack effect as performing IO, if there
+    /// is one at the end of its outputs.
+    fn io(&mut self) -> bool {
+        self.at_io() && self.tokens.next().is_some()
+    }
+
     fn type_expr(&mut self) -> ParseResult<TypeExpr> {
+        let start = self.tokens.span();
         let Some(token) = self.tokens.next() else {
             return Err(ParseError::UnexpectedEOF);
         };
 
-        match token {
-            Token::Word(word) => Ok(TypeExpr::Word(word)),
+        let kind = match token {
+            Token::Word(name) if self.tokens.next_if_eq(&Token::OpeningParenthesis).is_some() => {
+                let mut arguments = vec![];
+                while !matches!(self.tokens.peek(), Some(Token::ClosingParenthesis)) {
+                    arguments.push(self.nested(Self::type_expr)?);
+                }
+                self.expect(Token::ClosingParenthesis)?;
+                TypeExprKind::Application { name, arguments }
+            }
+            Token::Word(word) => TypeExprKind::Word(word),
+            Token::InvalidName => return Err(ParseError::InvalidName),
+            Token::OpeningParenthesis | Token::DetachedOpeningParenthesis => TypeExprKind::Tuple(self.tuple(Self::type_expr)?),
             Token::OpeningBracket => {
+                let forall = self.bound_variables()?;
+                let input_row = self.row();
                 let mut inputs = vec![];
                 while !matches!(self.tokens.peek(), Some(Token::Minus)) {
-                    inputs.push(self.type_expr()?);
+                    inputs.push(self.nested(Self::type_expr)?);
                 }
                 self.expect(Token::Minus)?;
 
+                let output_row = self.row();
                 let mut outputs = vec![];
-                while !matches!(self.tokens.peek(), Some(Token::ClosingBracket)) {
-                    outputs.push(self.type_expr()?);
+                while !matches!(self.tokens.peek(), Some(Token::ClosingBracket)) && !self.at_io() {
+                    outputs.push(self.nested(Self::type_expr)?);
                 }
+                let io = self.io();
                 self.expect(Token::ClosingBracket)?;
 
-                Ok(TypeExpr::Quotation { inputs, outputs })
+                let rows = Self::rows(input_row, output_row)?;
+                TypeExprKind::Quotation { inputs, outputs, rows, io, forall }
             }
-            _ => Err(ParseError::UnexpectedToken)
-        }
+            _ => return Err(ParseError::UnexpectedToken)
+        };
+        Ok(TypeExpr { kind, span: self.span_from(start) })
     }
 
     fn constructor(&mut self) -> ParseResult<Constructor> {
         self.expect(Token::Bar)?;
+        let start = self.tokens.span();
         let Some(token) = self.tokens.next() else {
             return Err(ParseError::UnexpectedEOF);
         };
 
         match token {
             Token::Word(name) => {
-                if name.chars().next().unwrap().is_lowercase() {
+                if is_variable_name(name.as_str()) {
                     return Err(ParseError::MinusculeConstructor)
                 }
 
                 let mut argument_types = vec![];
+                let mut output = None;
                 if let Some(Token::OpeningParenthesis) = self.tokens.peek() {
                     self.tokens.next();
-                    while !matches!(self.tokens.peek(), Some(Token::ClosingParenthesis)) {
+                    while !matches!(self.tokens.peek(), Some(Token::ClosingParenthesis | Token::Minus)) {
                         argument_types.push(self.type_expr()?);
                     }
+                    if self.tokens.next_if_eq(&Token::Minus).is_some() {
+                        output = Some(self.type_expr()?);
+                    }
                     self.expect(Token::ClosingParenthesis)?;
                 }
                 Ok(Constructor {
                     name,
                     argument_types,
+                    output,
+                    span: self.span_from(start),
                 })
             }
+            Token::InvalidName => Err(ParseError::InvalidName),
             _ => Err(ParseError::UnexpectedToken),
         }
     }
 
-    fn data(&mut self) -> ParseResult<TopLevel> {
+    fn data(&mut self, doc: Option<String>) -> ParseResult<TopLevel> {
+        let start = self.tokens.span();
+        let linear = self.tokens.next_if_eq(&Token::KeywordLinear).is_some();
         self.expect(Token::KeywordData)?;
         let name = self.expect_word()?;
+        let mut parameters = vec![];
+        if self.tokens.next_if_eq(&Token::OpeningParenthesis).is_some() {
+            while !matches!(self.tokens.peek(), Some(Token::ClosingParenthesis)) {
+                let parameter = self.expect_word()?;
+                if !is_variable_name(parameter.as_str()) {
+                    return Err(ParseError::MajusculeTypeParameter);
+                }
+                parameters.push(parameter);
+            }
+            self.expect(Token::ClosingParenthesis)?;
+        }
+        let mut derives = vec![];
+        if self.tokens.next_if_eq(&Token::KeywordDerives).is_some() {
+            self.expect_opening_parenthesis()?;
+            while !matches!(self.tokens.peek(), Some(Token::ClosingParenthesis)) {
+                derives.push(self.expect_word()?);
+                if self.tokens.next_if_eq(&Token::Comma).is_none() {
+                    break;
+                }
+            }
+            self.expect(Token::ClosingParenthesis)?;
+        }
         let mut constructors = vec![];
         while let Some(Token::Bar) = self.tokens.peek() {
             constructors.push(self.constructor()?);
         }
-        Ok(TopLevel::Data { name, constructors })
+        Ok(TopLevel::Data { name, parameters, constructors, linear, derives, doc, span: self.span_from(start) })
+    }
+
+    fn trait_declaration(&mut self) -> ParseResult<TopLevel> {
+        let start = self.tokens.span();
+        self.expect(Token::KeywordTrait)?;
+        let name = self.expect_word()?;
+        self.expect(Token::OpeningParenthesis)?;
+        let parameter = self.expect_word()?;
+        if !is_variable_name(parameter.as_str()) {
+            return Err(ParseError::MajusculeTypeParameter);
+        }
+        self.expect(Token::ClosingParenthesis)?;
+
+        let mut methods = vec![];
+        while self.tokens.next_if_eq(&Token::Bar).is_some() {
+            let method_start = self.tokens.span();
+            let name = self.expect_word()?;
+            let signature = self.signature()?;
+            methods.push(Method { name, signature, span: self.span_from(method_start) });
+        }
+        Ok(TopLevel::Trait { name, parameter, methods, span: self.span_from(start) })
+    }
+
+    fn implementation(&mut self) -> ParseResult<TopLevel> {
+        let start = self.tokens.span();
+        self.expect(Token::KeywordImpl)?;
+        let trait_name = self.expect_word()?;
+        self.expect(Token::OpeningParenthesis)?;
+        let ty = self.type_expr()?;
+        self.expect(Token::ClosingParenthesis)?;
+
+        let mut methods = vec![];
+        while self.tokens.next_if_eq(&Token::Bar).is_some() {
+            let method_start = self.tokens.span();
+            let method = self.expect_word()?;
+            self.expect(Token::EqualsSign)?;
+            let word = self.expect_word()?;
+            methods.push(MethodImpl { method, word, span: self.span_from(method_start) });
+        }
+        Ok(TopLevel::Impl { trait_name, ty, methods, span: self.span_from(start) })
+    }
+
+    fn alias(&mut self) -> ParseResult<TopLevel> {
+        let start = self.tokens.span();
+        self.expect(Token::KeywordType)?;
+        let name = self.expect_word()?;
+        self.expect(Token::EqualsSign)?;
+        let definition = self.type_expr()?;
+        Ok(TopLevel::Type { name, definition, span: self.span_from(start) })
     }
 
     fn pattern(&mut self) -> ParseResult<Pattern> {
+        let start = self.tokens.span();
         let Some(token) = self.tokens.next() else {
             return Err(ParseError::UnexpectedEOF);
         };
 
-        match token {
-            Token::Word(name) => Ok(if name.chars().next().unwrap().is_lowercase() {
-                Pattern::All(name)
+        let kind = match token {
+            Token::Word(name) => if name.as_str() == WILDCARD
+                || is_variable_name(name.as_str()) && !BOOL_CONSTRUCTORS.contains(&name.as_str())
+            {
+                PatternKind::All(name)
             } else {
                 let mut arguments = vec![];
                 if let Some(Token::OpeningParenthesis) = self.tokens.peek() {
                     self.tokens.next();
                     while !matches!(self.tokens.peek(), Some(Token::ClosingParenthesis)) {
-                        arguments.push(self.pattern()?);
+                        arguments.push(self.nested(Self::pattern)?);
                     }
                     self.expect(Token::ClosingParenthesis)?;
                 }
-                Pattern::Constructor { name, arguments }
-            }),
-            _ => Err(ParseError::UnexpectedToken),
+                PatternKind::Constructor { name, arguments }
+            },
+            Token::OpeningParenthesis | Token::DetachedOpeningParenthesis => PatternKind::Tuple(self.tuple(Self::pattern)?),
+            Token::InvalidName => return Err(ParseError::InvalidName),
+            Token::Char(ch) => PatternKind::Char(ch),
+            Token::InvalidChar => return Err(ParseError::InvalidChar),
+            Token::InvalidEscape => return Err(ParseError::InvalidEscape),
+            _ => return Err(ParseError::UnexpectedToken),
+        };
+        Ok(Pattern { kind, span: self.span_from(start) })
+    }
+
+    /// Parses the `inputs -` a quotation starts with, along with the span
+    /// of the `-`. If it doesn't start with types followed by `-`, nothing
+    /// is consumed and its inputs are left to be inferred.
+    fn quotation_inputs(&mut self) -> Option<(Vec<TypeExpr>, Span)> {
+        let tokens = self.tokens.clone();
+        let mut inputs = vec![];
+        while self.tokens.next_if_eq(&Token::Minus).is_none() {
+            match self.type_expr() {
+                Ok(input) => inputs.push(input),
+                Err(_) => {
+                    self.tokens = tokens;
+                    return None;
+                }
+            }
         }
+        Some((inputs, self.tokens.previous))
+    }
+
+    /// Parses the expressions making up one element of a tuple.
+    fn tuple_element(&mut self) -> ParseResult<Vec<Expr>> {
+        let mut element = vec![];
+        while !matches!(self.tokens.peek(), Some(Token::Comma | Token::ClosingParenthesis)) {
+            element.push(self.expr()?);
+        }
+        Ok(element)
     }
 
     fn expr(&mut self) -> ParseResult<Expr> {
+        let start = self.tokens.span();
         let Some(token) = self.tokens.next() else {
             return Err(ParseError::UnexpectedEOF);
         };
 
-        match token {
-            Token::Word(word) => Ok(Expr::Word(word)),
+        let kind = match token {
+            Token::Word(word) if word.as_str() == "?" => ExprKind::Hole,
+            Token::Word(word) => ExprKind::Word(word),
+            Token::InvalidName => return Err(ParseError::InvalidName),
+            Token::Integer(integer) => ExprKind::IntLit(integer),
+            Token::IntegerOutOfRange => return Err(ParseError::IntegerOutOfRange),
+            Token::Float(float) => ExprKind::FloatLit(float),
+            Token::String(string) => ExprKind::StringLit(string),
+            Token::UnterminatedString => return Err(ParseError::UnterminatedString),
+            Token::InvalidEscape => return Err(ParseError::InvalidEscape),
+            Token::Char(ch) => ExprKind::CharLit(ch),
+            Token::InvalidChar => return Err(ParseError::InvalidChar),
+            // Subtraction. A quotation starting with words followed by `-`
+            // reads as annotated, as in `[a b -]`, until the type checker
+            // finds the words aren't types.
+            Token::Minus => ExprKind::Word(Symbol::intern("-")),
+            // Equality, a body can't contain the `=` of a branch.
+            Token::EqualsSign => ExprKind::Word(Symbol::intern("=")),
             Token::OpeningBracket => {
-                let mut inputs = vec![];
-                while !matches!(self.tokens.peek(), Some(Token::Minus)) {
-                    inputs.push(self.type_expr()?);
-                }
-                self.expect(Token::Minus)?;
-
+                let inputs = self.quotation_inputs();
 
                 let mut quotation = vec![];
                 while !matches!(self.tokens.peek(), Some(Token::ClosingBracket)) {
-                    quotation.push(self.expr()?);
+                    quotation.push(self.nested(Self::expr)?);
                 }
                 self.expect(Token::ClosingBracket)?;
 
-                Ok(Expr::Quotation {
+                ExprKind::Quotation {
                     inputs,
                     quotation,
-                })
+                }
             },
-            Token::Ampersand => Ok(Expr::Unquote),
-            _ => Err(ParseError::UnexpectedToken)
-        }
+            Token::Ampersand => ExprKind::Unquote,
+            Token::OpeningParenthesis | Token::DetachedOpeningParenthesis => ExprKind::Tuple(self.tuple(Self::tuple_element)?),
+            _ => return Err(ParseError::UnexpectedToken)
+        };
+        Ok(Expr { kind, span: self.span_from(start) })
     }
 
     fn branch(&mut self) -> ParseResult<Branch> {
+        let start = self.tokens.span();
         self.expect(Token::Bar)?;
         let mut patterns = vec![];
         while !matches!(self.tokens.peek(), Some(Token::EqualsSign)) {
@@ -161,113 +535,489 @@ impl<'tokens> Parser<'tokens> {
         }
         self.expect(Token::EqualsSign)?;
         let mut body = vec![];
-        while let Some(Token::Word(_) | Token::OpeningBracket | Token::Ampersand) = self.tokens.peek() {
+        while let Some(
+            Token::Word(_)
+            | Token::InvalidName
+            | Token::Integer(_)
+            | Token::IntegerOutOfRange
+            | Token::Float(_)
+            | Token::String(_)
+            | Token::UnterminatedString
+            | Token::InvalidEscape
+            | Token::Char(_)
+            | Token::InvalidChar
+            | Token::Minus
+            | Token::EqualsSign
+            | Token::OpeningParenthesis
+            | Token::DetachedOpeningParenthesis
+            | Token::OpeningBracket
+            | Token::Ampersand,
+        ) = self.tokens.peek()
+        {
             body.push(self.expr()?);
         }
-        Ok(Branch { patterns, body })
+        Ok(Branch { patterns, body, span: self.span_from(start) })
     }
 
-    fn def(&mut self) -> ParseResult<TopLevel> {
+    fn def(&mut self, doc: Option<String>) -> ParseResult<TopLevel> {
+        let start = self.tokens.span();
+        let total = self.tokens.next_if_eq(&Token::KeywordTotal).is_some();
         self.expect(Token::KeywordDef)?;
         let name = self.expect_word()?;
+        let signature = match self.tokens.peek() {
+            Some(Token::OpeningParenthesis | Token::DetachedOpeningParenthesis) => Some(self.signature()?),
+            _ => None,
+        };
 
-        self.expect(Token::OpeningParenthesis)?;
+        let mut branches = vec![];
+        while let Some(Token::Bar) = self.tokens.peek() {
+            branches.push(self.branch()?);
+        }
+
+        Ok(TopLevel::Def {
+            name,
+            signature,
+            branches,
+            total,
+            doc,
+            span: self.span_from(start),
+        })
+    }
+
+    fn signature(&mut self) -> ParseResult<Signature> {
+        self.expect_opening_parenthesis()?;
+        let input_row = self.row();
         let mut inputs = vec![];
         while !matches!(self.tokens.peek(), Some(Token::Minus)) {
             inputs.push(self.type_expr()?);
         }
         self.expect(Token::Minus)?;
 
+        let output_row = self.row();
         let mut outputs = vec![];
-        while !matches!(self.tokens.peek(), Some(Token::ClosingParenthesis)) {
+        while !matches!(self.tokens.peek(), Some(Token::ClosingParenthesis)) && !self.at_io() {
             outputs.push(self.type_expr()?);
         }
+        let io = self.io();
         self.expect(Token::ClosingParenthesis)?;
+        let rows = Self::rows(input_row, output_row)?;
+        Ok(Signature { inputs, outputs, rows, io })
+    }
 
-        let mut branches = vec![];
-        while let Some(Token::Bar) = self.tokens.peek() {
-            branches.push(self.branch()?);
+    fn cfg_predicate(&mut self) -> ParseResult<CfgPredicate> {
+        let name = self.expect_word()?;
+        match name.as_str() {
+            "feature" | "target" => {
+                self.expect(Token::EqualsSign)?;
+                let value = self.expect_string()?;
+                Ok(if name.as_str() == "feature" {
+                    CfgPredicate::Feature(value)
+                } else {
+                    CfgPredicate::Target(value)
+                })
+            }
+            "not" => {
+                self.expect_opening_parenthesis()?;
+                let predicate = self.nested(Self::cfg_predicate)?;
+                self.expect(Token::ClosingParenthesis)?;
+                Ok(CfgPredicate::Not(Box::new(predicate)))
+            }
+            "all" | "any" => {
+                self.expect_opening_parenthesis()?;
+                let mut predicates = vec![];
+                while !matches!(self.tokens.peek(), Some(Token::ClosingParenthesis)) {
+                    predicates.push(self.nested(Self::cfg_predicate)?);
+                    if self.tokens.next_if_eq(&Token::Comma).is_none() {
+                        break;
+                    }
+                }
+                self.expect(Token::ClosingParenthesis)?;
+                Ok(if name.as_str() == "all" {
+                    CfgPredicate::All(predicates)
+                } else {
+                    CfgPredicate::Any(predicates)
+                })
+            }
+            _ => Err(ParseError::UnknownCfgPredicate),
+        }
+    }
+
+    fn attribute(&mut self) -> ParseResult<CfgPredicate> {
+        self.expect(Token::AttributeStart)?;
+        if self.expect_word()?.as_str() != "cfg" {
+            return Err(ParseError::UnknownAttribute);
         }
+        self.expect_opening_parenthesis()?;
+        let predicate = self.cfg_predicate()?;
+        self.expect(Token::ClosingParenthesis)?;
+        self.expect(Token::ClosingBracket)?;
+        Ok(predicate)
+    }
 
-        Ok(TopLevel::Def {
-            name,
-            inputs,
-            outputs,
-            branches,
-        })
+    /// Parses the next top level, or skips it if one of its `cfg`
+    /// attributes doesn't hold.
+    fn top_level(&mut self) -> ParseResult<Option<TopLevel>> {
+        // Before any attributes of the top level.
+        let doc = self.tokens.doc();
+        let mut enabled = true;
+        while let Some(Token::AttributeStart) = self.tokens.peek() {
+            let predicate = self.attribute()?;
+            enabled &= self.cfg.is_enabled(&predicate);
+        }
+
+        let top_level = match self.tokens.peek() {
+            Some(Token::KeywordData | Token::KeywordLinear) => self.data(doc)?,
+            Some(Token::KeywordDef | Token::KeywordTotal) => self.def(doc)?,
+            Some(Token::KeywordType) => self.alias()?,
+            Some(Token::KeywordTrait) => self.trait_declaration()?,
+            Some(Token::KeywordImpl) => self.implementation()?,
+            Some(_) => return Err(ParseError::UnexpectedToken),
+            None => return Err(ParseError::UnexpectedEOF),
+        };
+        Ok(enabled.then_some(top_level))
     }
 
-    pub fn top_levels(&mut self) -> ParseResult<Vec<TopLevel>> {
-        let mut top_levels = vec![];
+    /// Skips to the next token a top level can start with, where parsing
+    /// goes on after a syntax error, going back to it if the error was
+    /// reading it.
+    fn synchronize(&mut self) {
+        if self.tokens.resume() {
+            return;
+        }
         while let Some(token) = self.tokens.peek() {
-            top_levels.push(match token {
-                Token::KeywordData => self.data()?,
-                Token::KeywordDef => self.def()?,
-                _ => return Err(ParseError::UnexpectedToken),
-            })
+            if token.starts_top_level() {
+                return;
+            }
+            self.tokens.advance();
+        }
+    }
+
+    /// Parses the whole program. Syntax errors are reported to `sink`, the
+    /// top level of each is skipped and `None` is returned if there were
+    /// any.
+    pub fn top_levels(&mut self, sink: &mut dyn DiagnosticSink) -> Option<Vec<TopLevel>> {
+        let mut top_levels = vec![];
+        let mut failed = false;
+        while self.tokens.peek().is_some() {
+            self.tokens.resume = None;
+            match self.top_level() {
+                Ok(top_level) => top_levels.extend(top_level),
+                // The source ending in a comment is what ended the top level.
+                Err(ParseError::UnexpectedEOF) if self.tokens.tokens.unterminated_comment().is_some() => failed = true,
+                Err(error) => {
+                    sink.report(Diagnostic::new(error, self.tokens.last_read()));
+                    failed = true;
+                    self.synchronize();
+                }
+            }
         }
-        Ok(top_levels)
+        if let Some(span) = self.tokens.tokens.unterminated_comment() {
+            sink.report(Diagnostic::new(ParseError::UnterminatedComment, span));
+            failed = true;
+        }
+        (!failed).then_some(top_levels)
     }
 }
 
-type ParseResult<T> = Result<T, ParseError>;
+/// The constructors of the builtin `Bool`, lowercase unlike those of
+/// declared types.
+pub const BOOL_CONSTRUCTORS: [&str; 2] = ["false", "true"];
+
+/// The pattern matching anything without binding it.
+pub const WILDCARD: &str = "_";
+
+/// Whether `name` names a local in patterns and a type variable in types
+/// rather than a constructor or type: it starts with a letter that isn't
+/// uppercase, one without case like `数` included. Names starting with a
+/// symbol, like `→`, name words the way `+` does.
+pub fn is_variable_name(name: &str) -> bool {
+    name.chars().next().is_some_and(|ch| ch.is_alphabetic() && !ch.is_uppercase())
+}
+
+pub type ParseResult<T> = Result<T, ParseError>;
 #[derive(Debug)]
 pub enum ParseError {
     UnexpectedToken,
     UnexpectedEOF,
-    MinusculeConstructor
+    MinusculeConstructor,
+    MajusculeTypeParameter,
+    UnbalancedRow,
+    NestingTooDeep,
+    UnterminatedString,
+    IntegerOutOfRange,
+    UnknownAttribute,
+    UnknownCfgPredicate,
+    UnterminatedComment,
+    InvalidEscape,
+    InvalidChar,
+    InvalidName,
 }
 
+impl ParseError {
+    /// The code the error is known by, which `sl explain` describes.
+    pub fn code(&self) -> &'static str {
+        match self {
+            ParseError::UnexpectedToken => "E0101",
+            ParseError::UnexpectedEOF => "E0102",
+            ParseError::MinusculeConstructor => "E0103",
+            ParseError::MajusculeTypeParameter => "E0104",
+            ParseError::UnbalancedRow => "E0105",
+            ParseError::NestingTooDeep => "E0106",
+            ParseError::UnterminatedString => "E0107",
+            ParseError::IntegerOutOfRange => "E0108",
+            ParseError::UnknownAttribute => "E0109",
+            ParseError::UnknownCfgPredicate => "E0110",
+            ParseError::UnterminatedComment => "E0111",
+            ParseError::InvalidEscape => "E0112",
+            ParseError::InvalidChar => "E0113",
+            ParseError::InvalidName => "E0114",
+        }
+    }
+}
+
+impl ParseError {
+    /// The message of the error, written with the text of `catalog`.
+    pub fn message(&self, catalog: &Catalog) -> String {
+        catalog.text(self.code(), &[])
+    }
+}
+
+/// The message in English.
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message(&Catalog::english()))
+    }
+}
+
+impl std::error::Error for ParseError {}
+
 #[derive(Debug)]
 pub enum TopLevel {
     Data {
-        name: String,
+        name: Symbol,
+        /// The type variables the constructors are generic over, as in `data List(a)`.
+        parameters: Vec<Symbol>,
         constructors: Vec<Constructor>,
+        /// Values of the type have to be used exactly once, as with
+        /// `linear data File`.
+        linear: bool,
+        /// The traits implemented from how the values are built, as with
+        /// `data Nat derives (Eq, Show)`.
+        derives: Vec<Symbol>,
+        /// The text of the `---` comments before it.
+        doc: Option<String>,
+        span: Span,
     },
     Def {
-        name: String,
-        inputs: Vec<TypeExpr>,
-        outputs: Vec<TypeExpr>,
+        name: Symbol,
+        /// Inferred from the branches when left out.
+        signature: Option<Signature>,
         branches: Vec<Branch>,
+        /// The def has to terminate, as with `total def`.
+        total: bool,
+        /// The text of the `---` comments before it.
+        doc: Option<String>,
+        span: Span,
+    },
+    /// A name for `definition`, as in `type Op = [Int Int - Int]`.
+    Type {
+        name: Symbol,
+        definition: TypeExpr,
+        span: Span,
+    },
+    /// Words overloaded per type, as in `trait Show(a) | show(a - String)`.
+    Trait {
+        name: Symbol,
+        parameter: Symbol,
+        methods: Vec<Method>,
+        span: Span,
+    },
+    /// The words implementing the methods of a trait for a type, as in
+    /// `impl Show(Int) | show = show_int`.
+    Impl {
+        trait_name: Symbol,
+        ty: TypeExpr,
+        methods: Vec<MethodImpl>,
+        span: Span,
     },
 }
 
+impl TopLevel {
+    /// From its keyword to its end, leaving out its attributes.
+    pub fn span(&self) -> Span {
+        match self {
+            TopLevel::Data { span, .. }
+            | TopLevel::Def { span, .. }
+            | TopLevel::Type { span, .. }
+            | TopLevel::Trait { span, .. }
+            | TopLevel::Impl { span, .. } => *span,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Method {
+    pub name: Symbol,
+    /// In terms of the parameter of the trait, which has to be one of the
+    /// inputs.
+    pub signature: Signature,
+    pub span: Span,
+}
+
+impl Method {
+    /// The topmost input of the trait's parameter, whose type picks the
+    /// impl the method is run from.
+    pub fn dispatch_input(&self, parameter: Symbol) -> Option<usize> {
+        self.signature.inputs.iter().rposition(|input| matches!(input.kind, TypeExprKind::Word(word) if word == parameter))
+    }
+}
+
+#[derive(Debug)]
+pub struct MethodImpl {
+    pub method: Symbol,
+    pub word: Symbol,
+    pub span: Span,
+}
+
+#[derive(Debug)]
+pub struct Signature {
+    pub inputs: Vec<TypeExpr>,
+    pub outputs: Vec<TypeExpr>,
+    /// The rows named by `..a` in `(..a inputs - ..b outputs)`, when the
+    /// def does more to the rest of the stack than leave it alone.
+    pub rows: Option<(Symbol, Symbol)>,
+    /// Whether the def may perform IO, marked by `!io` after the outputs.
+    pub io: bool,
+}
+
 #[derive(Clone, Debug)]
 pub struct Branch {
+    /// A name bound more than once only matches values equal to each
+    /// other, as in `| n n = true`.
     pub patterns: Vec<Pattern>,
     pub body: Vec<Expr>,
+    /// From its `|` to the end of its body.
+    pub span: Span,
+}
+
+impl Branch {
+    /// The names the patterns bind with where they do, in order, repeated
+    /// ones as many times as they are bound.
+    pub fn bindings(&self) -> Vec<(Symbol, Span)> {
+        let mut names = vec![];
+        for pattern in &self.patterns {
+            pattern.bindings(&mut names);
+        }
+        names
+    }
+
+    /// Whether the branch only matches when the values bound to a name
+    /// more than once are equal.
+    pub fn compares_bindings(&self) -> bool {
+        let names = self.bindings();
+        names.iter().enumerate().any(|(index, (name, _))| names[..index].iter().any(|(earlier, _)| earlier == name))
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Expr {
+    pub kind: ExprKind,
+    pub span: Span,
 }
 
 #[derive(Clone, Debug)]
-pub enum Expr {
-    Word(String),
+pub enum ExprKind {
+    Word(Symbol),
+    IntLit(i64),
+    FloatLit(f64),
+    StringLit(Symbol),
+    CharLit(char),
     Quotation{
-        inputs: Vec<TypeExpr>,
+        /// Inferred from the body when left out. With the span of the `-`
+        /// after them.
+        inputs: Option<(Vec<TypeExpr>, Span)>,
         quotation: Vec<Expr>
     },
     Unquote,
+    /// Builds a tuple out of what each element pushes, exactly one value
+    /// each, as in `(n 1 +, "one")`.
+    Tuple(Vec<Vec<Expr>>),
+    /// `?`, standing for code yet to be written. The checker reports the
+    /// stack around it instead of accepting the program.
+    Hole,
 }
 
 #[derive(Clone, Debug)]
-pub enum Pattern {
-    All(String),
+pub struct Pattern {
+    pub kind: PatternKind,
+    pub span: Span,
+}
+
+#[derive(Clone, Debug)]
+pub enum PatternKind {
+    /// Binds the value to the name, unless it is the `_` wildcard.
+    All(Symbol),
     Constructor {
-        name: String,
+        name: Symbol,
         arguments: Vec<Pattern>,
     },
+    Tuple(Vec<Pattern>),
+    /// Matches the character only, as in `'a'`.
+    Char(char),
+}
+
+impl Pattern {
+    /// Adds the names the pattern binds to `names` with where it binds
+    /// them, in order.
+    pub fn bindings(&self, names: &mut Vec<(Symbol, Span)>) {
+        match &self.kind {
+            PatternKind::All(name) if name.as_str() == WILDCARD => (),
+            PatternKind::All(name) => names.push((*name, self.span)),
+            PatternKind::Constructor { name: _, arguments } | PatternKind::Tuple(arguments) => {
+                for pattern in arguments {
+                    pattern.bindings(names);
+                }
+            }
+            PatternKind::Char(_) => (),
+        }
+    }
 }
 
 #[derive(Debug)]
 pub struct Constructor {
-    pub name: String,
+    pub name: Symbol,
     pub argument_types: Vec<TypeExpr>,
+    /// The type the constructor builds, as in `IntLit(Int - Expr(Int))`,
+    /// if it is more specific than the declared type applied to its
+    /// parameters. Its type variables and those of the argument types are
+    /// the constructor's own.
+    pub output: Option<TypeExpr>,
+    /// From its name to its end.
+    pub span: Span,
 }
 
 #[derive(Clone, Debug)]
-pub enum TypeExpr {
-    Word(String),
+pub struct TypeExpr {
+    pub kind: TypeExprKind,
+    pub span: Span,
+}
+
+#[derive(Clone, Debug)]
+pub enum TypeExprKind {
+    Word(Symbol),
+    /// A generic data type applied to arguments, as in `List(Nat)`.
+    Application {
+        name: Symbol,
+        arguments: Vec<TypeExpr>,
+    },
     Quotation {
         inputs: Vec<TypeExpr>,
         outputs: Vec<TypeExpr>,
-    }
+        rows: Option<(Symbol, Symbol)>,
+        io: bool,
+        /// The type variables the quotation has to be polymorphic over.
+        forall: Vec<Symbol>,
+    },
+    /// As in `(Int, String)`.
+    Tuple(Vec<TypeExpr>),
 }
@@ -0,0 +1,111 @@
+//! Words taking apart and putting together paths, given as Strings, with
+//! the separators of the platform. They only look at the Strings, never at
+//! the file system.
+//!
+//! ```
+//! use sl::{engine::Engine, path::Paths};
+//!
+//! let engine = Engine::new().word_pack(&Paths);
+//! let program = engine.check("
+//! def main(- String)
+//! | = \"notes/./drafts/../todo.txt\" path_normalize path_extension
+//! ").unwrap();
+//! engine.run(&program).unwrap();
+//! ```
+
+use std::{
+    path::{Component, Path, PathBuf},
+    sync::Arc,
+};
+
+use crate::{
+    engine::{Builtin, WordPack},
+    evaluator::{RuntimeResult, Value},
+    type_checker::Type,
+};
+
+/// `path_join`, `(String String - String)`, and `path_parent`,
+/// `path_extension` and `path_normalize`, all of them `(String - String)`.
+/// What a path doesn't have is the empty String.
+pub struct Paths;
+
+impl WordPack for Paths {
+    fn builtins(&self) -> Vec<Builtin> {
+        vec![
+            builtin("path_join", 2, join, "The path below the top one followed by it, or the top one if it is absolute."),
+            builtin("path_parent", 1, parent, "A path without its last part, empty for a root or a path with one part."),
+            builtin("path_extension", 1, extension, "What follows the last `.` of the last part of a path, if something comes before it."),
+            builtin("path_normalize", 1, normalize, "A path without `.` parts nor a part together with the `..` following it."),
+        ]
+    }
+}
+
+fn builtin(name: &'static str, inputs: usize, run: fn(&mut Vec<Value>) -> RuntimeResult<()>, doc: &'static str) -> Builtin {
+    Builtin {
+        name,
+        ty: Type::Function {
+            inputs: vec![Type::string(); inputs].into(),
+            outputs: Arc::new([Type::string()]),
+            rows: None,
+            io: false,
+        },
+        run: Arc::new(run),
+        doc,
+    }
+}
+
+fn pop_string(stack: &mut Vec<Value>) -> Arc<str> {
+    let Some(Value::String(string)) = stack.pop() else {
+        unreachable!()
+    };
+    string
+}
+
+fn push_path(stack: &mut Vec<Value>, path: &Path) {
+    stack.push(Value::String(path.to_string_lossy().into()));
+}
+
+fn join(stack: &mut Vec<Value>) -> RuntimeResult<()> {
+    let right = pop_string(stack);
+    let left = pop_string(stack);
+    push_path(stack, &Path::new(&*left).join(&*right));
+    Ok(())
+}
+
+fn parent(stack: &mut Vec<Value>) -> RuntimeResult<()> {
+    let path = pop_string(stack);
+    push_path(stack, Path::new(&*path).parent().unwrap_or(Path::new("")));
+    Ok(())
+}
+
+fn extension(stack: &mut Vec<Value>) -> RuntimeResult<()> {
+    let path = pop_string(stack);
+    let extension = Path::new(&*path).extension().map_or("".into(), |extension| extension.to_string_lossy());
+    stack.push(Value::String(extension.into()));
+    Ok(())
+}
+
+fn normalize(stack: &mut Vec<Value>) -> RuntimeResult<()> {
+    let path = pop_string(stack);
+    let mut normalized = PathBuf::new();
+    for component in Path::new(&*path).components() {
+        match component {
+            Component::CurDir => (),
+            Component::ParentDir => match normalized.components().next_back() {
+                Some(Component::Normal(_)) => {
+                    normalized.pop();
+                }
+                // There is nothing above a root.
+                Some(Component::RootDir | Component::Prefix(_)) => (),
+                // Those leading a relative path can't be taken away.
+                Some(Component::ParentDir | Component::CurDir) | None => normalized.push(".."),
+            },
+            component => normalized.push(component),
+        }
+    }
+    if normalized.as_os_str().is_empty() {
+        normalized.push(".");
+    }
+    push_path(stack, &normalized);
+    Ok(())
+}
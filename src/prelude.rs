@@ -0,0 +1,143 @@
+//! The words every program can use without declaring them, provided by
+//! [`Engine::new`](crate::engine::Engine::new).
+
+use std::sync::Arc;
+
+use crate::{
+    engine::{Builtin, WordPack},
+    evaluator::{RuntimeError, RuntimeResult, Value},
+    symbol::Symbol,
+    type_checker::Type,
+};
+
+/// Integer arithmetic: `+ - * / mod`, all of them `(Int Int - Int)` and
+/// the last two failing with [`RuntimeError::DivisionByZero`] on zero,
+/// comparisons: `= < > <= >=`, all of them `(Int Int - Bool)`, float
+/// arithmetic: `+. -. *. /.`, all of them `(Float Float - Float)`,
+/// `to_float` and `to_int` converting between the two, `print`,
+/// `(String - !io)`, and `absurd`, `(Void - a)`.
+pub struct Prelude;
+
+impl WordPack for Prelude {
+    fn builtins(&self) -> Vec<Builtin> {
+        let int = || [Type::int(), Type::int()];
+        let float = || [Type::float(), Type::float()];
+        vec![
+            builtin("+", int(), Type::int(), |stack| int_arithmetic(stack, i64::wrapping_add), "Adds two Ints, wrapping around on overflow."),
+            builtin("-", int(), Type::int(), |stack| int_arithmetic(stack, i64::wrapping_sub), "Subtracts the top Int from the one below it."),
+            builtin("*", int(), Type::int(), |stack| int_arithmetic(stack, i64::wrapping_mul), "Multiplies two Ints, wrapping around on overflow."),
+            builtin("/", int(), Type::int(), |stack| int_division(stack, i64::wrapping_div), "Divides the Int below the top one by it, rounding towards zero. Fails when it is zero."),
+            builtin("mod", int(), Type::int(), |stack| int_division(stack, i64::wrapping_rem), "The remainder of `/`, with the sign of the dividend. Fails when the divisor is zero."),
+            builtin("=", int(), Type::bool(), |stack| comparison(stack, i64::eq), "Whether two Ints are equal."),
+            builtin("<", int(), Type::bool(), |stack| comparison(stack, i64::lt), "Whether the Int below the top one is less than it."),
+            builtin(">", int(), Type::bool(), |stack| comparison(stack, i64::gt), "Whether the Int below the top one is greater than it."),
+            builtin("<=", int(), Type::bool(), |stack| comparison(stack, i64::le), "Whether the Int below the top one is at most it."),
+            builtin(">=", int(), Type::bool(), |stack| comparison(stack, i64::ge), "Whether the Int below the top one is at least it."),
+            builtin("+.", float(), Type::float(), |stack| float_arithmetic(stack, |left, right| left + right), "Adds two Floats."),
+            builtin("-.", float(), Type::float(), |stack| float_arithmetic(stack, |left, right| left - right), "Subtracts the top Float from the one below it."),
+            builtin("*.", float(), Type::float(), |stack| float_arithmetic(stack, |left, right| left * right), "Multiplies two Floats."),
+            builtin("/.", float(), Type::float(), |stack| float_arithmetic(stack, |left, right| left / right), "Divides the Float below the top one by it."),
+            builtin("to_float", [Type::int()], Type::float(), to_float, "The Float closest to an Int."),
+            builtin("to_int", [Type::float()], Type::int(), to_int, "A Float rounded towards zero, saturating at the bounds of Int."),
+            builtin("absurd", [Type::void()], Type::Variable(Symbol::intern("a")), absurd, "Anything, from a Void there can't be."),
+            Builtin {
+                name: "print",
+                ty: Type::Function {
+                    inputs: Arc::new([Type::string()]),
+                    outputs: Arc::new([]),
+                    rows: None,
+                    io: true,
+                },
+                run: Arc::new(print),
+                doc: "Writes a String and a newline to stdout.",
+            },
+        ]
+    }
+}
+
+fn builtin<const N: usize>(
+    name: &'static str,
+    inputs: [Type; N],
+    output: Type,
+    run: fn(&mut Vec<Value>) -> RuntimeResult<()>,
+    doc: &'static str,
+) -> Builtin {
+    Builtin {
+        name,
+        ty: Type::Function {
+            inputs: Arc::new(inputs),
+            outputs: Arc::new([output]),
+            rows: None,
+            io: false,
+        },
+        run: Arc::new(run),
+        doc,
+    }
+}
+
+/// Pops the two Ints on top of the stack, the lower one first.
+fn int_operands(stack: &mut Vec<Value>) -> (i64, i64) {
+    let (Some(Value::Int(right)), Some(Value::Int(left))) = (stack.pop(), stack.pop()) else {
+        unreachable!()
+    };
+    (left, right)
+}
+
+fn int_arithmetic(stack: &mut Vec<Value>, operation: fn(i64, i64) -> i64) -> RuntimeResult<()> {
+    let (left, right) = int_operands(stack);
+    stack.push(Value::Int(operation(left, right)));
+    Ok(())
+}
+
+/// Like `int_arithmetic`, failing instead of running `operation` with a
+/// zero divisor.
+fn int_division(stack: &mut Vec<Value>, operation: fn(i64, i64) -> i64) -> RuntimeResult<()> {
+    let (left, right) = int_operands(stack);
+    if right == 0 {
+        return Err(RuntimeError::DivisionByZero);
+    }
+    stack.push(Value::Int(operation(left, right)));
+    Ok(())
+}
+
+fn comparison(stack: &mut Vec<Value>, comparison: fn(&i64, &i64) -> bool) -> RuntimeResult<()> {
+    let (left, right) = int_operands(stack);
+    stack.push(Value::bool(comparison(&left, &right)));
+    Ok(())
+}
+
+fn float_arithmetic(stack: &mut Vec<Value>, operation: fn(f64, f64) -> f64) -> RuntimeResult<()> {
+    let (Some(Value::Float(right)), Some(Value::Float(left))) = (stack.pop(), stack.pop()) else {
+        unreachable!()
+    };
+    stack.push(Value::Float(operation(left, right)));
+    Ok(())
+}
+
+fn to_float(stack: &mut Vec<Value>) -> RuntimeResult<()> {
+    let Some(Value::Int(integer)) = stack.pop() else {
+        unreachable!()
+    };
+    stack.push(Value::Float(integer as f64));
+    Ok(())
+}
+
+fn to_int(stack: &mut Vec<Value>) -> RuntimeResult<()> {
+    let Some(Value::Float(float)) = stack.pop() else {
+        unreachable!()
+    };
+    stack.push(Value::Int(float as i64));
+    Ok(())
+}
+
+fn absurd(_: &mut Vec<Value>) -> RuntimeResult<()> {
+    unreachable!()
+}
+
+fn print(stack: &mut Vec<Value>) -> RuntimeResult<()> {
+    let Some(Value::String(string)) = stack.pop() else {
+        unreachable!()
+    };
+    println!("{string}");
+    Ok(())
+}
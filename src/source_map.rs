@@ -0,0 +1,124 @@
+//! Ownership of source files and mapping of byte offsets back to them.
+//!
+//! ```
+//! use sl::source_map::{Location, SourceMap, Span};
+//!
+//! let mut source_map = SourceMap::new();
+//! let file = source_map.add("main.sl", "def main(- Nat)\n| = Zero Succ\n");
+//!
+//! assert_eq!(source_map.location(file, 20), Location { line: 2, column: 5 });
+//! assert_eq!(
+//!     source_map.snippet(file, Span::new(20, 24)),
+//!     " --> main.sl:2:5\n  |\n2 | | = Zero Succ\n  |     ^^^^",
+//! );
+//! ```
+
+use std::fmt::Write;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct FileId(u32);
+
+/// A byte range into the source of a single file. Programs built without
+/// source have the empty span at its start everywhere, the default one.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Span {
+    pub fn new(start: usize, end: usize) -> Self {
+        Self { start, end }
+    }
+
+    /// The smallest span covering both `self` and `other`.
+    pub fn to(self, other: Span) -> Self {
+        Self::new(self.start.min(other.start), self.end.max(other.end))
+    }
+}
+
+/// A 1-based line and column, the column counted in characters.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Location {
+    pub line: usize,
+    pub column: usize,
+}
+
+struct SourceFile {
+    name: String,
+    source: String,
+    line_starts: Vec<usize>,
+}
+
+#[derive(Default)]
+pub struct SourceMap {
+    files: Vec<SourceFile>,
+}
+
+impl SourceMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add(&mut self, name: impl Into<String>, source: impl Into<String>) -> FileId {
+        let source = source.into();
+        let line_starts = std::iter::once(0)
+            .chain(source.match_indices('\n').map(|(offset, _)| offset + 1))
+            .collect();
+
+        let file = FileId(self.files.len() as u32);
+        self.files.push(SourceFile {
+            name: name.into(),
+            source,
+            line_starts,
+        });
+        file
+    }
+
+    pub fn name(&self, file: FileId) -> &str {
+        &self.files[file.0 as usize].name
+    }
+
+    pub fn source(&self, file: FileId) -> &str {
+        &self.files[file.0 as usize].source
+    }
+
+    pub fn location(&self, file: FileId, offset: usize) -> Location {
+        let SourceFile { source, line_starts, .. } = &self.files[file.0 as usize];
+        let line = line_starts.partition_point(|&start| start <= offset) - 1;
+        let column = source[line_starts[line]..offset].chars().count();
+        Location {
+            line: line + 1,
+            column: column + 1,
+        }
+    }
+
+    /// The text of the 1-based `line`, without its line terminator.
+    pub fn line(&self, file: FileId, line: usize) -> &str {
+        let SourceFile { source, line_starts, .. } = &self.files[file.0 as usize];
+        let start = line_starts[line - 1];
+        let end = line_starts.get(line).map_or(source.len(), |&end| end);
+        source[start..end].trim_end_matches(['\n', '\r'])
+    }
+
+    /// Renders the first line `span` covers with the covered part underlined,
+    /// headed by its `file:line:column`.
+    pub fn snippet(&self, file: FileId, span: Span) -> String {
+        let Location { line, column } = self.location(file, span.start);
+        let text = self.line(file, line);
+        let width = text
+            .chars()
+            .skip(column - 1)
+            .take(self.source(file)[span.start..span.end].chars().count())
+            .count()
+            .max(1);
+
+        let gutter = " ".repeat(line.to_string().len());
+        let mut snippet = String::new();
+        writeln!(snippet, "{gutter}--> {}:{line}:{column}", self.name(file)).unwrap();
+        writeln!(snippet, "{gutter} |").unwrap();
+        writeln!(snippet, "{line} | {text}").unwrap();
+        write!(snippet, "{gutter} | {}{}", " ".repeat(column - 1), "^".repeat(width)).unwrap();
+        snippet
+    }
+}
@@ -1,28 +1,203 @@
-use std::{iter::Peekable, str::Chars};
+//! Lexer turning source text into [`Token`]s.
 
-pub struct Tokens<'chars> {
-    chars: Peekable<Chars<'chars>>,
+use std::{iter::Peekable, str::CharIndices};
+
+use crate::{source_map::Span, symbol::Symbol};
+
+mod xid;
+
+#[derive(Clone)]
+pub struct Tokens<'source> {
+    source: &'source str,
+    chars: Peekable<CharIndices<'source>>,
+    /// Whether the last token was a word or keyword.
+    after_word: bool,
+    span: Span,
+    /// The opening `{-` of a block comment the source ends in.
+    unterminated_comment: Option<Span>,
+    /// The lines of the doc comments right before the last token.
+    doc: Vec<&'source str>,
 }
 
-impl<'chars> Tokens<'chars> {
-    pub fn new(source: &'chars str) -> Self {
+impl<'source> Tokens<'source> {
+    pub fn new(source: &'source str) -> Self {
         Self {
-            chars: source.chars().peekable(),
+            source,
+            chars: source.char_indices().peekable(),
+            after_word: false,
+            span: Span::new(0, 0),
+            unterminated_comment: None,
+            doc: vec![],
+        }
+    }
+
+    /// The text of the `---` doc comments between the token returned last
+    /// and the one before it, a line each without the `---` and the space
+    /// after it.
+    pub fn doc(&self) -> Option<String> {
+        (!self.doc.is_empty()).then(|| self.doc.join("\n"))
+    }
+
+    /// The opening `{-` of the block comment the source ends in, if it
+    /// does, which the tokens stop at.
+    pub fn unterminated_comment(&self) -> Option<Span> {
+        self.unterminated_comment
+    }
+
+    /// The span of the token returned last, or the empty one at the end of
+    /// the source once there are none left.
+    pub fn span(&self) -> Span {
+        self.span
+    }
+
+    fn offset(&mut self) -> usize {
+        self.chars.peek().map_or(self.source.len(), |(offset, _)| *offset)
+    }
+
+    /// Skips whitespace, line comments starting with `#` or `--` and block
+    /// comments between `{-` and `-}`, which nest. The lines of the doc
+    /// comments starting with exactly `---` are kept.
+    fn skip_whitespace_and_comments(&mut self) {
+        self.doc.clear();
+        while let Some(&(offset, ch)) = self.chars.peek() {
+            let rest = &self.source[offset..];
+            if ch.is_whitespace() {
+                self.chars.next();
+            } else if rest.starts_with("---") && !rest.starts_with("----") {
+                let line = rest[3..].lines().next().unwrap_or_default();
+                self.doc.push(line.strip_prefix(' ').unwrap_or(line));
+                while !matches!(self.chars.next(), Some((_, '\n')) | None) {}
+            } else if (ch == '#' && !rest.starts_with("#[")) || rest.starts_with("--") {
+                while !matches!(self.chars.next(), Some((_, '\n')) | None) {}
+            } else if rest.starts_with("{-") {
+                self.block_comment(offset);
+            } else {
+                break;
+            }
+        }
+    }
+
+    fn block_comment(&mut self, start: usize) {
+        let mut depth = 0;
+        while let Some((offset, _)) = self.chars.next() {
+            let rest = &self.source[offset..];
+            if rest.starts_with("{-") {
+                depth += 1;
+            } else if rest.starts_with("-}") {
+                depth -= 1;
+            } else {
+                continue;
+            }
+            self.chars.next();
+            if depth == 0 {
+                return;
+            }
+        }
+        self.unterminated_comment = Some(Span::new(start, start + 2));
+    }
+
+    /// Lexes a string literal. What a `\` escapes is given by [`escape`].
+    fn string(&mut self) -> Token {
+        self.chars.next();
+        let mut string = String::new();
+        let mut invalid_escape = false;
+        loop {
+            match self.chars.next() {
+                Some((_, '"')) => break,
+                Some((_, '\\')) => match self.chars.next() {
+                    Some((_, ch)) => match escape(ch) {
+                        Some(ch) => string.push(ch),
+                        // Lexed to the end anyway, so that what follows the
+                        // string isn't read as part of it.
+                        None => invalid_escape = true,
+                    },
+                    None => return Token::UnterminatedString,
+                },
+                Some((_, ch)) => string.push(ch),
+                None => return Token::UnterminatedString,
+            }
+        }
+        if invalid_escape {
+            return Token::InvalidEscape;
+        }
+        Token::String(Symbol::intern(&string))
+    }
+
+    /// Lexes a character literal, exactly one character or escape between
+    /// `'`s, as in `'a'` and `'\n'`.
+    fn char(&mut self) -> Token {
+        self.chars.next();
+        let ch = match self.chars.next() {
+            Some((_, '\\')) => match self.chars.next() {
+                Some((_, ch)) => escape(ch).ok_or(Token::InvalidEscape),
+                None => Err(Token::InvalidChar),
+            },
+            Some((_, ch)) if ch != '\'' && ch != '\n' => Ok(ch),
+            _ => Err(Token::InvalidChar),
+        };
+        match (ch, self.chars.next_if(|(_, ch)| *ch == '\'')) {
+            (Ok(ch), Some(_)) => Token::Char(ch),
+            (Err(error), Some(_)) => error,
+            (_, None) => Token::InvalidChar,
+        }
+    }
+
+    /// Lexes a `-` right before the digits of an integer or float literal
+    /// as part of it, as in `-3` and `-0.5`. Anything else starting with
+    /// `-` is left alone.
+    fn negative_number(&mut self) -> Option<Token> {
+        let start = self.offset();
+        let rest = &self.source[start + 1..];
+        let end = rest
+            .char_indices()
+            .find(|(offset, ch)| ch.is_whitespace() || PUNCTUATION.contains(ch) || rest[*offset..].starts_with("{-"))
+            .map_or(rest.len(), |(offset, _)| offset);
+        let number = &rest[..end];
+        let token = if !number.is_empty() && number.bytes().all(|byte| byte.is_ascii_digit()) {
+            // Parsed with the sign for the most negative `Int` to fit.
+            self.source[start..start + 1 + end].parse().map_or(Token::IntegerOutOfRange, Token::Integer)
+        } else if is_float(number) {
+            Token::Float(-number.parse::<f64>().unwrap())
+        } else {
+            return None;
+        };
+        while self.offset() < start + 1 + end {
+            self.chars.next();
         }
+        self.after_word = true;
+        Some(token)
     }
 
     fn word_or_keyword(&mut self) -> Token {
-        let mut word = String::new();
-        while let Some(ch) = self
+        // Words are sliced out of the source, so interning one that was
+        // seen before doesn't allocate.
+        self.after_word = true;
+        let start = self.offset();
+        let source = self.source;
+        while self
             .chars
-            .next_if(|ch| !(ch.is_whitespace() || PUNCTUATION.contains(ch)))
-        {
-            word.push(ch);
+            .next_if(|(offset, ch)| !(ch.is_whitespace() || PUNCTUATION.contains(ch) || source[*offset..].starts_with("{-")))
+            .is_some()
+        {}
+        // `=` is punctuation, but not at the end of these comparisons.
+        if matches!(&self.source[start..self.offset()], "<" | ">") {
+            self.chars.next_if(|(_, ch)| *ch == '=');
         }
-        match word.as_str() {
+        match &self.source[start..self.offset()] {
             "data" => Token::KeywordData,
             "def" => Token::KeywordDef,
-            _ => Token::Word(word),
+            "type" => Token::KeywordType,
+            "trait" => Token::KeywordTrait,
+            "impl" => Token::KeywordImpl,
+            "linear" => Token::KeywordLinear,
+            "total" => Token::KeywordTotal,
+            "derives" => Token::KeywordDerives,
+            word if word.bytes().all(|byte| byte.is_ascii_digit()) => {
+                word.parse().map_or(Token::IntegerOutOfRange, Token::Integer)
+            }
+            word if is_float(word) => Token::Float(word.parse().unwrap()),
+            word if !is_name(word) => Token::InvalidName,
+            word => Token::Word(Symbol::intern(word)),
         }
     }
 }
@@ -31,63 +206,140 @@ impl Iterator for Tokens<'_> {
     type Item = Token;
 
     fn next(&mut self) -> Option<Self::Item> {
-        let Some(ch) = self.chars.peek() else {
-            return None;
-        };
+        let start = self.offset();
+        self.skip_whitespace_and_comments();
+        let after_word = std::mem::take(&mut self.after_word) && self.offset() == start;
+        let start = self.offset();
+        self.span = Span::new(start, start);
+        let (_, ch) = self.chars.peek()?;
 
-        if ch.is_whitespace() {
-            self.chars.next();
-            self.next()
-        } else if ch == &'#' {
-            while !matches!(self.chars.next(), Some('\n') | None) {}
-            self.next()
-        } else {
-            Some(match ch {
-                '(' => {
-                    self.chars.next();
-                    Token::OpeningParenthesis
-                }
-                ')' => {
-                    self.chars.next();
-                    Token::ClosingParenthesis
-                }
-                '[' => {
-                    self.chars.next();
-                    Token::OpeningBracket
-                }
-                ']' => {
-                    self.chars.next();
-                    Token::ClosingBracket
-                }
-                '=' => {
-                    self.chars.next();
-                    Token::EqualsSign
-                }
-                '&' => {
-                    self.chars.next();
-                    Token::Ampersand
-                }
-                '-' => {
-                    self.chars.next();
-                    Token::Minus
-                }
-                '|' => {
+        let token = match ch {
+            // Right after a word it opens the word's arguments, as in
+            // `Succ(n)`, anywhere else it can only open a tuple.
+            '(' if after_word => {
+                self.chars.next();
+                Token::OpeningParenthesis
+            }
+            '(' => {
+                self.chars.next();
+                Token::DetachedOpeningParenthesis
+            }
+            ')' => {
+                self.chars.next();
+                Token::ClosingParenthesis
+            }
+            '[' => {
+                self.chars.next();
+                Token::OpeningBracket
+            }
+            ']' => {
+                self.chars.next();
+                Token::ClosingBracket
+            }
+            '=' => {
+                self.chars.next();
+                Token::EqualsSign
+            }
+            '&' => {
+                self.chars.next();
+                Token::Ampersand
+            }
+            '-' => match self.negative_number() {
+                Some(token) => token,
+                None => {
                     self.chars.next();
-                    Token::Bar
+                    // Float subtraction, the only word starting with `-`.
+                    match self.chars.next_if(|(_, ch)| *ch == '.') {
+                        Some(_) => Token::Word(Symbol::intern("-.")),
+                        None => Token::Minus,
+                    }
                 }
-                _ => self.word_or_keyword(),
-            })
-        }
+            },
+            '|' => {
+                self.chars.next();
+                Token::Bar
+            }
+            ',' => {
+                self.chars.next();
+                Token::Comma
+            }
+            '#' => {
+                // Only reached for `#[`, any other `#` starts a comment.
+                self.chars.next();
+                self.chars.next();
+                Token::AttributeStart
+            }
+            '"' => self.string(),
+            '\'' => self.char(),
+            _ => self.word_or_keyword(),
+        };
+        self.span.end = self.offset();
+        Some(token)
+    }
+}
+
+/// The character `\` followed by `ch` stands for in a string or character
+/// literal, if it is an escape: `\n`, `\t`, `\r`, `\0`, `\"`, `\'` or `\\`.
+fn escape(ch: char) -> Option<char> {
+    match ch {
+        'n' => Some('\n'),
+        't' => Some('\t'),
+        'r' => Some('\r'),
+        '0' => Some('\0'),
+        '"' | '\'' | '\\' => Some(ch),
+        _ => None,
     }
 }
 
-const PUNCTUATION: [char; 8] = ['[', ']', '(', ')', '=', '&', '-', '|'];
-#[derive(Debug, PartialEq, Eq)]
+/// Whether `word` is digits, a `.` and digits again, as in `3.14`.
+fn is_float(word: &str) -> bool {
+    word.split_once('.').is_some_and(|(whole, fraction)| {
+        [whole, fraction]
+            .iter()
+            .all(|digits| !digits.is_empty() && digits.bytes().all(|byte| byte.is_ascii_digit()))
+    })
+}
+
+/// Whether `word` is a name. Those starting with a letter or `_`, as in
+/// `Succ`, `数` or `हिन्दी`, are Unicode identifiers: only letters, digits,
+/// combining marks and connectors like `_` follow. Those starting with a
+/// symbol, as in `+`, `→` or `!io`, can have anything. Only numbers start
+/// with a digit.
+fn is_name(word: &str) -> bool {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(ch) if xid::is_xid_start(ch) || ch == '_' => chars.all(xid::is_xid_continue),
+        Some(ch) => !ch.is_numeric(),
+        None => false,
+    }
+}
+
+const PUNCTUATION: [char; 10] = ['[', ']', '(', ')', '=', '&', '-', '|', ',', '"'];
+#[derive(Clone, Debug, PartialEq)]
 pub enum Token {
-    Word(String),
+    Word(Symbol),
+    /// A word that isn't a name, as in `1st` or `x+`.
+    InvalidName,
+    /// With its escapes replaced by what they stand for.
+    String(Symbol),
+    UnterminatedString,
+    InvalidEscape,
+    Char(char),
+    /// Empty, unterminated or with more than one character, as in `'ab'`.
+    InvalidChar,
+    Integer(i64),
+    IntegerOutOfRange,
+    Float(f64),
     KeywordData,
     KeywordDef,
+    KeywordType,
+    KeywordTrait,
+    KeywordImpl,
+    KeywordLinear,
+    KeywordTotal,
+    KeywordDerives,
     OpeningParenthesis,
+    DetachedOpeningParenthesis,
     ClosingParenthesis,
     OpeningBracket,
     ClosingBracket,
@@ -95,4 +347,23 @@ pub enum Token {
     Ampersand,
     Minus,
     Bar,
+    Comma,
+    AttributeStart,
+}
+
+impl Token {
+    /// Whether a top level can start with the token, attributes included.
+    pub fn starts_top_level(&self) -> bool {
+        matches!(
+            self,
+            Token::KeywordData
+                | Token::KeywordLinear
+                | Token::KeywordDef
+                | Token::KeywordTotal
+                | Token::KeywordType
+                | Token::KeywordTrait
+                | Token::KeywordImpl
+                | Token::AttributeStart
+        )
+    }
 }
@@ -1,246 +1,3295 @@
-use std::collections::{HashMap, HashSet};
+//! Static checking of data declarations and def signatures against their branches.
 
-use crate::parser::{Branch, Constructor, Expr, Pattern, TopLevel, TypeExpr};
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+    sync::Arc,
+};
+
+use self::{
+    unify::{Bound, Folder, Generalization, Instantiation, Unifier},
+    usefulness::Witness,
+};
+use crate::{
+    diagnostics::{catalog::Catalog, Diagnostic, DiagnosticSink},
+    parser::{
+        Branch, Constructor, Expr, ExprKind, Method, MethodImpl, Pattern, PatternKind, Signature, TopLevel, TypeExpr,
+        TypeExprKind, BOOL_CONSTRUCTORS, WILDCARD, is_variable_name,
+    },
+    source_map::Span,
+    symbol::Symbol,
+};
+
+mod unify;
+mod usefulness;
+
+/// Types built into the language rather than declared with `data`. They
+/// have no constructors, values of them come from literals and builtins.
+const PRIMITIVE_TYPES: [&str; 4] = ["Int", "Float", "String", "Char"];
 
 pub struct TypeChecker {
-    types: HashSet<String>,
-    ctx: HashMap<String, Type>,
-    locals: Vec<(String, Type)>
+    /// Every data type with the names of its constructors in declaration order.
+    types: HashMap<Symbol, Vec<Symbol>>,
+    /// How many type parameters every type takes, primitive ones and
+    /// aliases included.
+    arities: HashMap<Symbol, usize>,
+    /// What every type alias stands for.
+    aliases: HashMap<Symbol, Type>,
+    ctx: HashMap<Symbol, Type>,
+    constructors: HashMap<Symbol, ConstructorInfo>,
+    /// The data types declared `linear`.
+    linear_types: HashSet<Symbol>,
+    /// Every trait with the names of its methods in declaration order.
+    traits: HashMap<Symbol, TraitInfo>,
+    methods: HashMap<Symbol, MethodInfo>,
+    /// Every trait with the name of a type it is implemented for.
+    impls: HashSet<(Symbol, Symbol)>,
+    /// The word every method runs for each type its trait is implemented for.
+    implementations: HashMap<Symbol, HashMap<Symbol, Symbol>>,
+    /// The impls derived with `derives`, which only hold for the arguments
+    /// of their type that implement the trait too.
+    derived: HashMap<(Symbol, Symbol), Derivable>,
+    /// Where every def is declared among them and whether it is total.
+    defs: HashMap<Symbol, DefInfo>,
+    /// What the defs sharing a name were renamed to, in source order.
+    overloads: HashMap<Symbol, Vec<Symbol>>,
+    /// Where every type declared in the source is declared, and where every
+    /// global word defined in it is first defined, for the errors about
+    /// defining them again.
+    type_spans: HashMap<Symbol, Span>,
+    word_spans: HashMap<Symbol, Span>,
+    recursion_limit: usize,
+    forbid_shadowing: bool,
+    /// The def whose stack is traced after every expression.
+    traced: Option<Symbol>,
+    /// What the pass running is checking, where an error it finds is
+    /// reported.
+    span: Span,
+    /// Everything reported by the passes so far.
+    diagnostics: Vec<Diagnostic>,
 }
 
 impl TypeChecker {
     pub fn new() -> Self {
-        Self {
-            types: HashSet::new(),
-            ctx: HashMap::new(),
-            locals: vec![],
+        Self::builder().build()
+    }
+
+    pub fn builder() -> TypeCheckerBuilder {
+        TypeCheckerBuilder {
+            words: vec![],
+            recursion_limit: 256,
+            forbid_shadowing: false,
+            traced: None,
         }
     }
 
-    fn type_expr(&self, type_expr: &TypeExpr) -> Type {
-        match type_expr {
-            TypeExpr::Word(word) => Type::Basic(word.clone()),
-            TypeExpr::Quotation { inputs, outputs } => Type::Quotation {
-                inputs: inputs.iter().map(|ty| self.type_expr(ty)).collect(),
-                outputs: outputs.iter().map(|ty| self.type_expr(ty)).collect()
-            },
+    /// Converts `type_expr`, reading the words in `parameters` as type
+    /// variables. Any other word has to name a data type, applied to as
+    /// many arguments as it has parameters.
+    fn type_expr(&self, type_expr: &TypeExpr, parameters: &[Symbol]) -> TypeCheckResult<Type> {
+        match &type_expr.kind {
+            TypeExprKind::Word(word) if parameters.contains(word) => Ok(Type::Variable(*word)),
+            TypeExprKind::Word(word) if self.aliases.contains_key(word) => Ok(self.aliases[word].clone()),
+            TypeExprKind::Word(word) => {
+                self.check_arity(*word, 0)?;
+                Ok(Type::basic(*word))
+            }
+            // Type variables stand for types, which can't be applied.
+            TypeExprKind::Application { name, arguments } if parameters.contains(name) => Err(TypeCheckError::KindMismatch {
+                name: *name,
+                expected: 0,
+                found: arguments.len(),
+            }),
+            TypeExprKind::Application { name, arguments } => {
+                self.check_arity(*name, arguments.len())?;
+                Ok(Type::Basic {
+                    name: *name,
+                    arguments: self.type_exprs(arguments, parameters)?,
+                })
+            }
+            TypeExprKind::Quotation { inputs, outputs, rows, io, forall } => {
+                let parameters: Vec<_> = parameters.iter().chain(forall).copied().collect();
+                Ok(Type::Quotation {
+                    inputs: self.type_exprs(inputs, &parameters)?,
+                    outputs: self.type_exprs(outputs, &parameters)?,
+                    rows: rows.map(|(input_row, output_row)| (Row::Variable(input_row), Row::Variable(output_row))),
+                    io: *io,
+                    forall: forall.iter().copied().collect(),
+                })
+            }
+            TypeExprKind::Tuple(elements) => Ok(Type::Tuple(self.type_exprs(elements, parameters)?)),
+        }
+    }
+
+    fn type_exprs(&self, type_exprs: &[TypeExpr], parameters: &[Symbol]) -> TypeCheckResult<Arc<[Type]>> {
+        type_exprs.iter().map(|ty| self.type_expr(ty, parameters)).collect()
+    }
+
+    fn check_arity(&self, name: Symbol, arguments: usize) -> TypeCheckResult<()> {
+        match self.arities.get(&name) {
+            None => Err(TypeCheckError::UnknownType(name)),
+            Some(&arity) if arity != arguments => Err(TypeCheckError::KindMismatch {
+                name,
+                expected: arity,
+                found: arguments,
+            }),
+            Some(_) => Ok(()),
         }
     }
 
-    fn resolve_word(&self, word: &str) -> TypeCheckResult<Type> {
-        match self.locals.iter().rev().find(|(name, _)| name == word) {
-            Some((_, ty)) => Ok(ty.clone()),
-            None => match self.ctx.get(word) {
-                Some(ty) => Ok(ty.clone()),
-                None => Err(TypeCheckError::UnboundSymbol),
+    /// Whether values of `ty` have to be used exactly once. Type variables
+    /// never stand for such types, the words generic over them could
+    /// duplicate or drop their values.
+    fn is_linear(&self, ty: &Type) -> bool {
+        match ty {
+            Type::Basic { name, arguments } => {
+                self.linear_types.contains(name) || arguments.iter().any(|ty| self.is_linear(ty))
             }
+            Type::Tuple(elements) => elements.iter().any(|ty| self.is_linear(ty)),
+            _ => false,
+        }
+    }
+
+    /// What the global word `name` already is, as found when defining it
+    /// again as a `kind`.
+    fn already_defined(&self, name: Symbol, kind: SymbolKind) -> TypeCheckError {
+        let previous = if self.constructors.contains_key(&name) {
+            SymbolKind::Constructor
+        } else if self.methods.contains_key(&name) {
+            SymbolKind::Method
+        } else if self.defs.contains_key(&name) || self.overloads.contains_key(&name) {
+            SymbolKind::Def
+        } else {
+            SymbolKind::Builtin
+        };
+        TypeCheckError::SymbolAlreadyDefined { name, kind, previous, previous_span: self.word_spans.get(&name).copied() }
+    }
+
+    fn type_already_defined(&self, name: Symbol) -> TypeCheckError {
+        TypeCheckError::TypeAlreadyDefined { name, previous_span: self.type_spans.get(&name).copied() }
+    }
+
+    /// Reports `error` as found at what the pass running is checking. The
+    /// pass goes on with the next thing it checks.
+    fn report(&mut self, error: TypeCheckError) {
+        self.diagnostics.push(Diagnostic::new(error, self.span));
+    }
+
+    fn has_errors(&self) -> bool {
+        self.diagnostics.iter().any(Diagnostic::is_error)
+    }
+
+    /// The type of a def or method with `signature`, where lowercase words
+    /// are type variables the same way lowercase words in patterns are
+    /// bindings.
+    fn signature_type(&self, Signature { inputs, outputs, rows, io }: &Signature) -> TypeCheckResult<Type> {
+        let mut variables = vec![];
+        for ty in inputs.iter().chain(outputs) {
+            type_variables(ty, &mut variables);
         }
+        Ok(Type::Function {
+            inputs: self.type_exprs(inputs, &variables)?,
+            outputs: self.type_exprs(outputs, &variables)?,
+            rows: rows.map(|(input_row, output_row)| (Row::Variable(input_row), Row::Variable(output_row))),
+            io: *io,
+        })
     }
 
-    fn collect_types(&mut self, top_levels: &[TopLevel]) -> TypeCheckResult<()> {
+    /// Declares a data type without parameters or constructor arguments
+    /// the way `collect_types` and `collect_constructors` would, for the
+    /// ones built into the language.
+    fn builtin_data(&mut self, ty: Type, constructors: &[&str]) {
+        let Type::Basic { name: type_name, .. } = ty else {
+            unreachable!()
+        };
+        self.arities.insert(type_name, 0);
+        for constructor in constructors {
+            let name = Symbol::intern(constructor);
+            self.ctx.insert(name, Type::Function {
+                inputs: Arc::new([]), outputs: Arc::new([ty.clone()]), rows: None, io: false,
+            });
+            self.constructors.insert(name, ConstructorInfo {
+                type_name,
+                parameters: Arc::new([]),
+                inputs: Arc::new([]),
+                output: Arc::new([]),
+            });
+        }
+        self.types.insert(type_name, constructors.iter().map(|name| Symbol::intern(name)).collect());
+    }
+
+    fn collect_types(&mut self, top_levels: &[TopLevel]) {
         for top_level in top_levels {
-            if let TopLevel::Data { name, .. } = top_level {
-                if !self.types.insert(name.clone()) {
-                    return Err(TypeCheckError::TypeAlreadyDefined);
+            self.span = top_level.span();
+            match top_level {
+                TopLevel::Data { name, parameters, linear, .. } => {
+                    if self.arities.insert(*name, parameters.len()).is_some() {
+                        let error = self.type_already_defined(*name);
+                        self.report(error);
+                        continue;
+                    }
+                    self.type_spans.insert(*name, self.span);
+                    self.types.insert(*name, vec![]);
+                    if *linear {
+                        self.linear_types.insert(*name);
+                    }
+                }
+                TopLevel::Type { name, .. } => {
+                    if self.arities.insert(*name, 0).is_some() {
+                        let error = self.type_already_defined(*name);
+                        self.report(error);
+                        continue;
+                    }
+                    self.type_spans.insert(*name, self.span);
                 }
+                TopLevel::Def { .. } | TopLevel::Trait { .. } | TopLevel::Impl { .. } => (),
             }
         }
-        Ok(())
     }
 
-    fn collect_constructors(&mut self, top_levels: &[TopLevel]) -> TypeCheckResult<()> {
+    /// Expands every type alias into the type it stands for, so the rest of
+    /// the checker never sees them. An alias is expanded once the aliases it
+    /// mentions are, so those can't be cyclic.
+    fn collect_aliases(&mut self, top_levels: &[TopLevel]) {
+        let mut pending: Vec<_> = top_levels
+            .iter()
+            .filter_map(|top_level| match top_level {
+                TopLevel::Type { name, definition, span } => Some((*name, definition, *span)),
+                _ => None,
+            })
+            .collect();
+        let mut unexpanded: HashSet<_> = pending.iter().map(|(name, _, _)| *name).collect();
+
+        while !pending.is_empty() {
+            let count = pending.len();
+            let mut blocked = vec![];
+            for (name, definition, span) in pending {
+                let mut words = vec![];
+                type_words(definition, &mut words);
+                if words.iter().any(|word| unexpanded.contains(word)) {
+                    blocked.push((name, definition, span));
+                    continue;
+                }
+
+                self.span = span;
+                match self.type_expr(definition, &[]) {
+                    Ok(ty) => {
+                        self.aliases.insert(name, ty);
+                    }
+                    Err(error) => self.report(error),
+                }
+                unexpanded.remove(&name);
+            }
+
+            if blocked.len() == count {
+                let (name, _, span) = blocked[0];
+                self.span = span;
+                self.report(TypeCheckError::CyclicTypeAlias(name));
+                break;
+            }
+            pending = blocked;
+        }
+    }
+
+    fn collect_constructors(&mut self, top_levels: &[TopLevel]) {
         for top_level in top_levels {
-            if let TopLevel::Data { name: type_name, constructors } = top_level {
-                for Constructor { name, argument_types } in constructors {
-                    let inputs = argument_types
-                        .iter()
-                        .map(|argument_type| self.type_expr(argument_type))
-                        .collect();
-                    if self.ctx.insert(name.clone(), Type::Function {
-                        inputs, outputs: vec![Type::Basic(type_name.clone())],
-                    }).is_some() {
-                        return Err(TypeCheckError::SymbolAlreadyDefined)
+            if let TopLevel::Data { name: type_name, parameters, constructors, linear, .. } = top_level {
+                for constructor in constructors {
+                    self.span = constructor.span;
+                    if let Err(error) = self.collect_constructor(*type_name, parameters, *linear, constructor) {
+                        self.report(error);
                     }
                 }
             }
         }
+    }
+
+    fn collect_constructor(
+        &mut self,
+        type_name: Symbol,
+        parameters: &[Symbol],
+        linear: bool,
+        Constructor { name, argument_types, output, .. }: &Constructor,
+    ) -> TypeCheckResult<()> {
+        let (parameters, output) = match output {
+            Some(output) => {
+                let mut variables = vec![];
+                for ty in argument_types.iter().chain([output]) {
+                    type_variables(ty, &mut variables);
+                }
+                match self.type_expr(output, &variables)? {
+                    Type::Basic { name, arguments } if name == type_name => (variables, arguments),
+                    _ => return Err(TypeCheckError::InvalidConstructorOutput(*name)),
+                }
+            }
+            None => (parameters.to_vec(), parameters.iter().map(|parameter| Type::Variable(*parameter)).collect()),
+        };
+        let inputs = self.type_exprs(argument_types, &parameters)?;
+        if !linear && inputs.iter().any(|input| self.is_linear(input)) {
+            return Err(TypeCheckError::LinearField(*name));
+        }
+        let ty = Type::Basic { name: type_name, arguments: output.clone() };
+        if self.ctx.insert(*name, Type::Function {
+            inputs: inputs.clone(), outputs: Arc::new([ty]), rows: None, io: false,
+        }).is_some() {
+            return Err(self.already_defined(*name, SymbolKind::Constructor));
+        }
+        self.word_spans.insert(*name, self.span);
+        self.constructors.insert(*name, ConstructorInfo {
+            type_name,
+            parameters: parameters.into(),
+            inputs,
+            output,
+        });
+        self.types.get_mut(&type_name).unwrap().push(*name);
         Ok(())
     }
 
-    fn collect_defs(&mut self, top_levels: &[TopLevel]) -> TypeCheckResult<()> {
+    /// Makes the methods of every trait global words, typed in terms of the
+    /// trait's parameter like a def with that type variable.
+    fn collect_traits(&mut self, top_levels: &[TopLevel]) {
         for top_level in top_levels {
-            if let TopLevel::Def { name, inputs, outputs, branches: _ } = top_level {
-                let ty = Type::Function {
-                    inputs: inputs.iter().map(|ty| self.type_expr(ty)).collect(),
-                    outputs: outputs.iter().map(|ty| self.type_expr(ty)).collect()
+            let TopLevel::Trait { name: trait_name, parameter, methods, span } = top_level else {
+                continue;
+            };
+            self.span = *span;
+            if self.traits.contains_key(trait_name) {
+                self.report(TypeCheckError::TraitAlreadyDefined(*trait_name));
+                continue;
+            }
+
+            for method @ Method { name, signature, span } in methods {
+                self.span = *span;
+                let Some(input) = method.dispatch_input(*parameter) else {
+                    self.report(TypeCheckError::UndispatchableMethod(*name));
+                    continue;
                 };
-                if self.ctx.insert(name.clone(), ty).is_some() {
-                    return Err(TypeCheckError::SymbolAlreadyDefined)
+                let ty = match self.signature_type(signature) {
+                    Ok(ty) => ty,
+                    Err(error) => {
+                        self.report(error);
+                        continue;
+                    }
+                };
+                if self.ctx.contains_key(name) {
+                    let error = self.already_defined(*name, SymbolKind::Method);
+                    self.report(error);
+                    continue;
                 }
+                self.ctx.insert(*name, ty);
+                self.word_spans.insert(*name, *span);
+                self.methods.insert(*name, MethodInfo { trait_name: *trait_name, input });
             }
+            self.traits.insert(*trait_name, TraitInfo {
+                parameter: *parameter,
+                methods: methods.iter().map(|method| method.name).collect(),
+            });
         }
-        Ok(())
     }
 
-    fn pattern_fits(&self, input: &Type, pattern: &Pattern) -> bool {
-        match (input, pattern) {
-            (input_type, Pattern::Constructor { name, arguments }) => {
-                let Some(Type::Function { inputs, outputs }) = self.ctx.get(name) else {
-                    return false;
+    /// Records the word every impl gives each method, so uses of methods
+    /// can be resolved before those words are checked. A derived impl gives
+    /// its method a word of its own, as in `eq[Nat]`, which the evaluator
+    /// runs on the structure of the values.
+    fn collect_impls(&mut self, top_levels: &[TopLevel]) {
+        for top_level in top_levels {
+            let TopLevel::Data { name: type_name, derives, span, .. } = top_level else {
+                continue;
+            };
+            self.span = *span;
+            for trait_name in derives {
+                let derivable = match self.derivable(*trait_name) {
+                    Ok(derivable) => derivable,
+                    Err(error) => {
+                        self.report(error);
+                        continue;
+                    }
                 };
+                if !self.impls.insert((*trait_name, *type_name)) {
+                    self.report(TypeCheckError::ImplAlreadyDefined { trait_name: *trait_name, type_name: *type_name });
+                    continue;
+                }
+                self.derived.insert((*trait_name, *type_name), derivable);
+                let method = self.traits[trait_name].methods[0];
+                let word = Symbol::intern(&format!("{method}[{type_name}]"));
+                self.implementations.entry(method).or_default().insert(*type_name, word);
+            }
+        }
 
-                let [output_type] = &outputs[..] else {
-                    unreachable!()
-                };
+        for top_level in top_levels {
+            let TopLevel::Impl { trait_name, ty, methods, span } = top_level else {
+                continue;
+            };
+            self.span = *span;
+            if let Err(error) = self.collect_impl(*trait_name, ty, methods) {
+                self.report(error);
+            }
+        }
+    }
+
+    fn collect_impl(&mut self, trait_name: Symbol, ty: &TypeExpr, methods: &[MethodImpl]) -> TypeCheckResult<()> {
+        let Some(TraitInfo { methods: trait_methods, .. }) = self.traits.get(&trait_name) else {
+            return Err(TypeCheckError::UnknownTrait(trait_name));
+        };
+
+        let mut variables = vec![];
+        type_variables(ty, &mut variables);
+        let ty = self.type_expr(ty, &variables)?;
+        let Type::Basic { name: type_name, .. } = ty else {
+            return Err(TypeCheckError::InvalidImplType { trait_name, ty });
+        };
+        if !self.impls.insert((trait_name, type_name)) {
+            return Err(TypeCheckError::ImplAlreadyDefined { trait_name, type_name });
+        }
+
+        for MethodImpl { method, word, span } in methods {
+            if !trait_methods.contains(method) {
+                self.span = *span;
+                return Err(TypeCheckError::UnknownMethod(*method));
+            }
+            if self.implementations.entry(*method).or_default().insert(type_name, *word).is_some() {
+                self.span = *span;
+                return Err(TypeCheckError::MethodAlreadyImplemented(*method));
+            }
+        }
+        let implemented = |method: &&Symbol| methods.iter().any(|method_impl| method_impl.method == **method);
+        if let Some(method) = trait_methods.iter().find(|method| !implemented(method)) {
+            return Err(TypeCheckError::MissingMethod(*method));
+        }
+        Ok(())
+    }
 
-                if output_type != input_type {
-                    return false;
+    /// What deriving `trait_name` does, which is only known for an `Eq`
+    /// with a single `(a a - Bool)` method and a `Show` with a single
+    /// `(a - String)` one.
+    fn derivable(&self, trait_name: Symbol) -> TypeCheckResult<Derivable> {
+        let Some(TraitInfo { parameter, methods }) = self.traits.get(&trait_name) else {
+            return Err(TypeCheckError::UnknownTrait(trait_name));
+        };
+        let parameter = Type::Variable(*parameter);
+        let (derivable, inputs, output) = match trait_name.as_str() {
+            "Eq" => (Derivable::Eq, vec![parameter.clone(), parameter], Type::bool()),
+            "Show" => (Derivable::Show, vec![parameter], Type::string()),
+            _ => return Err(TypeCheckError::UnderivableTrait(trait_name)),
+        };
+        let expected = Type::Function {
+            inputs: inputs.into(),
+            outputs: Arc::new([output]),
+            rows: None,
+            io: false,
+        };
+        match &methods[..] {
+            [method] if self.ctx[method] == expected => Ok(derivable),
+            _ => Err(TypeCheckError::UnderivableTrait(trait_name)),
+        }
+    }
+
+    /// Whether `trait_name` is implemented for `ty`, taking type variables
+    /// for which `variable` holds to be types it is implemented for.
+    fn implements(&self, trait_name: Symbol, ty: &Type, variable: &dyn Fn(&Type) -> bool) -> bool {
+        match ty {
+            Type::Basic { name, arguments } => {
+                self.impls.contains(&(trait_name, *name))
+                    && (!self.derived.contains_key(&(trait_name, *name))
+                        || arguments.iter().all(|ty| self.field_implements(trait_name, ty, variable)))
+            }
+            Type::Variable(_) => variable(ty),
+            _ => false,
+        }
+    }
+
+    /// Whether a derived impl of `trait_name` can use it on a field of type
+    /// `ty`, which it does on each element of tuples. There are no impls for
+    /// tuples themselves to use the methods of elsewhere.
+    fn field_implements(&self, trait_name: Symbol, ty: &Type, variable: &dyn Fn(&Type) -> bool) -> bool {
+        match ty {
+            Type::Tuple(elements) => elements.iter().all(|ty| self.field_implements(trait_name, ty, variable)),
+            ty => self.implements(trait_name, ty, variable),
+        }
+    }
+
+    /// Checks that no branch binds a name more than once when there is no
+    /// `Eq` to compare the values it is bound to with.
+    fn check_bindings(&mut self, top_levels: &[TopLevel]) {
+        if self.traits.contains_key(&Symbol::intern("Eq")) {
+            return;
+        }
+        for top_level in top_levels {
+            let TopLevel::Def { name: def, branches, .. } = top_level else {
+                continue;
+            };
+            for (branch, Branch { patterns, .. }) in branches.iter().enumerate() {
+                let mut bound = vec![];
+                for (position, pattern) in patterns.iter().enumerate() {
+                    let mut names = vec![];
+                    pattern.bindings(&mut names);
+                    for (name, span) in names {
+                        if let Some(&(_, first)) = bound.iter().find(|(earlier, _)| *earlier == name) {
+                            self.span = span;
+                            self.report(TypeCheckError::DuplicatePatternBinding { def: *def, name, branch, first, second: position });
+                        }
+                        bound.push((name, position));
+                    }
                 }
+            }
+        }
+    }
 
-                if inputs.len() != arguments.len() {
-                    return false;
+    /// Checks that every impl gives each method of its trait a word of the
+    /// method's type, with the trait's parameter replaced by the impl's type,
+    /// and that the fields of every type deriving a trait implement it too.
+    /// Those of the type's arguments only have to where it is used.
+    fn check_impls(&mut self, top_levels: &[TopLevel]) {
+        for top_level in top_levels {
+            let TopLevel::Data { derives, constructors, .. } = top_level else {
+                continue;
+            };
+            for trait_name in derives {
+                for Constructor { name, span, .. } in constructors {
+                    let ConstructorInfo { inputs, output, .. } = &self.constructors[name];
+                    let argument = |variable: &Type| output.iter().any(|ty| ty.mentions(&mut |leaf| leaf == variable, &mut |_| false));
+                    if !inputs.iter().all(|input| self.field_implements(*trait_name, input, &argument)) {
+                        self.span = *span;
+                        self.report(TypeCheckError::UnderivableField { trait_name: *trait_name, constructor: *name });
+                    }
                 }
+            }
+        }
+
+        for top_level in top_levels {
+            let TopLevel::Impl { trait_name, ty, methods, .. } = top_level else {
+                continue;
+            };
 
-                if !inputs.iter().zip(arguments)
-                    .all(|(input, pattern)| self.pattern_fits(input, pattern)) {
-                    return false;
+            let mut variables = vec![];
+            type_variables(ty, &mut variables);
+            // Collecting the impl converted the type already.
+            let Ok(ty) = self.type_expr(ty, &variables) else {
+                unreachable!()
+            };
+            let mut instance = HashMap::from([(self.traits[trait_name].parameter, ty)]);
+            for MethodImpl { method, word, span } in methods {
+                self.span = *span;
+                let Some(word_ty) = self.ctx.get(word) else {
+                    self.report(TypeCheckError::UnknownImplWord { method: *method, word: *word });
+                    continue;
+                };
+                // The word may be more general than the method, the method's
+                // own type variables stay rigid.
+                let mut unifier = Unifier::default();
+                let found = word_ty.fold(&mut Instantiation::new(&mut unifier));
+                let expected = self.ctx[method].fold(&mut instance);
+                if unifier.unify(&found, &expected).is_err() {
+                    let found = Box::new(word_ty.clone());
+                    self.report(TypeCheckError::ImplMismatch {
+                        method: *method,
+                        word: *word,
+                        expected: Box::new(expected),
+                        found,
+                    });
                 }
-                true
-            },
-            (_, Pattern::All(_)) => true,
+            }
         }
     }
 
-    fn define_pattern_locals(&mut self, input: Type, pattern: Pattern) {
-        match pattern {
-            Pattern::All(name) => {
-                self.locals.push((name, input));
+    /// Renames the defs sharing a name after their position among them, as
+    /// in `add[1]`, which no word in source can be. Uses of the name are
+    /// resolved to one of them by the stack they are used on.
+    fn collect_overloads(&mut self, top_levels: &mut [TopLevel]) {
+        let mut counts = HashMap::new();
+        for top_level in top_levels.iter() {
+            if let TopLevel::Def { name, .. } = top_level {
+                *counts.entry(*name).or_insert(0) += 1;
             }
-            Pattern::Constructor { name, arguments } => {
-                let Some(Type::Function { inputs, outputs: _ }) = self.ctx.get(&name) else {
-                    unreachable!();
-                };
+        }
+
+        for top_level in top_levels {
+            let TopLevel::Def { name, signature, span, .. } = top_level else {
+                continue;
+            };
+            if counts[name] == 1 {
+                continue;
+            }
+            if signature.is_none() {
+                self.span = *span;
+                self.report(TypeCheckError::CannotInfer { def: *name });
+                continue;
+            }
+            let overloads = self.overloads.entry(*name).or_default();
+            let overload = Symbol::intern(&format!("{name}[{}]", overloads.len()));
+            overloads.push(overload);
+            *name = overload;
+        }
+    }
 
-                for (input, pattern) in inputs.clone().into_iter().zip(arguments) {
-                    self.define_pattern_locals(input, pattern);
+    /// Checks that no stack could be given to two overloads of a name,
+    /// which is what lets every use of it resolve to one of them.
+    fn check_overloads(&mut self) {
+        // In source order, which is the order they are reported in.
+        let mut overloads: Vec<_> = self.overloads.iter().map(|(name, overloads)| (*name, overloads.clone())).collect();
+        overloads.sort_by_key(|(_, overloads)| self.defs[&overloads[0]].index);
+        for (name, overloads) in overloads {
+            if self.ctx.contains_key(&name) {
+                let error = self.already_defined(name, SymbolKind::Def);
+                self.span = self.defs[&overloads[0]].span;
+                self.report(error);
+                continue;
+            }
+            for (index, first) in overloads.iter().enumerate() {
+                for second in &overloads[index + 1..] {
+                    let mut unifier = Unifier::default();
+                    let [Type::Function { inputs: left, .. }, Type::Function { inputs: right, .. }] =
+                        [first, second].map(|overload| self.ctx[overload].fold(&mut Instantiation::new(&mut unifier)))
+                    else {
+                        unreachable!()
+                    };
+                    let shared = left.len().min(right.len());
+                    if unifier.unify_all(&left[left.len() - shared..], &right[right.len() - shared..]).is_ok() {
+                        self.span = self.defs[second].span;
+                        self.report(TypeCheckError::OverlappingOverloads(name));
+                    }
                 }
-            },
+            }
         }
     }
 
-    fn type_check_expr(&self, expr: &Expr, stack: &mut Vec<Type>) -> TypeCheckResult<()> {
-        match expr {
-            Expr::Word(word) => {
-                match self.resolve_word(word)? {
-                    ty@Type::Basic(_) => stack.push(ty),
-                    ty@Type::Quotation { .. } => stack.push(ty),
-                    Type::Function { inputs, outputs } => {
-                        if inputs.len() > stack.len() {
-                            return Err(TypeCheckError::TypeMismatch);
-                        }
+    /// Reads the inputs of every quotation annotated with words that are
+    /// neither types nor type variables of the def's signature, as in
+    /// `[a b -]` subtracting two locals, as the start of its body instead.
+    fn unannotate_quotations(&self, top_levels: &mut [TopLevel]) {
+        /// The expression `ty` reads as, if it can be read as one.
+        fn as_expr(ty: &TypeExpr) -> Option<Expr> {
+            let kind = match &ty.kind {
+                TypeExprKind::Word(word) if word.as_str() == "?" => ExprKind::Hole,
+                TypeExprKind::Word(word) => ExprKind::Word(*word),
+                TypeExprKind::Tuple(elements) => {
+                    ExprKind::Tuple(elements.iter().map(|ty| Some(vec![as_expr(ty)?])).collect::<Option<_>>()?)
+                }
+                TypeExprKind::Application { .. } | TypeExprKind::Quotation { .. } => return None,
+            };
+            Some(Expr { kind, span: ty.span })
+        }
 
-                        if stack[stack.len() - inputs.len()..] != inputs {
-                            return Err(TypeCheckError::TypeMismatch);
+        fn unannotate(expr: &mut Expr, is_type: &dyn Fn(Symbol) -> bool) {
+            match &mut expr.kind {
+                ExprKind::Quotation { inputs, quotation } => {
+                    if let Some((types, minus)) = inputs {
+                        let mut words = vec![];
+                        for ty in types.iter() {
+                            type_words(ty, &mut words);
                         }
-
-                        stack.truncate(stack.len() - inputs.len());
-                        stack.extend(outputs);
-                    },
+                        let exprs: Option<Vec<_>> = types.iter().map(as_expr).collect();
+                        if let (Some(mut exprs), false) = (exprs, words.into_iter().all(is_type)) {
+                            exprs.push(Expr { kind: ExprKind::Word(Symbol::intern("-")), span: *minus });
+                            exprs.append(quotation);
+                            *quotation = exprs;
+                            *inputs = None;
+                        }
+                    }
+                    for expr in quotation {
+                        unannotate(expr, is_type);
+                    }
                 }
-            },
-            Expr::Quotation { inputs, quotation } => {
-                let inputs: Vec<_> = inputs.iter().map(|ty| self.type_expr(ty)).collect();
-                let mut outputs = inputs.clone();
-                for expr in quotation {
-                    self.type_check_expr(expr, &mut outputs)?;
+                ExprKind::Tuple(elements) => {
+                    for expr in elements.iter_mut().flatten() {
+                        unannotate(expr, is_type);
+                    }
                 }
+                ExprKind::Word(_) | ExprKind::IntLit(_) | ExprKind::FloatLit(_) | ExprKind::StringLit(_) | ExprKind::CharLit(_) | ExprKind::Unquote | ExprKind::Hole => (),
+            }
+        }
 
-                stack.push(Type::Quotation { inputs, outputs })
-            },
-            Expr::Unquote => {
-                let Some(Type::Quotation { inputs, outputs }) = stack.pop() else {
-                    return Err(TypeCheckError::TypeMismatch)
-                };
+        let declared: HashSet<_> = top_levels
+            .iter()
+            .filter_map(|top_level| match top_level {
+                TopLevel::Data { name, .. } | TopLevel::Type { name, .. } => Some(*name),
+                _ => None,
+            })
+            .collect();
+        for top_level in top_levels {
+            let TopLevel::Def { signature, branches, .. } = top_level else {
+                continue;
+            };
+            let mut variables = vec![];
+            for ty in signature.iter().flat_map(|signature| signature.inputs.iter().chain(&signature.outputs)) {
+                type_variables(ty, &mut variables);
+            }
+            let is_type = |word| self.arities.contains_key(&word) || declared.contains(&word) || variables.contains(&word);
+            for expr in branches.iter_mut().flat_map(|branch| &mut branch.body) {
+                unannotate(expr, &is_type);
+            }
+        }
+    }
 
-                if stack[stack.len() - inputs.len()..] != inputs {
-                    return Err(TypeCheckError::TypeMismatch);
+    /// Collects the signatures of annotated defs, those without one are
+    /// left to `infer_defs`.
+    fn collect_defs(&mut self, top_levels: &[TopLevel]) {
+        for top_level in top_levels {
+            if let TopLevel::Def { name, signature, branches: _, total, doc: _, span } = top_level {
+                self.span = *span;
+                let info = DefInfo { index: self.defs.len(), total: *total, span: *span };
+                if self.ctx.contains_key(name) || self.defs.contains_key(name) {
+                    let error = self.already_defined(*name, SymbolKind::Def);
+                    self.report(error);
+                    continue;
                 }
+                self.defs.insert(*name, info);
+                self.word_spans.insert(*name, *span);
 
-                stack.truncate(stack.len() - inputs.len());
-                stack.extend(outputs);
-            },
+                let Some(signature) = signature else {
+                    continue;
+                };
+                match self.signature_type(signature) {
+                    Ok(ty) => {
+                        self.ctx.insert(*name, ty);
+                    }
+                    Err(error) => self.report(error),
+                }
+            }
         }
-        Ok(())
     }
 
-    fn type_check_defs(&mut self, top_levels: &[TopLevel]) -> TypeCheckResult<()> {
-        for top_level in top_levels {
-            if let TopLevel::Def { name, inputs: _, outputs: _, branches } = top_level {
-                let (inputs, outputs) = match self.ctx.get(name).unwrap().clone() {
-                    ty@Type::Basic(_) => (vec![], vec![ty]),
-                    ty@Type::Quotation { .. } => (vec![], vec![ty]),
-                    Type::Function { inputs, outputs } => (inputs, outputs),
-                };
+    /// Infers the most general signature of every def without one. A def
+    /// is inferred once the unannotated defs it uses are, so those can't be
+    /// mutually recursive. One using a def that couldn't be inferred isn't
+    /// either, without an error of its own.
+    fn infer_defs(&mut self, top_levels: &[TopLevel]) {
+        let mut pending: Vec<_> = top_levels
+            .iter()
+            .filter_map(|top_level| match top_level {
+                TopLevel::Def { name, signature: None, branches, span, .. } => Some((*name, &branches[..], *span)),
+                _ => None,
+            })
+            .collect();
+        let mut uninferred: HashSet<_> = pending.iter().map(|(name, _, _)| *name).collect();
+        let mut failed = HashSet::new();
 
-                for Branch { patterns, body } in branches {
-                    let mut inputs = inputs.clone();
-                    if inputs.len() < patterns.len() {
-                        return Err(TypeCheckError::TypeMismatch);
-                    }
+        while !pending.is_empty() {
+            let count = pending.len();
+            let mut blocked = vec![];
+            for (name, branches, span) in pending {
+                if used_words(branches).any(|word| word != name && uninferred.contains(&word)) {
+                    blocked.push((name, branches, span));
+                    continue;
+                }
+                uninferred.remove(&name);
+                if used_words(branches).any(|word| failed.contains(&word)) {
+                    failed.insert(name);
+                    continue;
+                }
 
-                    let leftover = inputs.split_off(patterns.len());
-                    if !inputs.iter().zip(patterns)
-                        .all(|(input, pattern)| self.pattern_fits(input, pattern)) {
-                        return Err(TypeCheckError::TypeMismatch)
+                let mut def_checker = DefChecker::new(self);
+                let ty = def_checker.infer_def(name, branches);
+                self.span = def_checker.span;
+                match ty {
+                    Ok(ty) => {
+                        self.ctx.insert(name, ty);
                     }
-
-                    let locals_len = self.locals.len();
-                    for (input, pattern) in inputs.iter().zip(patterns) {
-                        self.define_pattern_locals(input.clone(), pattern.clone());
+                    Err(error) => {
+                        self.report(error);
+                        failed.insert(name);
                     }
+                }
+            }
+
+            if blocked.len() == count {
+                let (def, _, span) = blocked[0];
+                self.span = span;
+                self.report(TypeCheckError::CannotInfer { def });
+                break;
+            }
+            pending = blocked;
+        }
+    }
+
+    /// Checks the branches of every def, gathering what is reported about
+    /// each along with the bodies of those that check. An error in a branch
+    /// poisons what it leaves for the rest of the branch, see
+    /// `DefChecker::type_check_exprs`, and other defs are checked
+    /// regardless.
+    fn type_check_defs(&self, top_levels: &[TopLevel]) -> (Bodies, Vec<Diagnostic>) {
+        let defs: Vec<_> = top_levels
+            .iter()
+            .filter_map(|top_level| match top_level {
+                TopLevel::Def { name, branches, .. } => Some((*name, &branches[..])),
+                _ => None,
+            })
+            .collect();
+
+        #[cfg(not(feature = "parallel"))]
+        {
+            // One buffer is reused as the simulated stack of every branch.
+            let mut def_checker = DefChecker::new(self);
+            let mut stack = vec![];
+            let bodies = defs
+                .into_iter()
+                .filter_map(|(name, branches)| Some((name, def_checker.check_def(name, branches, &mut stack)?)))
+                .collect();
+            (bodies, def_checker.diagnostics)
+        }
+
+        #[cfg(feature = "parallel")]
+        {
+            // Once collection is done defs only read the checker, so they are
+            // split across threads. Results are joined back in source order,
+            // which keeps the diagnostics the same as a sequential run.
+            let threads = std::thread::available_parallelism().map_or(1, |threads| threads.get());
+            let chunk_size = defs.len().div_ceil(threads).max(1);
+            std::thread::scope(|scope| {
+                let handles: Vec<_> = defs
+                    .chunks(chunk_size)
+                    .map(|chunk| scope.spawn(move || {
+                        let mut def_checker = DefChecker::new(self);
+                        let mut stack = vec![];
+                        let bodies = chunk
+                            .iter()
+                            .filter_map(|(name, branches)| Some((*name, def_checker.check_def(*name, branches, &mut stack)?)))
+                            .collect::<Vec<_>>();
+                        (bodies, def_checker.diagnostics)
+                    }))
+                    .collect();
+
+                let mut bodies = HashMap::new();
+                let mut diagnostics = vec![];
+                for handle in handles {
+                    let (chunk_bodies, chunk_diagnostics) = handle.join().unwrap();
+                    bodies.extend(chunk_bodies);
+                    diagnostics.extend(chunk_diagnostics);
+                }
+                (bodies, diagnostics)
+            })
+        }
+    }
+
+    /// Runs every pass, each of which reports all the errors it finds.
+    /// The passes after one rely on everything before it being collected,
+    /// so none runs after a pass reporting an error. Then there are no
+    /// bodies either.
+    fn type_check_top_levels(&mut self, top_levels: &mut [TopLevel]) -> Option<Bodies> {
+        self.collect_overloads(top_levels);
+        self.unannotate_quotations(top_levels);
+        let top_levels = &*top_levels;
+        let passes: [fn(&mut Self, &[TopLevel]); 10] = [
+            Self::collect_types,
+            Self::collect_aliases,
+            Self::collect_constructors,
+            Self::collect_traits,
+            Self::check_bindings,
+            Self::collect_impls,
+            Self::collect_defs,
+            |checker, _| checker.check_overloads(),
+            Self::infer_defs,
+            Self::check_impls,
+        ];
+        for pass in passes {
+            if self.has_errors() {
+                return None;
+            }
+            pass(self, top_levels);
+        }
+        if self.has_errors() {
+            return None;
+        }
+        let (bodies, diagnostics) = self.type_check_defs(top_levels);
+        self.diagnostics.extend(diagnostics);
+        Some(bodies)
+    }
+
+    /// Warns about the data types with constructors that all take a value
+    /// of a type no value can be built of, like `data Loop | L(Loop)`. Type
+    /// variables and quotations are taken to stand for values that can be
+    /// built, and so are the arguments a data type is applied to.
+    fn uninhabited_types(&self, top_levels: &[TopLevel]) -> Vec<Diagnostic> {
+        fn inhabited(ty: &Type, types: &HashSet<Symbol>) -> bool {
+            match ty {
+                Type::Basic { name, .. } => types.contains(name),
+                Type::Tuple(elements) => elements.iter().all(|ty| inhabited(ty, types)),
+                _ => true,
+            }
+        }
+
+        let mut types: HashSet<_> = PRIMITIVE_TYPES.iter().map(|name| Symbol::intern(name)).collect();
+        loop {
+            let count = types.len();
+            for (type_name, constructors) in &self.types {
+                if constructors.iter().any(|constructor| self.constructors[constructor].inputs.iter().all(|ty| inhabited(ty, &types))) {
+                    types.insert(*type_name);
+                }
+            }
+            if types.len() == count {
+                break;
+            }
+        }
+
+        top_levels
+            .iter()
+            .filter_map(|top_level| match top_level {
+                TopLevel::Data { name, constructors, span, .. } if !constructors.is_empty() && !types.contains(name) => {
+                    Some(Diagnostic::new(TypeCheckWarning::UninhabitedType(*name), *span))
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Warns about the defs and constructors nothing run from `main` uses,
+    /// going through the impls of every method used. A program without
+    /// `main` is only checked, so nothing is.
+    fn dead_code(&self, top_levels: &[TopLevel], bodies: &Bodies) -> Vec<Diagnostic> {
+        fn used(info: &ExprInfo, words: &mut Vec<Symbol>) {
+            if let Some(Target::Global(word)) = info.target {
+                words.push(word);
+            }
+            for info in &info.quotation {
+                used(info, words);
+            }
+        }
 
-                    let mut stack = leftover;
-                    for expr in body {
-                        self.type_check_expr(expr, &mut stack)?;
+        fn matched(pattern: &Pattern, words: &mut Vec<Symbol>) {
+            match &pattern.kind {
+                PatternKind::All(_) => (),
+                PatternKind::Constructor { name, arguments } => {
+                    words.push(*name);
+                    for pattern in arguments {
+                        matched(pattern, words);
                     }
-                    self.locals.truncate(locals_len);
+                }
+                PatternKind::Tuple(elements) => {
+                    for pattern in elements {
+                        matched(pattern, words);
+                    }
+                }
+                PatternKind::Char(_) => (),
+            }
+        }
 
-                    if outputs != stack {
-                        return Err(TypeCheckError::TypeMismatch);
+        let main = Symbol::intern("main");
+        if !bodies.contains_key(&main) {
+            return vec![];
+        }
+        let branches: HashMap<_, _> = top_levels
+            .iter()
+            .filter_map(|top_level| match top_level {
+                TopLevel::Def { name, branches, .. } => Some((*name, branches)),
+                _ => None,
+            })
+            .collect();
+
+        let mut reached = HashSet::from([main]);
+        let mut pending = vec![main];
+        while let Some(def) = pending.pop() {
+            let mut words = vec![];
+            for info in bodies[&def].iter().flatten() {
+                used(info, &mut words);
+            }
+            for Branch { patterns, .. } in branches[&def] {
+                for pattern in patterns {
+                    matched(pattern, &mut words);
+                }
+            }
+            while let Some(word) = words.pop() {
+                if !reached.insert(word) {
+                    continue;
+                }
+                if let Some(implementations) = self.implementations.get(&word) {
+                    words.extend(implementations.values().copied());
+                }
+                if bodies.contains_key(&word) {
+                    pending.push(word);
+                }
+            }
+        }
+
+        let mut diagnostics = vec![];
+        for top_level in top_levels {
+            match top_level {
+                TopLevel::Def { name, span, .. } if !reached.contains(name) => {
+                    // An overload is told apart from the others by its type.
+                    let warning = match self.overloads.iter().find(|(_, overloads)| overloads.contains(name)) {
+                        Some((source_name, _)) => TypeCheckWarning::UnusedDef { name: *source_name, overload: Some(self.ctx[name].clone()) },
+                        None => TypeCheckWarning::UnusedDef { name: *name, overload: None },
+                    };
+                    diagnostics.push(Diagnostic::new(warning, *span));
+                }
+                TopLevel::Data { constructors, .. } => {
+                    for Constructor { name, span, .. } in constructors {
+                        if !reached.contains(name) {
+                            diagnostics.push(Diagnostic::new(TypeCheckWarning::UnusedConstructor(*name), *span));
+                        }
                     }
                 }
+                _ => (),
             }
         }
-        Ok(())
+        diagnostics
+    }
+
+    /// Checks the program, reporting errors and warnings to `sink`. Returns
+    /// the checked program if it is well typed.
+    pub fn type_check(mut self, mut top_levels: Vec<TopLevel>, sink: &mut dyn DiagnosticSink) -> Option<CheckedProgram> {
+        let bodies = self.type_check_top_levels(&mut top_levels);
+        let mut diagnostics = std::mem::take(&mut self.diagnostics);
+        if bodies.is_some() {
+            diagnostics.extend(self.uninhabited_types(&top_levels));
+        }
+        let checked = diagnostics.iter().all(|diagnostic| !diagnostic.is_error());
+        // What is used can't be known from defs that didn't check.
+        if let (Some(bodies), true) = (&bodies, checked) {
+            diagnostics.extend(self.dead_code(&top_levels, bodies));
+        }
+        for diagnostic in diagnostics {
+            sink.report(diagnostic);
+        }
+
+        match bodies {
+            Some(bodies) if checked => {
+                rename_overloaded_words(&mut top_levels, &bodies);
+                let derived = self
+                    .derived
+                    .iter()
+                    .map(|(&(trait_name, type_name), &derivable)| {
+                        let method = self.traits[&trait_name].methods[0];
+                        (self.implementations[&method][&type_name], (derivable, method))
+                    })
+                    .collect();
+                Some(CheckedProgram {
+                    top_levels,
+                    symbols: self.ctx,
+                    types: self.types,
+                    implementations: self.implementations,
+                    derived,
+                    bodies,
+                })
+            }
+            _ => None,
+        }
+    }
+}
+
+impl Default for TypeChecker {
+    fn default() -> Self {
+        Self::new()
     }
+}
+
+/// A program that passed type checking, along with what the checker
+/// resolved about it.
+pub struct CheckedProgram {
+    /// The defs sharing a name are renamed to their overloads, and so are
+    /// the uses of that name.
+    pub top_levels: Vec<TopLevel>,
+    /// The type of every global word: constructors, defs and host words.
+    pub symbols: HashMap<Symbol, Type>,
+    /// Every data type with the names of its constructors in declaration order.
+    pub types: HashMap<Symbol, Vec<Symbol>>,
+    /// The word every method runs for each type its trait is implemented
+    /// for, by the name of the type.
+    pub implementations: HashMap<Symbol, HashMap<Symbol, Symbol>>,
+    /// What every word of a derived impl does, with the method it runs on
+    /// the fields of the values.
+    pub derived: HashMap<Symbol, (Derivable, Symbol)>,
+    /// What was inferred about each expression of every def, per branch.
+    pub bodies: Bodies,
+}
+
+pub type Bodies = HashMap<Symbol, Vec<Vec<ExprInfo>>>;
 
-    pub fn type_check(&mut self, top_levels: &[TopLevel]) -> TypeCheckResult<()> {
-        self.collect_types(top_levels)?;
-        self.collect_constructors(top_levels)?;
-        self.collect_defs(top_levels)?;
-        self.type_check_defs(top_levels)
+impl CheckedProgram {
+    /// Every def with its signature, in source order.
+    pub fn defs(&self) -> impl Iterator<Item = (Symbol, &Type)> {
+        self.top_levels.iter().filter_map(|top_level| match top_level {
+            TopLevel::Def { name, .. } => Some((*name, &self.symbols[name])),
+            _ => None,
+        })
     }
 }
 
-type TypeCheckResult<T> = Result<T, TypeCheckError>;
+/// The outcome of checking one expression of a branch body.
 #[derive(Debug)]
-pub enum TypeCheckError {
-    TypeAlreadyDefined,
-    SymbolAlreadyDefined,
-    TypeMismatch,
-    UnboundSymbol,
+pub struct ExprInfo {
+    /// What the expression named, if it is a word.
+    pub target: Option<Target>,
+    /// The stack the expression could see before and after it, bottom first.
+    pub before: Vec<Type>,
+    pub after: Vec<Type>,
+    /// The expressions of a quotation's body, or those of a tuple's
+    /// elements one after another.
+    pub quotation: Vec<ExprInfo>,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
-pub enum Type {
-    Basic(String),
-    Function {
-        inputs: Vec<Type>,
-        outputs: Vec<Type>,
-    },
-    Quotation {
-        inputs: Vec<Type>,
-        outputs: Vec<Type>,
-    },
+/// What defines a global word.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SymbolKind {
+    Constructor,
+    Def,
+    Method,
+    /// A word provided by the host.
+    Builtin,
+}
+
+/// A trait impls can be derived for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Derivable {
+    /// Values are equal when built by the same constructor from equal
+    /// arguments.
+    Eq,
+    /// Values are shown as the constructor building them, followed by its
+    /// arguments shown in parentheses.
+    Show,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Target {
+    Local(Symbol),
+    Global(Symbol),
+}
+
+pub struct TypeCheckerBuilder {
+    words: Vec<(Symbol, Type)>,
+    recursion_limit: usize,
+    forbid_shadowing: bool,
+    traced: Option<Symbol>,
+}
+
+impl TypeCheckerBuilder {
+    /// Registers a word provided by the host, checked against like any def.
+    pub fn word(mut self, name: &str, ty: Type) -> Self {
+        self.words.push((Symbol::intern(name), ty));
+        self
+    }
+
+    /// How deeply quotations may nest inside a branch body. Parsed programs
+    /// are already limited by the parser, this also covers built ones.
+    pub fn recursion_limit(mut self, limit: usize) -> Self {
+        self.recursion_limit = limit;
+        self
+    }
+
+    /// Makes a pattern binding that shadows a global word an error rather
+    /// than a warning. Binding a name the branch already binds compares the
+    /// values instead, which isn't shadowing.
+    pub fn forbid_shadowing(mut self, forbid: bool) -> Self {
+        self.forbid_shadowing = forbid;
+        self
+    }
+
+    /// Reports the stack after every expression of the def `name`, or of
+    /// every def sharing the name, as a [`TypeTrace`].
+    pub fn trace(mut self, name: &str) -> Self {
+        self.traced = Some(Symbol::intern(name));
+        self
+    }
+
+    pub fn build(self) -> TypeChecker {
+        let mut checker = TypeChecker {
+            types: HashMap::new(),
+            arities: PRIMITIVE_TYPES.iter().map(|name| (Symbol::intern(name), 0)).collect(),
+            aliases: HashMap::new(),
+            ctx: self.words.into_iter().collect(),
+            constructors: HashMap::new(),
+            linear_types: HashSet::new(),
+            traits: HashMap::new(),
+            methods: HashMap::new(),
+            impls: HashSet::new(),
+            implementations: HashMap::new(),
+            derived: HashMap::new(),
+            defs: HashMap::new(),
+            overloads: HashMap::new(),
+            type_spans: HashMap::new(),
+            word_spans: HashMap::new(),
+            recursion_limit: self.recursion_limit,
+            forbid_shadowing: self.forbid_shadowing,
+            traced: self.traced,
+            span: Span::default(),
+            diagnostics: vec![],
+        };
+        checker.builtin_data(Type::bool(), &BOOL_CONSTRUCTORS);
+        checker.builtin_data(Type::void(), &[]);
+        checker
+    }
+}
+
+/// State for checking the branches of one def. The `TypeChecker` is only
+/// read from here, which is what lets defs be checked independently.
+struct DefChecker<'checker> {
+    checker: &'checker TypeChecker,
+    /// The def being checked or inferred, which errors are reported in.
+    def: Symbol,
+    locals: Vec<(Symbol, Type)>,
+    depth: usize,
+    unifier: Unifier,
+    /// The row below the part of the stack the current body can see.
+    bottom: Row,
+    /// The type variables of the current def's signature, which its body
+    /// can't assume anything about.
+    variables: Vec<Symbol>,
+    /// The def whose signature is being inferred, with the type recursive
+    /// uses of it are checked against.
+    inferring: Option<(Symbol, Type)>,
+    /// Whether the body being checked performed IO so far.
+    io: bool,
+    /// The rows before and after every hole of the current def, and where
+    /// the hole is.
+    holes: Vec<(Row, Row, Span)>,
+    /// The trait of every method used in the current def, with the type it
+    /// was used at and where. Each has to be implemented once the def is
+    /// checked.
+    constraints: Vec<(Symbol, Type, Span)>,
+    /// Every global word used in the current def, with what one of the type
+    /// variables of its type was instantiated to and where it is used.
+    instances: Vec<(Symbol, Type, Span)>,
+    /// The type of every numeric literal in the current def, `Int` unless
+    /// what is done with it makes it a `Float`, and where the literal is.
+    literals: Vec<(Type, Span)>,
+    /// How many errors were reported in the current def that checking went
+    /// on past.
+    errors: usize,
+    /// The stack after every expression of the current def, if it is
+    /// traced, and where the expression is.
+    traces: Vec<(Vec<Type>, Span)>,
+    diagnostics: Vec<Diagnostic>,
+    /// The innermost expression or pattern being checked, or the def or
+    /// branch if none is, where an error is reported.
+    span: Span,
 }
 
+impl<'checker> DefChecker<'checker> {
+    fn new(checker: &'checker TypeChecker) -> Self {
+        Self {
+            checker,
+            // Set by `reset` before any def is checked.
+            def: Symbol::intern(""),
+            locals: vec![],
+            depth: 0,
+            unifier: Unifier::default(),
+            bottom: Row::Rest(0),
+            variables: vec![],
+            inferring: None,
+            io: false,
+            holes: vec![],
+            constraints: vec![],
+            instances: vec![],
+            literals: vec![],
+            errors: 0,
+            traces: vec![],
+            diagnostics: vec![],
+            span: Span::default(),
+        }
+    }
+
+    /// Moves whatever a solved bottom row stands for into the part of the
+    /// stack above `floor`, where it can be consumed.
+    fn settle(&mut self, stack: &mut Vec<Type>, floor: usize) {
+        let (bottom, types) = self.unifier.resolve_stack(self.bottom, vec![]);
+        self.bottom = bottom;
+        stack.splice(floor..floor, types);
+    }
+
+    /// Makes sure there are at least as many types above `floor` as there
+    /// are `inputs`, which everything consuming from the stack goes through.
+    /// While inferring, the stack a def or a quotation starts with grows to
+    /// whatever its body consumes.
+    fn demand(&mut self, word: Option<Symbol>, inputs: &[Type], stack: &mut Vec<Type>, floor: usize) -> TypeCheckResult<()> {
+        self.settle(stack, floor);
+        if inputs.len() > stack.len() - floor && self.bottom == Row::Error {
+            let missing = inputs.len() - (stack.len() - floor);
+            stack.splice(floor..floor, std::iter::repeat_n(Type::Error, missing));
+        }
+        if inputs.len() > stack.len() - floor {
+            let Row::Unknown(_) = self.bottom else {
+                return Err(TypeCheckError::StackUnderflow {
+                    def: self.def,
+                    word,
+                    inputs: self.resolve_all(inputs),
+                    stack: self.resolve_all(&stack[floor..]),
+                });
+            };
+            // The bottom row is unknown, so it can stand for anything.
+            let missing = (0..inputs.len() - (stack.len() - floor)).map(|_| self.unifier.fresh()).collect();
+            let bottom = self.unifier.fresh_row();
+            let _ = self.unifier.bind_row(self.bottom, bottom, missing);
+            self.settle(stack, floor);
+        }
+        Ok(())
+    }
+
+    /// How far below the top the first value of the types `found` is that
+    /// doesn't fit the one of `expected` as deep, both bottom first, with
+    /// the ones above it fitting.
+    fn first_difference(&self, found: &[Type], expected: &[Type]) -> Option<usize> {
+        let mut unifier = self.unifier.clone();
+        found
+            .iter()
+            .rev()
+            .zip(expected.iter().rev())
+            .position(|(found, expected)| unifier.subsume(found, expected).is_err())
+    }
+
+    /// Pops `inputs` off the top of `stack`, above `floor`, and pushes
+    /// `outputs`. With `rows`, whatever is left above `floor` must be the
+    /// input row and is replaced by the output row. Errors are about using
+    /// `word`, or `&` without one.
+    fn apply(
+        &mut self,
+        word: Option<Symbol>,
+        (inputs, outputs): (&[Type], &[Type]),
+        rows: Option<(Row, Row)>,
+        stack: &mut Vec<Type>,
+        floor: usize,
+    ) -> TypeCheckResult<()> {
+        self.demand(word, inputs, stack, floor)?;
+
+        let visible = stack.split_off(floor);
+        let (rest, top) = visible.split_at(visible.len() - inputs.len());
+        let fits = self.unifier.subsume_all(top, inputs).and_then(|()| match rows {
+            Some((input_row, _)) => self.unifier.unify_stacks((input_row, vec![]), (self.bottom, rest.to_vec())),
+            None => Ok(()),
+        });
+        if fits.is_err() {
+            return Err(TypeCheckError::TypeMismatch {
+                def: self.def,
+                word,
+                inputs: self.resolve_all(inputs),
+                stack: self.resolve_all(&visible),
+                differs: self.first_difference(top, inputs),
+            });
+        }
+        match rows {
+            Some((_, output_row)) => self.bottom = output_row,
+            None => stack.extend_from_slice(rest),
+        }
+        stack.extend(outputs.iter().cloned());
+        self.settle(stack, floor);
+        Ok(())
+    }
+
+    /// A copy of the unifier with the literals nothing has decided the type
+    /// of yet taken to be the `Int` they default to.
+    fn defaulted(&self) -> Unifier {
+        let mut unifier = self.unifier.clone();
+        for (literal, _) in &self.literals {
+            if let Type::Unknown(_) = unifier.resolve(literal) {
+                let _ = unifier.unify(literal, &Type::int());
+            }
+        }
+        unifier
+    }
+
+    /// `ty` as a diagnostic shows it, resolved with the literals in it
+    /// defaulted.
+    fn shown(&self, ty: &Type) -> Type {
+        self.defaulted().resolve(ty)
+    }
+
+    fn resolve_all(&self, types: &[Type]) -> Vec<Type> {
+        let unifier = self.defaulted();
+        types.iter().map(|ty| unifier.resolve(ty)).collect()
+    }
+
+    fn resolve_info(&self, info: &mut ExprInfo) {
+        for ty in info.before.iter_mut().chain(info.after.iter_mut()) {
+            *ty = self.unifier.resolve(ty);
+        }
+        for info in &mut info.quotation {
+            self.resolve_info(info);
+        }
+    }
+
+    fn resolve_word(&mut self, word: Symbol) -> TypeCheckResult<(Target, Type)> {
+        if let Some((_, ty)) = self.locals.iter().rev().find(|(name, _)| *name == word && word.as_str() != WILDCARD) {
+            return Ok((Target::Local(word), ty.clone()));
+        }
+
+        match &self.inferring {
+            Some((name, ty)) if *name == word => Ok((Target::Global(word), ty.clone())),
+            _ => match self.checker.ctx.get(&word) {
+                Some(ty) => {
+                    let mut instantiation = Instantiation::new(&mut self.unifier);
+                    let ty = ty.fold(&mut instantiation);
+                    let mut instances = instantiation.into_types();
+                    if let Some(MethodInfo { trait_name, input }) = self.checker.methods.get(&word) {
+                        let Type::Function { inputs, .. } = &ty else {
+                            unreachable!()
+                        };
+                        self.constraints.push((*trait_name, inputs[*input].clone(), self.span));
+                        // The impl picked is for that type, linear or not.
+                        instances.remove(&self.checker.traits[trait_name].parameter);
+                    }
+                    let span = self.span;
+                    self.instances.extend(instances.into_values().map(|instance| (word, instance, span)));
+                    Ok((Target::Global(word), ty))
+                }
+                None => Err(TypeCheckError::UnboundSymbol {
+                    def: self.def,
+                    name: word,
+                    suggestions: self.similar_words(word),
+                }),
+            }
+        }
+    }
+
+    /// The locals and global words named closest to `word`, at most three
+    /// of them, for when it is neither. Names only the checker gives, like
+    /// those of overloads, aren't suggested.
+    fn similar_words(&self, word: Symbol) -> Vec<Symbol> {
+        let threshold = (word.as_str().chars().count() / 3).max(1);
+        let mut similar: Vec<_> = self
+            .locals
+            .iter()
+            .map(|(name, _)| *name)
+            .chain(self.inferring.iter().map(|(name, _)| *name))
+            .chain(self.checker.ctx.keys().copied())
+            .chain(self.checker.overloads.keys().copied())
+            .filter(|name| name.as_str() != WILDCARD && !name.as_str().contains('['))
+            .map(|name| (edit_distance(word.as_str(), name.as_str()), name))
+            .filter(|&(distance, _)| distance <= threshold)
+            .collect();
+        similar.sort_by_key(|&(distance, name)| (distance, name.as_str()));
+        similar.dedup_by_key(|(_, name)| *name);
+        similar.into_iter().take(3).map(|(_, name)| name).collect()
+    }
+
+    /// The overload of `word` whose inputs the top of `stack` can be given
+    /// to, or `word` itself if it isn't overloaded.
+    fn resolve_overload(&mut self, word: Symbol, stack: &mut Vec<Type>, floor: usize) -> TypeCheckResult<Symbol> {
+        let Some(overloads) = self.checker.overloads.get(&word) else {
+            return Ok(word);
+        };
+        if self.locals.iter().any(|(name, _)| *name == word) {
+            return Ok(word);
+        }
+
+        self.settle(stack, floor);
+        let visible = &stack[floor..];
+        let candidates = |defaulted: bool| -> Vec<Symbol> {
+            overloads.iter().copied().filter(|overload| {
+                // Tried on a copy, the chosen one is applied for real afterwards.
+                let mut unifier = if defaulted { self.defaulted() } else { self.unifier.clone() };
+                let Type::Function { inputs, .. } = self.checker.ctx[overload].fold(&mut Instantiation::new(&mut unifier)) else {
+                    unreachable!()
+                };
+                let shared = inputs.len().min(visible.len());
+                unifier.unify_all(&inputs[inputs.len() - shared..], &visible[visible.len() - shared..]).is_ok()
+            }).collect()
+        };
+        let mut matching = candidates(false);
+        // Literals nothing has decided the type of yet are taken to be the
+        // `Int` they default to.
+        if matching.len() > 1 {
+            matching = candidates(true);
+        }
+        match matching[..] {
+            [overload] => Ok(overload),
+            [] => Err(TypeCheckError::NoMatchingOverload {
+                def: self.def,
+                word,
+                stack: self.resolve_all(visible),
+            }),
+            _ => Err(TypeCheckError::AmbiguousOverload(word)),
+        }
+    }
+
+    /// What the type variables of the current signature are known to be in
+    /// a branch matching `patterns` against the top of `inputs`, from the
+    /// constructors matched building a more specific type than the input's.
+    fn refinement(&mut self, inputs: &[Type], patterns: &[Pattern]) -> HashMap<Symbol, Type> {
+        let mut refinement = HashMap::new();
+        for (input, pattern) in inputs.iter().rev().zip(patterns.iter().rev()) {
+            let (Type::Basic { name: input_type, arguments }, PatternKind::Constructor { name, .. }) = (input, &pattern.kind) else {
+                continue;
+            };
+            let Some(ConstructorInfo { type_name, parameters, output, .. }) = self.checker.constructors.get(name) else {
+                continue;
+            };
+            if type_name != input_type {
+                continue;
+            }
+
+            let mut instance: HashMap<_, _> = parameters.iter().map(|parameter| (*parameter, self.unifier.fresh())).collect();
+            for (argument, refined) in arguments.iter().zip(output.iter()) {
+                match (argument, refined.fold(&mut instance)) {
+                    (_, Type::Unknown(_)) => (),
+                    (Type::Variable(variable), refined) if self.variables.contains(variable) => {
+                        refinement.entry(*variable).or_insert(refined);
+                    }
+                    _ => (),
+                }
+            }
+        }
+        refinement
+    }
+
+    /// Checks that `pattern` can match a value of type `input`, defining the
+    /// locals it binds.
+    fn check_pattern(&mut self, input: &Type, pattern: &Pattern) -> TypeCheckResult<()> {
+        let span = std::mem::replace(&mut self.span, pattern.span);
+        match &pattern.kind {
+            PatternKind::All(name) => {
+                // Wildcards are kept as locals, only for linear values to be
+                // known to be dropped there.
+                if self.checker.forbid_shadowing && name.as_str() != WILDCARD && self.checker.ctx.contains_key(name) {
+                    return Err(TypeCheckError::ShadowedSymbol { def: self.def, name: *name });
+                }
+                self.locals.push((*name, input.clone()));
+            }
+            PatternKind::Constructor { name, arguments } => {
+                let Some(ConstructorInfo { type_name, parameters, inputs, output }) = self.checker.constructors.get(name) else {
+                    return Err(TypeCheckError::UnknownConstructor { def: self.def, name: *name });
+                };
+
+                if inputs.len() != arguments.len() {
+                    return Err(TypeCheckError::PatternArity {
+                        def: self.def,
+                        constructor: *name,
+                        expected: inputs.len(),
+                        found: arguments.len(),
+                    });
+                }
+
+                let mut instance: HashMap<_, _> = parameters.iter().map(|parameter| (*parameter, self.unifier.fresh())).collect();
+                let constructs = Type::Basic {
+                    name: *type_name,
+                    arguments: output.iter().map(|ty| ty.fold(&mut instance)).collect(),
+                };
+                if self.unifier.unify(input, &constructs).is_err() {
+                    return Err(TypeCheckError::ConstructorMismatch {
+                        def: self.def,
+                        constructor: *name,
+                        type_name: *type_name,
+                        input: self.shown(input),
+                    });
+                }
+                for (input, pattern) in inputs.iter().zip(arguments) {
+                    self.check_pattern(&input.fold(&mut instance), pattern)?;
+                }
+            },
+            PatternKind::Tuple(patterns) => {
+                let elements: Arc<[Type]> = patterns.iter().map(|_| self.unifier.fresh()).collect();
+                if self.unifier.unify(input, &Type::Tuple(elements.clone())).is_err() {
+                    return Err(TypeCheckError::TuplePatternMismatch {
+                        def: self.def,
+                        elements: patterns.len(),
+                        input: self.shown(input),
+                    });
+                }
+                for (element, pattern) in elements.iter().zip(patterns) {
+                    self.check_pattern(element, pattern)?;
+                }
+            }
+            PatternKind::Char(_) => {
+                if self.unifier.unify(input, &Type::char()).is_err() {
+                    return Err(TypeCheckError::CharPatternMismatch { def: self.def, input: self.shown(input) });
+                }
+            }
+        }
+        self.span = span;
+        Ok(())
+    }
+
+    /// Makes the locals bound more than once since `locals_len` have the
+    /// same type, one `Eq` is implemented for since they are compared with
+    /// its method.
+    fn check_equality_patterns(&mut self, locals_len: usize) -> TypeCheckResult<()> {
+        let bound = &self.locals[locals_len..];
+        let repeated: Vec<_> = bound
+            .iter()
+            .enumerate()
+            .filter(|(_, (name, _))| name.as_str() != WILDCARD)
+            .filter_map(|(index, (name, ty))| {
+                let (_, first) = bound[..index].iter().find(|(earlier, _)| earlier == name)?;
+                Some((*name, first.clone(), ty.clone()))
+            })
+            .collect();
+        if repeated.is_empty() {
+            return Ok(());
+        }
+
+        let eq = Symbol::intern("Eq");
+        let Derivable::Eq = self.checker.derivable(eq)? else {
+            unreachable!()
+        };
+        for (name, first, ty) in repeated {
+            if self.unifier.unify(&first, &ty).is_err() {
+                return Err(TypeCheckError::EqualityPatternMismatch {
+                    def: self.def,
+                    name,
+                    first: Box::new(self.shown(&first)),
+                    second: Box::new(self.shown(&ty)),
+                });
+            }
+            self.constraints.push((eq, ty, self.span));
+        }
+        Ok(())
+    }
+
+    /// Checks `expr` against the simulated `stack`. Everything below `floor`
+    /// belongs to an enclosing quotation or branch and must not be consumed.
+    fn type_check_expr(&mut self, expr: &Expr, stack: &mut Vec<Type>, floor: usize) -> TypeCheckResult<ExprInfo> {
+        let span = std::mem::replace(&mut self.span, expr.span);
+        let before = stack[floor..].to_vec();
+        let mut target = None;
+        let mut quotation_info = vec![];
+        match &expr.kind {
+            ExprKind::Word(name) => {
+                let word = self.resolve_overload(*name, stack, floor)?;
+                let (word_target, ty) = self.resolve_word(word)?;
+                target = Some(word_target);
+                match ty {
+                    Type::Function { inputs, outputs, rows, io } => {
+                        self.io |= io;
+                        self.apply(Some(*name), (&inputs, &outputs), rows, stack, floor)?
+                    }
+                    ty => stack.push(ty),
+                }
+            },
+            ExprKind::Hole => {
+                // Anything may come out of a hole, what the rest of the
+                // branch does with it is what it is expected to do.
+                let rows = (self.unifier.fresh_row(), self.unifier.fresh_row());
+                self.holes.push((rows.0, rows.1, expr.span));
+                self.apply(None, (&[], &[]), Some(rows), stack, floor)?;
+            }
+            ExprKind::IntLit(_) => {
+                let ty = self.unifier.fresh();
+                self.literals.push((ty.clone(), expr.span));
+                stack.push(ty);
+            }
+            ExprKind::FloatLit(_) => stack.push(Type::float()),
+            ExprKind::StringLit(_) => stack.push(Type::string()),
+            ExprKind::CharLit(_) => stack.push(Type::char()),
+            ExprKind::Quotation { inputs, quotation } => {
+                if self.depth == self.checker.recursion_limit {
+                    return Err(TypeCheckError::RecursionLimitReached { def: self.def });
+                }
+
+                // The body is checked on top of the current stack rather than in
+                // a fresh buffer, its region is split off once it is done.
+                let base = stack.len();
+                let bottom = self.bottom;
+                // Performing IO in the body makes the quotation perform it
+                // when unquoted, not the def creating it.
+                let io = std::mem::take(&mut self.io);
+                let inputs = inputs
+                    .as_ref()
+                    .map(|(inputs, _)| self.checker.type_exprs(inputs, &self.variables))
+                    .transpose()?;
+                self.depth += 1;
+                // Without annotations the body starts on a row to be solved,
+                // what it consumes from it are the inputs.
+                let inputs_row = self.unifier.fresh_row();
+                match &inputs {
+                    Some(inputs) => {
+                        stack.extend(inputs.iter().cloned());
+                        self.bottom = Row::Rest(self.depth);
+                    }
+                    None => self.bottom = inputs_row,
+                }
+                let errors = self.errors;
+                quotation_info = self.type_check_exprs(quotation, stack, base)?;
+                self.settle(stack, base);
+                if self.unifier.bind_row(self.bottom, Row::Rest(self.depth), vec![]).is_err() {
+                    return Err(TypeCheckError::TypeMismatch {
+                        def: self.def,
+                        word: None,
+                        inputs: vec![],
+                        stack: self.resolve_all(&stack[base..]),
+                        differs: None,
+                    });
+                }
+                self.bottom = bottom;
+                self.depth -= 1;
+
+                let outputs = stack.drain(base..).collect();
+                let inputs = inputs.unwrap_or_else(|| self.unifier.resolve_stack(inputs_row, vec![]).1.into());
+                let io = std::mem::replace(&mut self.io, io);
+                // Nothing is known of what a body with an error in it does.
+                if self.errors > errors {
+                    stack.push(Type::Error);
+                } else {
+                    stack.push(Type::Quotation { inputs, outputs, rows: None, io, forall: Arc::new([]) });
+                }
+            },
+            ExprKind::Tuple(elements) => {
+                // Each element is checked in a region of its own, which it
+                // can't consume from, and has to leave exactly one value.
+                if self.depth == self.checker.recursion_limit {
+                    return Err(TypeCheckError::RecursionLimitReached { def: self.def });
+                }
+                let bottom = self.bottom;
+                self.depth += 1;
+                self.bottom = Row::Rest(self.depth);
+                let mut types = vec![];
+                for element in elements {
+                    let base = stack.len();
+                    for expr in element {
+                        quotation_info.push(self.type_check_expr(expr, stack, base)?);
+                    }
+                    if stack.len() != base + 1 {
+                        return Err(TypeCheckError::InvalidTupleElement {
+                            def: self.def,
+                            stack: self.resolve_all(&stack[base..]),
+                        });
+                    }
+                    types.push(stack.pop().unwrap());
+                }
+                self.bottom = bottom;
+                self.depth -= 1;
+                stack.push(Type::Tuple(types.into()));
+            }
+            ExprKind::Unquote => {
+                let quotation = self.unifier.fresh();
+                self.demand(None, &[quotation], stack, floor)?;
+
+                let quotation = match self.unifier.resolve(&stack.pop().unwrap()) {
+                    // Whatever it is, it is a quotation.
+                    unknown@Type::Unknown(_) => {
+                        let quotation = Type::Quotation {
+                            inputs: Arc::new([]),
+                            outputs: Arc::new([]),
+                            rows: Some((self.unifier.fresh_row(), self.unifier.fresh_row())),
+                            io: false,
+                            forall: Arc::new([]),
+                        };
+                        // Nothing is known about it yet, so this can't fail.
+                        let _ = self.unifier.unify(&unknown, &quotation);
+                        quotation
+                    }
+                    // It could have done anything to the stack.
+                    Type::Error => Type::Quotation {
+                        inputs: Arc::new([]),
+                        outputs: Arc::new([]),
+                        rows: Some((Row::Error, Row::Error)),
+                        io: false,
+                        forall: Arc::new([]),
+                    },
+                    ty => ty,
+                };
+                let Type::Quotation { mut inputs, mut outputs, rows, io, forall } = quotation else {
+                    return Err(TypeCheckError::NotAQuotation { def: self.def, ty: self.shown(&quotation) });
+                };
+                // A polymorphic quotation can be used at new types every time.
+                if !forall.is_empty() {
+                    let mut instance: HashMap<_, _> = forall.iter().map(|variable| (*variable, self.unifier.fresh())).collect();
+                    inputs = inputs.iter().map(|ty| ty.fold(&mut instance)).collect();
+                    outputs = outputs.iter().map(|ty| ty.fold(&mut instance)).collect();
+                }
+                self.io |= io;
+                self.apply(None, (&inputs, &outputs), rows, stack, floor)?;
+            },
+        }
+        self.span = span;
+        Ok(ExprInfo {
+            target,
+            before,
+            after: stack[floor..].to_vec(),
+            quotation: quotation_info,
+        })
+    }
+
+    /// Checks every branch against the stack effect
+    /// `(..input_row inputs - ..output_row outputs)`.
+    fn type_check_branches(
+        &mut self,
+        (input_row, inputs): (Row, &[Type]),
+        (output_row, outputs): (Row, &[Type]),
+        branches: &[Branch],
+        stack: &mut Vec<Type>,
+    ) -> TypeCheckResult<Vec<Vec<ExprInfo>>> {
+        let span = self.span;
+        let mut bodies = vec![];
+        for (index, branch) in branches.iter().enumerate() {
+            let locals_len = self.locals.len();
+            match self.type_check_branch(index, branch, (input_row, inputs), (output_row, outputs), stack) {
+                Ok(infos) => bodies.push(infos),
+                // While checking, the branches after it are checked
+                // regardless.
+                Err(error) if self.inferring.is_none() => {
+                    self.diagnostics.push(Diagnostic::new(error, self.span));
+                    self.errors += 1;
+                    self.locals.truncate(locals_len);
+                }
+                Err(error) => return Err(error),
+            }
+        }
+        self.span = span;
+        Ok(bodies)
+    }
+
+    fn type_check_branch(
+        &mut self,
+        index: usize,
+        Branch { patterns, body, span }: &Branch,
+        (input_row, inputs): (Row, &[Type]),
+        (output_row, outputs): (Row, &[Type]),
+        stack: &mut Vec<Type>,
+    ) -> TypeCheckResult<Vec<ExprInfo>> {
+        self.span = *span;
+        let mut refinement = self.refinement(inputs, patterns);
+        let inputs: Vec<_> = inputs.iter().map(|ty| ty.fold(&mut refinement)).collect();
+        let outputs: Vec<_> = outputs.iter().map(|ty| ty.fold(&mut refinement)).collect();
+
+        // Patterns match the values on top of the stack, the inputs they
+        // leave are what the body starts with.
+        let rest = self.unifier.fresh_row();
+        let matched: Vec<_> = patterns.iter().map(|_| self.unifier.fresh()).collect();
+        if self.unifier.unify_stacks((input_row, inputs.clone()), (rest, matched.clone())).is_err() {
+            if let Some(extra) = patterns.get(inputs.len()..).filter(|extra| !extra.is_empty()) {
+                // At the patterns without an input.
+                self.span = extra[0].span.to(extra[extra.len() - 1].span);
+                return Err(TypeCheckError::TooManyPatterns {
+                    def: self.def,
+                    branch: index,
+                    patterns: patterns.len(),
+                    inputs: inputs.len(),
+                });
+            }
+            return Err(TypeCheckError::TypeMismatch {
+                def: self.def,
+                word: None,
+                inputs: self.resolve_all(&inputs),
+                stack: self.resolve_all(&matched),
+                differs: None,
+            });
+        }
+
+        let locals_len = self.locals.len();
+        for (input, pattern) in matched.iter().zip(patterns) {
+            self.check_pattern(input, pattern)?;
+        }
+        self.check_equality_patterns(locals_len)?;
+
+        stack.clear();
+        self.bottom = rest;
+        self.settle(stack, 0);
+        let mut infos = self.type_check_exprs(body, stack, 0)?;
+        let bound = self.locals.split_off(locals_len);
+
+        let left = std::mem::take(stack);
+        if self.unifier.unify_stacks((output_row, outputs.clone()), (self.bottom, left.clone())).is_err() {
+            // Without a value that doesn't fit, it is the first one only
+            // one of them has.
+            let differs = self
+                .first_difference(&left, &outputs)
+                .or((left.len() != outputs.len()).then(|| left.len().min(outputs.len())));
+            return Err(TypeCheckError::OutputMismatch {
+                def: self.def,
+                branch: index,
+                outputs: self.resolve_all(&outputs),
+                stack: self.resolve_all(&left),
+                differs,
+            });
+        }
+        self.check_linearity(&bound, body)?;
+        for info in &mut infos {
+            self.resolve_info(info);
+        }
+        Ok(infos)
+    }
+
+    /// Checks `exprs` one after the other against the simulated `stack`. An
+    /// error found while checking a def, not inferring one, is reported and
+    /// the stack above `floor` poisoned for the rest of `exprs`: they go on
+    /// with values of [`Type::Error`] on top of [`Row::Error`], which fit
+    /// however they are used. An ambiguous overload on a poisoned stack
+    /// isn't reported, the poison is what makes it ambiguous.
+    fn type_check_exprs(&mut self, exprs: &[Expr], stack: &mut Vec<Type>, floor: usize) -> TypeCheckResult<Vec<ExprInfo>> {
+        let mut infos = vec![];
+        for expr in exprs {
+            let (span, depth) = (self.span, self.depth);
+            let poisoned = self.bottom == Row::Error || stack[floor..].iter().any(|ty| self.is_poisoned(ty));
+            match self.type_check_expr(expr, stack, floor) {
+                Ok(info) => {
+                    if self.inferring.is_none() && self.is_traced() {
+                        self.traces.push((stack[floor..].to_vec(), expr.span));
+                    }
+                    infos.push(info);
+                }
+                Err(error) if self.inferring.is_none() => {
+                    if !(poisoned && matches!(error, TypeCheckError::AmbiguousOverload(_))) {
+                        self.diagnostics.push(Diagnostic::new(error, self.span));
+                    }
+                    self.errors += 1;
+                    (self.span, self.depth) = (span, depth);
+                    stack.truncate(floor);
+                    self.bottom = Row::Error;
+                }
+                Err(error) => return Err(error),
+            }
+        }
+        Ok(infos)
+    }
+
+    fn is_traced(&self) -> bool {
+        self.checker.traced.is_some_and(|traced| {
+            traced == self.def || self.checker.overloads.get(&traced).is_some_and(|overloads| overloads.contains(&self.def))
+        })
+    }
+
+    fn is_poisoned(&self, ty: &Type) -> bool {
+        self.unifier.resolve(ty).mentions(&mut |leaf| *leaf == Type::Error, &mut |row| row == Row::Error)
+    }
+
+    /// Forgets the def checked before, which may have stopped at an error
+    /// anywhere.
+    fn reset(&mut self, def: Symbol) {
+        self.def = def;
+        self.locals.clear();
+        self.depth = 0;
+        self.inferring = None;
+        self.unifier = Unifier::default();
+        self.variables.clear();
+        self.io = false;
+        self.holes.clear();
+        self.constraints.clear();
+        self.instances.clear();
+        self.literals.clear();
+        self.errors = 0;
+        self.traces.clear();
+    }
+
+    /// Defaults numeric literals whose type is still unknown to `Int`, then
+    /// checks that the trait of every method used is implemented for the
+    /// type it was used at, which has to be known by now, and that no type
+    /// variable of a word used stands for a linear type.
+    fn check_constraints(&mut self) -> TypeCheckResult<()> {
+        for (literal, span) in std::mem::take(&mut self.literals) {
+            self.span = span;
+            match self.unifier.resolve(&literal) {
+                unknown @ Type::Unknown(_) => {
+                    let _ = self.unifier.unify(&unknown, &Type::int());
+                }
+                ty if ty == Type::int() || ty == Type::float() || ty == Type::Error => (),
+                ty => return Err(TypeCheckError::NonNumericLiteral { def: self.def, ty }),
+            }
+        }
+        for (trait_name, ty, span) in std::mem::take(&mut self.constraints) {
+            self.span = span;
+            let ty = self.unifier.resolve(&ty);
+            if !self.is_poisoned(&ty) && !self.checker.implements(trait_name, &ty, &|_| false) {
+                return Err(TypeCheckError::NoImpl { trait_name, ty });
+            }
+        }
+        for (word, ty, span) in std::mem::take(&mut self.instances) {
+            self.span = span;
+            if self.checker.is_linear(&self.unifier.resolve(&ty)) {
+                return Err(TypeCheckError::LinearTypeVariable(word));
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks that the `total` def `name` only uses total defs declared
+    /// before it, and itself only directly in a branch body, with some one
+    /// of its inputs given a value matched inside that input every time.
+    /// Quotations it is given are trusted to terminate.
+    fn check_totality(&mut self, name: Symbol, inputs: usize, branches: &[Branch], bodies: &[Vec<ExprInfo>]) -> TypeCheckResult<()> {
+        // The inputs every recursive use so far is given a value matched
+        // inside of. Shrinking one each time isn't enough, as `f` could
+        // shrink its first input and grow its second, and then the other
+        // way around, so some input has to shrink on every use.
+        let mut decreasing: Vec<usize> = (0..inputs).collect();
+        for (Branch { patterns, body, .. }, infos) in branches.iter().zip(bodies) {
+            // The input every local is matched inside of, if it isn't the
+            // whole input.
+            let mut smaller = HashMap::new();
+            for (input, pattern) in (inputs - patterns.len()..).zip(patterns) {
+                nested_locals(pattern, input, false, &mut smaller);
+            }
+
+            // The local every value on the stack is, if it is one.
+            let mut origins: Vec<Option<Symbol>> = vec![];
+            for (expr, info) in body.iter().zip(infos) {
+                self.span = expr.span;
+                self.check_total_calls(name, info)?;
+                if origins.len() < info.before.len() {
+                    origins.splice(0..0, std::iter::repeat_n(None, info.before.len() - origins.len()));
+                }
+
+                let consumed = match (&expr.kind, info.target) {
+                    (_, Some(Target::Local(local))) => {
+                        origins.push(Some(local));
+                        continue;
+                    }
+                    (_, Some(Target::Global(word))) => {
+                        if word == name {
+                            let arguments = origins.len().checked_sub(inputs).map(|start| &origins[start..]);
+                            decreasing.retain(|&input| {
+                                arguments.is_some_and(|arguments| {
+                                    arguments[input].is_some_and(|local| smaller.get(&local) == Some(&Some(input)))
+                                })
+                            });
+                            if decreasing.is_empty() {
+                                return Err(TypeCheckError::NonStructuralRecursion(name));
+                            }
+                        }
+                        match &self.checker.ctx[&word] {
+                            Type::Function { inputs, rows: None, .. } => Some(inputs.len()),
+                            Type::Function { rows: Some(_), .. } => None,
+                            _ => Some(0),
+                        }
+                    }
+                    (
+                        ExprKind::IntLit(_)
+                        | ExprKind::FloatLit(_)
+                        | ExprKind::StringLit(_)
+                        | ExprKind::CharLit(_)
+                        | ExprKind::Quotation { .. }
+                        | ExprKind::Tuple(_),
+                        None,
+                    ) => Some(0),
+                    _ => None,
+                };
+                match consumed {
+                    Some(consumed) if consumed <= origins.len() => origins.truncate(origins.len() - consumed),
+                    // Whatever is left isn't known to be any local.
+                    _ => origins.clear(),
+                }
+                origins.resize(info.after.len(), None);
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks that the words `info` and the expressions nested in it use
+    /// are allowed in the `total` def `name`, the recursive uses directly
+    /// in the branch body aside.
+    fn check_total_calls(&self, name: Symbol, info: &ExprInfo) -> TypeCheckResult<()> {
+        for info in &info.quotation {
+            if info.target == Some(Target::Global(name)) {
+                return Err(TypeCheckError::NonStructuralRecursion(name));
+            }
+            self.check_total_calls(name, info)?;
+        }
+
+        let Some(Target::Global(word)) = info.target else {
+            return Ok(());
+        };
+        let partial = match self.checker.defs.get(&word) {
+            Some(DefInfo { index, total, .. }) => word != name && (!total || *index > self.checker.defs[&name].index),
+            None => self.checker.methods.contains_key(&word),
+        };
+        if partial {
+            return Err(TypeCheckError::NonTotalCall { def: name, word });
+        }
+        Ok(())
+    }
+
+    /// Checks that every linear value `bound` by the patterns of a branch
+    /// is used exactly once in its `body`, and not from a quotation, which
+    /// could be unquoted any number of times.
+    fn check_linearity(&self, bound: &[(Symbol, Type)], body: &[Expr]) -> TypeCheckResult<()> {
+        for (index, (name, ty)) in bound.iter().enumerate() {
+            if !self.checker.is_linear(&self.unifier.resolve(ty)) {
+                continue;
+            }
+            // A later binding of the same name hides this one from the body.
+            if bound[index + 1..].iter().any(|(later, _)| later == name) {
+                return Err(TypeCheckError::DroppedLinear(*name));
+            }
+
+            let mut uses = [0, 0];
+            for expr in body {
+                local_uses(*name, expr, false, &mut uses);
+            }
+            let [outside, quoted] = uses;
+            if quoted > 0 {
+                return Err(TypeCheckError::CapturedLinear(*name));
+            }
+            if outside == 0 {
+                return Err(TypeCheckError::DroppedLinear(*name));
+            }
+            if outside > 1 {
+                return Err(TypeCheckError::DuplicatedLinear(*name));
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks `name` like `type_check_def`, reporting the errors it goes on
+    /// past and the one it stops at along with its warnings.
+    fn check_def(&mut self, name: Symbol, branches: &[Branch], stack: &mut Vec<Type>) -> Option<Vec<Vec<ExprInfo>>> {
+        let start = self.diagnostics.len();
+        let bodies = match self.type_check_def(name, branches, stack) {
+            Ok(bodies) if self.errors == 0 => Some(bodies),
+            Ok(_) => None,
+            Err(error) => {
+                self.diagnostics.push(Diagnostic::new(error, self.span));
+                None
+            }
+        };
+        // Reported before the errors of the def, resolved with everything
+        // known about it by the end.
+        let traces: Vec<_> = std::mem::take(&mut self.traces)
+            .into_iter()
+            .map(|(stack, span)| {
+                Diagnostic::new(TypeTrace { def: name, stack: self.resolve_all(&stack) }, span)
+            })
+            .collect();
+        self.diagnostics.splice(start..start, traces);
+        bodies
+    }
+
+    fn type_check_def(&mut self, name: Symbol, branches: &[Branch], stack: &mut Vec<Type>) -> TypeCheckResult<Vec<Vec<ExprInfo>>> {
+        let (inputs, outputs, rows, io) = match self.checker.ctx[&name].clone() {
+            Type::Function { inputs, outputs, rows, io } => (inputs, outputs, rows, io),
+            ty => (Arc::from([]), Arc::from([ty]), None, false),
+        };
+        let (input_row, output_row) = rows.unwrap_or((Row::Rest(0), Row::Rest(0)));
+        self.reset(name);
+        let span = self.checker.defs[&name].span;
+        self.span = span;
+        for ty in inputs.iter().chain(outputs.iter()) {
+            ty.mentions(
+                &mut |leaf| {
+                    if let Type::Variable(variable) = leaf {
+                        if !self.variables.contains(variable) {
+                            self.variables.push(*variable);
+                        }
+                    }
+                    false
+                },
+                &mut |_| false,
+            );
+        }
+
+        let mut bodies = self.type_check_branches((input_row, &inputs), (output_row, &outputs), branches, stack)?;
+        // Whether a quotation with an error in it performs IO isn't known.
+        if self.io && !io && self.errors == 0 {
+            return Err(TypeCheckError::UndeclaredIo { def: name });
+        }
+        self.check_constraints()?;
+        // The rest is about what the branches do, which isn't known past an
+        // error.
+        if self.errors > 0 {
+            return Ok(bodies);
+        }
+        // Literals defaulted to `Int` only now.
+        for info in bodies.iter_mut().flatten() {
+            self.resolve_info(info);
+        }
+        if self.checker.defs[&name].total {
+            self.check_totality(name, inputs.len(), branches, &bodies)?;
+        }
+        self.span = span;
+        for (before, after, span) in std::mem::take(&mut self.holes) {
+            let [before, after] = [before, after].map(|row| {
+                let (_, types) = self.unifier.resolve_stack(row, vec![]);
+                types.iter().map(|ty| self.unifier.resolve(ty)).collect()
+            });
+            self.diagnostics.push(Diagnostic::new(TypeCheckError::Hole { def: name, before, after }, span));
+        }
+
+        // A branch leaves the inputs below the ones it has patterns for
+        // alone, which is the same as matching anything there.
+        let rows: Vec<Vec<_>> = branches
+            .iter()
+            .map(|Branch { patterns, .. }| std::iter::repeat_n(None, inputs.len() - patterns.len())
+                .chain(patterns.iter().map(Some))
+                .collect())
+            .collect();
+        // A branch comparing its bindings may not match what its patterns
+        // do, so it doesn't cover anything for the branches after it.
+        let covering = |rows: &[Vec<_>], branches: &[Branch]| -> Vec<Vec<_>> {
+            rows.iter()
+                .zip(branches)
+                .filter(|(_, branch)| !branch.compares_bindings())
+                .map(|(row, _)| row.clone())
+                .collect()
+        };
+        // No value of an empty type can be given, so a def taking one needs
+        // no branches.
+        let impossible = inputs.iter().any(|input| match input {
+            Type::Basic { name, .. } => self.checker.types.get(name).is_some_and(Vec::is_empty),
+            _ => false,
+        });
+        if let Some(witness) = self.checker.witness(&covering(&rows, branches), &inputs).filter(|_| !impossible) {
+            let missing: Vec<_> = witness.iter().map(Witness::to_string).collect();
+            return Err(TypeCheckError::NonExhaustivePatterns { def: name, missing: missing.join(" ") });
+        }
+        for (index, row) in rows.iter().enumerate() {
+            if !self.checker.is_useful(&covering(&rows[..index], branches), row, &inputs) {
+                self.diagnostics.push(Diagnostic::new(
+                    TypeCheckWarning::UnreachableBranch { def: name, branch: index },
+                    branches[index].span,
+                ));
+            }
+        }
+        for (index, branch) in branches.iter().enumerate() {
+            let bindings = branch.bindings();
+            for &(local, span) in &bindings {
+                if self.checker.ctx.contains_key(&local) {
+                    self.diagnostics.push(Diagnostic::new(TypeCheckWarning::ShadowedGlobal {
+                        def: name,
+                        branch: index,
+                        name: local,
+                    }, span));
+                }
+                // Those bound more than once are used by comparing them.
+                if bindings.iter().filter(|(name, _)| *name == local).count() > 1 {
+                    continue;
+                }
+                let mut uses = [0, 0];
+                for expr in &branch.body {
+                    local_uses(local, expr, false, &mut uses);
+                }
+                if uses == [0, 0] {
+                    self.diagnostics.push(Diagnostic::new(TypeCheckWarning::UnusedLocal {
+                        def: name,
+                        branch: index,
+                        name: local,
+                    }, span));
+                }
+            }
+        }
+        Ok(bodies)
+    }
+
+    /// Infers the most general type `name` can be given from its branches.
+    fn infer_def(&mut self, name: Symbol, branches: &[Branch]) -> TypeCheckResult<Type> {
+        self.reset(name);
+        let span = self.checker.defs[&name].span;
+        self.span = span;
+        let (input_row, output_row) = (self.unifier.fresh_row(), self.unifier.fresh_row());
+        self.inferring = Some((name, Type::Function {
+            inputs: Arc::new([]),
+            outputs: Arc::new([]),
+            rows: Some((input_row, output_row)),
+            io: false,
+        }));
+        self.type_check_branches((input_row, &[]), (output_row, &[]), branches, &mut vec![])?;
+        self.inferring = None;
+        self.check_constraints()?;
+        self.span = span;
+
+        let (input_row, inputs) = self.unifier.resolve_stack(input_row, vec![]);
+        let (output_row, outputs) = self.unifier.resolve_stack(output_row, vec![]);
+        let rows = match (input_row, output_row) {
+            // The rest of the stack is left alone.
+            (input_row, output_row) if input_row == output_row => None,
+            (Row::Unknown(_), Row::Unknown(_)) => Some((input_row, output_row)),
+            _ => return Err(TypeCheckError::CannotInfer { def: name }),
+        };
+
+        let ty = Type::Function {
+            inputs: inputs.into(),
+            outputs: outputs.into(),
+            rows,
+            io: self.io,
+        };
+        Ok(self.unifier.resolve(&ty).fold(&mut Generalization::default()))
+    }
+}
+
+/// The words used in the bodies of `branches` other than the locals their
+/// patterns bind.
+fn used_words(branches: &[Branch]) -> impl Iterator<Item = Symbol> + '_ {
+    fn expr_words(expr: &Expr, locals: &[Symbol], words: &mut Vec<Symbol>) {
+        match &expr.kind {
+            ExprKind::Word(word) => {
+                if !locals.contains(word) {
+                    words.push(*word);
+                }
+            }
+            ExprKind::Quotation { inputs: _, quotation } => {
+                for expr in quotation {
+                    expr_words(expr, locals, words);
+                }
+            }
+            ExprKind::Tuple(elements) => {
+                for expr in elements.iter().flatten() {
+                    expr_words(expr, locals, words);
+                }
+            }
+            ExprKind::IntLit(_) | ExprKind::FloatLit(_) | ExprKind::StringLit(_) | ExprKind::CharLit(_) | ExprKind::Unquote | ExprKind::Hole => (),
+        }
+    }
+
+    branches.iter().flat_map(|branch| {
+        let locals: Vec<_> = branch.bindings().into_iter().map(|(name, _)| name).collect();
+        let mut words = vec![];
+        for expr in &branch.body {
+            expr_words(expr, &locals, &mut words);
+        }
+        words
+    })
+}
+
+/// Records the locals bound by `pattern`, matched against `input`, as
+/// being inside it if they are `nested` in a constructor or tuple pattern.
+fn nested_locals(pattern: &Pattern, input: usize, nested: bool, locals: &mut HashMap<Symbol, Option<usize>>) {
+    match &pattern.kind {
+        PatternKind::All(name) => {
+            locals.insert(*name, nested.then_some(input));
+        }
+        PatternKind::Constructor { name: _, arguments } | PatternKind::Tuple(arguments) => {
+            for pattern in arguments {
+                nested_locals(pattern, input, true, locals);
+            }
+        }
+        PatternKind::Char(_) => (),
+    }
+}
+
+/// Counts the uses of the local `name` in `expr`, those outside and those
+/// inside quotations.
+fn local_uses(name: Symbol, expr: &Expr, quoted: bool, uses: &mut [usize; 2]) {
+    match &expr.kind {
+        ExprKind::Word(word) => {
+            if *word == name {
+                uses[quoted as usize] += 1;
+            }
+        }
+        ExprKind::Quotation { inputs: _, quotation } => {
+            for expr in quotation {
+                local_uses(name, expr, true, uses);
+            }
+        }
+        ExprKind::Tuple(elements) => {
+            for expr in elements.iter().flatten() {
+                local_uses(name, expr, quoted, uses);
+            }
+        }
+        ExprKind::IntLit(_) | ExprKind::FloatLit(_) | ExprKind::StringLit(_) | ExprKind::CharLit(_) | ExprKind::Unquote | ExprKind::Hole => (),
+    }
+}
+
+/// Replaces every use of an overloaded name in the defs with the overload
+/// it was resolved to, and every integer literal used as a `Float` with a
+/// float literal.
+fn rename_overloaded_words(top_levels: &mut [TopLevel], bodies: &Bodies) {
+    fn rename(expr: &mut Expr, info: &ExprInfo) {
+        match &mut expr.kind {
+            ExprKind::Word(word) => {
+                if let Some(Target::Global(target)) = info.target {
+                    *word = target;
+                }
+            }
+            ExprKind::IntLit(value) => {
+                if info.after.last() == Some(&Type::float()) {
+                    expr.kind = ExprKind::FloatLit(*value as f64);
+                }
+            }
+            ExprKind::Quotation { inputs: _, quotation } => {
+                for (expr, info) in quotation.iter_mut().zip(&info.quotation) {
+                    rename(expr, info);
+                }
+            }
+            ExprKind::Tuple(elements) => {
+                for (expr, info) in elements.iter_mut().flatten().zip(&info.quotation) {
+                    rename(expr, info);
+                }
+            }
+            ExprKind::FloatLit(_) | ExprKind::StringLit(_) | ExprKind::CharLit(_) | ExprKind::Unquote | ExprKind::Hole => (),
+        }
+    }
+
+    for top_level in top_levels {
+        if let TopLevel::Def { name, branches, .. } = top_level {
+            for (Branch { patterns: _, body, span: _ }, infos) in branches.iter_mut().zip(&bodies[name]) {
+                for (expr, info) in body.iter_mut().zip(infos) {
+                    rename(expr, info);
+                }
+            }
+        }
+    }
+}
+
+fn type_variables(type_expr: &TypeExpr, variables: &mut Vec<Symbol>) {
+    let mut words = vec![];
+    type_words(type_expr, &mut words);
+    for word in words {
+        if is_variable_name(word.as_str()) && !variables.contains(&word) {
+            variables.push(word);
+        }
+    }
+}
+
+/// How many characters have to be inserted, deleted, substituted or
+/// swapped with the next one to turn `from` into `to`.
+fn edit_distance(from: &str, to: &str) -> usize {
+    let (from, to): (Vec<_>, Vec<_>) = (from.chars().collect(), to.chars().collect());
+    // The distances from every prefix of `from` to every prefix of `to`,
+    // `j` characters long, are at `j` of the row for its length.
+    let mut rows = vec![(0..=to.len()).collect::<Vec<_>>()];
+    for i in 1..=from.len() {
+        let mut row = vec![i];
+        for j in 1..=to.len() {
+            let substitution = rows[i - 1][j - 1] + usize::from(from[i - 1] != to[j - 1]);
+            let mut distance = substitution.min(rows[i - 1][j] + 1).min(row[j - 1] + 1);
+            if i > 1 && j > 1 && from[i - 1] == to[j - 2] && from[i - 2] == to[j - 1] {
+                distance = distance.min(rows[i - 2][j - 2] + 1);
+            }
+            row.push(distance);
+        }
+        rows.push(row);
+    }
+    rows[from.len()][to.len()]
+}
+
+/// Every word `type_expr` mentions, applied or not.
+fn type_words(type_expr: &TypeExpr, words: &mut Vec<Symbol>) {
+    match &type_expr.kind {
+        TypeExprKind::Word(word) => words.push(*word),
+        TypeExprKind::Application { name, arguments } => {
+            words.push(*name);
+            for ty in arguments {
+                type_words(ty, words);
+            }
+        }
+        TypeExprKind::Quotation { inputs, outputs, .. } => {
+            for ty in inputs.iter().chain(outputs) {
+                type_words(ty, words);
+            }
+        }
+        TypeExprKind::Tuple(elements) => {
+            for ty in elements {
+                type_words(ty, words);
+            }
+        }
+    }
+}
+
+pub type TypeCheckResult<T> = Result<T, TypeCheckError>;
+#[derive(Debug)]
+pub enum TypeCheckError {
+    /// A type or alias of the name is already declared at `previous_span`,
+    /// or built into the language.
+    TypeAlreadyDefined {
+        name: Symbol,
+        previous_span: Option<Span>,
+    },
+    /// `name` is defined as a `kind` of word while it already is a
+    /// `previous` one, defined at `previous_span` unless it is built in.
+    /// Types are named apart from words, so a type and a constructor
+    /// sharing its name don't conflict.
+    SymbolAlreadyDefined {
+        name: Symbol,
+        kind: SymbolKind,
+        previous: SymbolKind,
+        previous_span: Option<Span>,
+    },
+    /// In the branches of `def`, `word` takes values of the types `inputs`
+    /// and is used where the part of the stack it can consume is of the
+    /// types `stack`, both bottom first. Without a word it is `&` that is
+    /// used, or what a branch's patterns match that doesn't fit its inputs.
+    /// The first value not fitting is `differs` below the top, if one is
+    /// found by itself.
+    TypeMismatch {
+        def: Symbol,
+        word: Option<Symbol>,
+        inputs: Vec<Type>,
+        stack: Vec<Type>,
+        differs: Option<usize>,
+    },
+    /// In the branches of `def`, `word`, or `&` without one, takes values of
+    /// the types `inputs` where only those of the types `stack` can be
+    /// consumed, both bottom first.
+    StackUnderflow {
+        def: Symbol,
+        word: Option<Symbol>,
+        inputs: Vec<Type>,
+        stack: Vec<Type>,
+    },
+    /// A branch of `def` leaves values of the types `stack` where its
+    /// signature says it leaves `outputs`, both bottom first. The first
+    /// value not fitting, or missing from one of them, is `differs` below
+    /// the top.
+    OutputMismatch {
+        def: Symbol,
+        branch: usize,
+        outputs: Vec<Type>,
+        stack: Vec<Type>,
+        differs: Option<usize>,
+    },
+    /// `name` is used in the branches of `def` without being a local or a
+    /// global word. The ones named closest to it are `suggestions`.
+    UnboundSymbol {
+        def: Symbol,
+        name: Symbol,
+        suggestions: Vec<Symbol>,
+    },
+    /// None of the defs sharing the name `word` can be given the part of
+    /// the stack of the types `stack` it is used on in `def`.
+    NoMatchingOverload {
+        def: Symbol,
+        word: Symbol,
+        stack: Vec<Type>,
+    },
+    /// A pattern of `def` binds `name`, which is a global word, while
+    /// shadowing is forbidden.
+    ShadowedSymbol {
+        def: Symbol,
+        name: Symbol,
+    },
+    /// Quotations and tuples nest deeper in `def` than the checker's limit.
+    RecursionLimitReached {
+        def: Symbol,
+    },
+    /// An element of a tuple built in `def` leaves values of the types
+    /// `stack` rather than exactly one.
+    InvalidTupleElement {
+        def: Symbol,
+        stack: Vec<Type>,
+    },
+    /// `&` is used in `def` on a value of type `ty`, which isn't a quotation.
+    NotAQuotation {
+        def: Symbol,
+        ty: Type,
+    },
+    /// An integer literal in `def` is used as a value of type `ty`, which
+    /// is neither `Int` nor `Float`.
+    NonNumericLiteral {
+        def: Symbol,
+        ty: Type,
+    },
+    /// Some inputs of the def aren't matched by any of its branches, like
+    /// those `missing` matches, written as the patterns of a branch.
+    NonExhaustivePatterns {
+        def: Symbol,
+        missing: String,
+    },
+    /// The signature of the def has to be given, as it uses unannotated
+    /// defs recursively, shares its name with other defs or its inferred
+    /// type would be unsound.
+    CannotInfer {
+        def: Symbol,
+    },
+    /// A constructor builds something other than the type it is declared in.
+    InvalidConstructorOutput(Symbol),
+    /// A pattern of `def` names `name`, which isn't a constructor.
+    UnknownConstructor {
+        def: Symbol,
+        name: Symbol,
+    },
+    /// A pattern of `def` matches `found` arguments of `constructor`,
+    /// which takes `expected`.
+    PatternArity {
+        def: Symbol,
+        constructor: Symbol,
+        expected: usize,
+        found: usize,
+    },
+    /// A pattern of `def` matches a value of type `input` with
+    /// `constructor`, which builds values of the type named `type_name`
+    /// instead, or of a more specific one than `input`.
+    ConstructorMismatch {
+        def: Symbol,
+        constructor: Symbol,
+        type_name: Symbol,
+        input: Type,
+    },
+    /// A pattern of `def` matches a value of type `input` with a tuple of
+    /// `elements` elements.
+    TuplePatternMismatch {
+        def: Symbol,
+        elements: usize,
+        input: Type,
+    },
+    /// The patterns of a branch of `def` bind `name` to values of the types
+    /// `first` and `second`, which can't be compared.
+    EqualityPatternMismatch {
+        def: Symbol,
+        name: Symbol,
+        first: Box<Type>,
+        second: Box<Type>,
+    },
+    /// A constructor of a type that isn't linear takes a linear argument,
+    /// which matching on it could drop.
+    LinearField(Symbol),
+    /// A linear local is used more than once.
+    DuplicatedLinear(Symbol),
+    /// A linear local isn't used.
+    DroppedLinear(Symbol),
+    /// A linear local is used in a quotation.
+    CapturedLinear(Symbol),
+    /// A linear value is given to the word where its type is a type
+    /// variable.
+    LinearTypeVariable(Symbol),
+    /// The `total` def uses a def that isn't total or is declared after it,
+    /// or a method, whose impls may not be total.
+    NonTotalCall {
+        def: Symbol,
+        word: Symbol,
+    },
+    /// The `total` def uses itself without some one of its inputs being
+    /// given a value matched inside that input every time, which could
+    /// recurse forever.
+    NonStructuralRecursion(Symbol),
+    /// Some stack could be given to two of the defs sharing the name.
+    OverlappingOverloads(Symbol),
+    /// The stack an overloaded name is used on isn't known well enough to
+    /// tell which of the defs sharing it is meant.
+    AmbiguousOverload(Symbol),
+    /// A type annotation names a type that isn't declared.
+    UnknownType(Symbol),
+    /// A type is applied to a different number of arguments than it has
+    /// parameters, which is `expected`. Type variables and aliases have
+    /// none.
+    KindMismatch {
+        name: Symbol,
+        expected: usize,
+        found: usize,
+    },
+    /// The type alias is defined in terms of itself, through other aliases
+    /// or not.
+    CyclicTypeAlias(Symbol),
+    /// The def performs IO without `!io` in its signature, by using a word
+    /// or unquoting a quotation that does.
+    UndeclaredIo {
+        def: Symbol,
+    },
+    /// A `?` in the branches of `def`. The stack looked like `before` when
+    /// reaching it and has to look like `after` for the rest of the branch
+    /// to check, both bottom first.
+    Hole {
+        def: Symbol,
+        before: Vec<Type>,
+        after: Vec<Type>,
+    },
+    TraitAlreadyDefined(Symbol),
+    UnknownTrait(Symbol),
+    /// None of the inputs of the method is of the trait's parameter, so
+    /// nothing picks the impl to use.
+    UndispatchableMethod(Symbol),
+    /// An impl of the trait is for `ty`, which isn't a data or primitive
+    /// type.
+    InvalidImplType {
+        trait_name: Symbol,
+        ty: Type,
+    },
+    /// The trait is implemented for the type of that name more than once.
+    ImplAlreadyDefined {
+        trait_name: Symbol,
+        type_name: Symbol,
+    },
+    /// An impl gives a word for a method its trait doesn't have.
+    UnknownMethod(Symbol),
+    /// An impl gives more than one word for a method.
+    MethodAlreadyImplemented(Symbol),
+    /// An impl doesn't give a word for a method of its trait.
+    MissingMethod(Symbol),
+    /// An impl gives `method` the word `word`, which isn't a global word.
+    UnknownImplWord {
+        method: Symbol,
+        word: Symbol,
+    },
+    /// An impl gives `method` the word `word` of type `found`, which can't
+    /// be used as the method is at the impl's type, `expected`.
+    ImplMismatch {
+        method: Symbol,
+        word: Symbol,
+        expected: Box<Type>,
+        found: Box<Type>,
+    },
+    /// A method is used at a type its trait isn't implemented for, or at
+    /// one that isn't known.
+    NoImpl {
+        trait_name: Symbol,
+        ty: Type,
+    },
+    /// The branch at `branch` in the branches of `def` binds `name` in both
+    /// its patterns at `first` and `second`, counted from the first one,
+    /// without an `Eq` trait to compare the values with. Both can be the
+    /// same pattern.
+    DuplicatePatternBinding {
+        def: Symbol,
+        name: Symbol,
+        branch: usize,
+        first: usize,
+        second: usize,
+    },
+    /// A data type derives a trait there is no known way to derive, or the
+    /// `Eq` a name bound twice in patterns is compared with isn't one.
+    UnderivableTrait(Symbol),
+    /// A data type derives a trait not implemented for the type of one of
+    /// the arguments of `constructor`.
+    UnderivableField {
+        trait_name: Symbol,
+        constructor: Symbol,
+    },
+    /// Branch `branch` of `def` has `patterns` patterns, more than the
+    /// `inputs` inputs of `def`.
+    TooManyPatterns {
+        def: Symbol,
+        branch: usize,
+        patterns: usize,
+        inputs: usize,
+    },
+    /// A pattern of `def` matches a value of type `input` with a character.
+    CharPatternMismatch {
+        def: Symbol,
+        input: Type,
+    },
+}
+
+/// The stack after an expression of a def traced with
+/// [`TypeCheckerBuilder::trace`], the part of it the expression can see.
+#[derive(Debug)]
+pub struct TypeTrace {
+    pub def: Symbol,
+    pub stack: Vec<Type>,
+}
+
+impl TypeTrace {
+    pub fn code(&self) -> &'static str {
+        "T0001"
+    }
+
+    /// The message of the trace, written with the text of `catalog`.
+    pub fn message(&self, catalog: &Catalog) -> String {
+        catalog.text(self.code(), &[("def", self.def.to_string())])
+    }
+}
+
+#[derive(Debug)]
+pub enum TypeCheckWarning {
+    /// The branch at `branch` in the branches of `def` only matches inputs
+    /// the branches before it already do.
+    UnreachableBranch {
+        def: Symbol,
+        branch: usize,
+    },
+    /// The branch at `branch` in the branches of `def` binds `name`, which
+    /// hides the global word of that name from its body.
+    ShadowedGlobal {
+        def: Symbol,
+        branch: usize,
+        name: Symbol,
+    },
+    /// No value of the data type can be built, as every constructor of it
+    /// takes one of a type no value can be built of.
+    UninhabitedType(Symbol),
+    /// The def `name` is used by nothing run from `main`. Of the defs
+    /// sharing that name, it is the one of type `overload`.
+    UnusedDef {
+        name: Symbol,
+        overload: Option<Type>,
+    },
+    /// The constructor is neither used nor matched by anything run from
+    /// `main`.
+    UnusedConstructor(Symbol),
+    /// The branch at `branch` in the branches of `def` binds `name` without
+    /// using it, which `_` would match the same without binding.
+    UnusedLocal {
+        def: Symbol,
+        branch: usize,
+        name: Symbol,
+    },
+}
+
+impl SymbolKind {
+    /// What the kind is called in `catalog`.
+    fn text(&self, catalog: &Catalog) -> String {
+        catalog.text(
+            match self {
+                SymbolKind::Constructor => "symbol.constructor",
+                SymbolKind::Def => "symbol.def",
+                SymbolKind::Method => "symbol.method",
+                SymbolKind::Builtin => "symbol.builtin",
+            },
+            &[],
+        )
+    }
+}
+
+impl fmt::Display for SymbolKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.text(&Catalog::english()))
+    }
+}
+
+/// The word an error is about, `&` when there is none.
+struct Used(Option<Symbol>);
+
+impl fmt::Display for Used {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            Some(word) => write!(f, "`{word}`"),
+            None => f.write_str("`&`"),
+        }
+    }
+}
+
+impl TypeCheckError {
+    /// The code the error is known by, which `sl explain` describes.
+    pub fn code(&self) -> &'static str {
+        match self {
+            TypeCheckError::UnboundSymbol { .. } => "E0001",
+            TypeCheckError::TypeMismatch { .. } => "E0002",
+            TypeCheckError::StackUnderflow { .. } => "E0003",
+            TypeCheckError::OutputMismatch { .. } => "E0004",
+            TypeCheckError::NoMatchingOverload { .. } => "E0005",
+            TypeCheckError::AmbiguousOverload(_) => "E0006",
+            TypeCheckError::OverlappingOverloads(_) => "E0007",
+            TypeCheckError::SymbolAlreadyDefined { .. } => "E0008",
+            TypeCheckError::TypeAlreadyDefined { .. } => "E0009",
+            TypeCheckError::ShadowedSymbol { .. } => "E0010",
+            TypeCheckError::RecursionLimitReached { .. } => "E0011",
+            TypeCheckError::InvalidTupleElement { .. } => "E0012",
+            TypeCheckError::NotAQuotation { .. } => "E0013",
+            TypeCheckError::NonNumericLiteral { .. } => "E0014",
+            TypeCheckError::NonExhaustivePatterns { .. } => "E0015",
+            TypeCheckError::CannotInfer { .. } => "E0016",
+            TypeCheckError::InvalidConstructorOutput(_) => "E0017",
+            TypeCheckError::UnknownConstructor { .. } => "E0018",
+            TypeCheckError::PatternArity { .. } => "E0019",
+            TypeCheckError::ConstructorMismatch { .. } => "E0020",
+            TypeCheckError::TuplePatternMismatch { .. } => "E0021",
+            TypeCheckError::EqualityPatternMismatch { .. } => "E0022",
+            TypeCheckError::DuplicatePatternBinding { .. } => "E0023",
+            TypeCheckError::LinearField(_) => "E0024",
+            TypeCheckError::DuplicatedLinear(_) => "E0025",
+            TypeCheckError::DroppedLinear(_) => "E0026",
+            TypeCheckError::CapturedLinear(_) => "E0027",
+            TypeCheckError::LinearTypeVariable(_) => "E0028",
+            TypeCheckError::NonTotalCall { .. } => "E0029",
+            TypeCheckError::NonStructuralRecursion(_) => "E0030",
+            TypeCheckError::UnknownType(_) => "E0031",
+            TypeCheckError::KindMismatch { .. } => "E0032",
+            TypeCheckError::CyclicTypeAlias(_) => "E0033",
+            TypeCheckError::UndeclaredIo { .. } => "E0034",
+            TypeCheckError::Hole { .. } => "E0035",
+            TypeCheckError::TraitAlreadyDefined(_) => "E0036",
+            TypeCheckError::UnknownTrait(_) => "E0037",
+            TypeCheckError::UndispatchableMethod(_) => "E0038",
+            TypeCheckError::InvalidImplType { .. } => "E0039",
+            TypeCheckError::ImplAlreadyDefined { .. } => "E0040",
+            TypeCheckError::UnknownMethod(_) => "E0041",
+            TypeCheckError::MethodAlreadyImplemented(_) => "E0042",
+            TypeCheckError::MissingMethod(_) => "E0043",
+            TypeCheckError::UnknownImplWord { .. } => "E0044",
+            TypeCheckError::ImplMismatch { .. } => "E0045",
+            TypeCheckError::NoImpl { .. } => "E0046",
+            TypeCheckError::UnderivableTrait(_) => "E0047",
+            TypeCheckError::UnderivableField { .. } => "E0048",
+            TypeCheckError::TooManyPatterns { .. } => "E0049",
+            TypeCheckError::CharPatternMismatch { .. } => "E0050",
+        }
+    }
+}
+
+impl TypeCheckWarning {
+    pub fn code(&self) -> &'static str {
+        match self {
+            TypeCheckWarning::UnreachableBranch { .. } => "W0001",
+            TypeCheckWarning::ShadowedGlobal { .. } => "W0002",
+            TypeCheckWarning::UninhabitedType(_) => "W0003",
+            TypeCheckWarning::UnusedDef { .. } => "W0004",
+            TypeCheckWarning::UnusedConstructor(_) => "W0005",
+            TypeCheckWarning::UnusedLocal { .. } => "W0006",
+        }
+    }
+}
+
+impl TypeCheckError {
+    /// The message of the error, written with the text of `catalog`.
+    pub fn message(&self, catalog: &Catalog) -> String {
+        let word = |word: &Option<Symbol>| Used(*word).to_string();
+        let branch = |branch: &usize| (branch + 1).to_string();
+        let arguments = match self {
+            TypeCheckError::TypeAlreadyDefined { name, .. } => vec![("name", name.to_string())],
+            TypeCheckError::SymbolAlreadyDefined { name, kind, previous, .. } => vec![
+                ("name", name.to_string()),
+                ("kind", kind.text(catalog)),
+                ("previous", previous.text(catalog)),
+            ],
+            TypeCheckError::TypeMismatch { def, word: used, .. } | TypeCheckError::StackUnderflow { def, word: used, .. } => {
+                vec![("def", def.to_string()), ("word", word(used))]
+            }
+            TypeCheckError::OutputMismatch { def, branch: index, .. } => {
+                vec![("def", def.to_string()), ("branch", branch(index))]
+            }
+            TypeCheckError::UnboundSymbol { def, name, suggestions } => {
+                let suggestions = match suggestions.split_last() {
+                    Some((last, rest)) => {
+                        let mut words: Vec<_> = rest.iter().map(|suggestion| format!("`{suggestion}`")).collect();
+                        let last = format!("`{last}`");
+                        words.push(if rest.is_empty() { last } else { format!("{} {last}", catalog.text("or", &[])) });
+                        catalog.text("E0001.suggestions", &[("words", words.join(", "))])
+                    }
+                    None => String::new(),
+                };
+                vec![("def", def.to_string()), ("name", name.to_string()), ("suggestions", suggestions)]
+            }
+            TypeCheckError::NoMatchingOverload { def, word, .. } | TypeCheckError::NonTotalCall { def, word } => {
+                vec![("def", def.to_string()), ("word", word.to_string())]
+            }
+            TypeCheckError::ShadowedSymbol { def, name } | TypeCheckError::UnknownConstructor { def, name } => {
+                vec![("def", def.to_string()), ("name", name.to_string())]
+            }
+            TypeCheckError::RecursionLimitReached { def }
+            | TypeCheckError::InvalidTupleElement { def, .. }
+            | TypeCheckError::CannotInfer { def }
+            | TypeCheckError::UndeclaredIo { def }
+            | TypeCheckError::Hole { def, .. } => vec![("def", def.to_string())],
+            TypeCheckError::NotAQuotation { def, ty } | TypeCheckError::NonNumericLiteral { def, ty } => {
+                vec![("def", def.to_string()), ("ty", ty.to_string())]
+            }
+            TypeCheckError::CharPatternMismatch { def, input } => {
+                vec![("def", def.to_string()), ("input", input.to_string())]
+            }
+            TypeCheckError::NonExhaustivePatterns { def, missing } => {
+                vec![("def", def.to_string()), ("missing", missing.clone())]
+            }
+            TypeCheckError::InvalidConstructorOutput(name)
+            | TypeCheckError::LinearField(name)
+            | TypeCheckError::DuplicatedLinear(name)
+            | TypeCheckError::DroppedLinear(name)
+            | TypeCheckError::CapturedLinear(name)
+            | TypeCheckError::LinearTypeVariable(name)
+            | TypeCheckError::NonStructuralRecursion(name)
+            | TypeCheckError::OverlappingOverloads(name)
+            | TypeCheckError::AmbiguousOverload(name)
+            | TypeCheckError::UnknownType(name)
+            | TypeCheckError::CyclicTypeAlias(name)
+            | TypeCheckError::TraitAlreadyDefined(name)
+            | TypeCheckError::UnknownTrait(name)
+            | TypeCheckError::UndispatchableMethod(name)
+            | TypeCheckError::UnknownMethod(name)
+            | TypeCheckError::MethodAlreadyImplemented(name)
+            | TypeCheckError::MissingMethod(name)
+            | TypeCheckError::UnderivableTrait(name) => vec![("name", name.to_string())],
+            TypeCheckError::PatternArity { def, constructor, expected, found } => vec![
+                ("def", def.to_string()),
+                ("constructor", constructor.to_string()),
+                ("expected", expected.to_string()),
+                ("found", found.to_string()),
+            ],
+            TypeCheckError::ConstructorMismatch { def, constructor, input, .. } => vec![
+                ("def", def.to_string()),
+                ("constructor", constructor.to_string()),
+                ("input", input.to_string()),
+            ],
+            TypeCheckError::TuplePatternMismatch { def, elements, input } => vec![
+                ("def", def.to_string()),
+                ("elements", elements.to_string()),
+                ("input", input.to_string()),
+            ],
+            TypeCheckError::EqualityPatternMismatch { def, name, first, second } => vec![
+                ("def", def.to_string()),
+                ("name", name.to_string()),
+                ("first", first.to_string()),
+                ("second", second.to_string()),
+            ],
+            TypeCheckError::KindMismatch { name, expected, found } => vec![
+                ("name", name.to_string()),
+                ("expected", expected.to_string()),
+                ("found", found.to_string()),
+            ],
+            TypeCheckError::InvalidImplType { trait_name, ty } | TypeCheckError::NoImpl { trait_name, ty } => {
+                vec![("trait_name", trait_name.to_string()), ("ty", ty.to_string())]
+            }
+            TypeCheckError::ImplAlreadyDefined { trait_name, type_name } => {
+                vec![("trait_name", trait_name.to_string()), ("type_name", type_name.to_string())]
+            }
+            TypeCheckError::UnknownImplWord { method, word } => {
+                vec![("method", method.to_string()), ("word", word.to_string())]
+            }
+            TypeCheckError::ImplMismatch { method, word, expected, found } => vec![
+                ("method", method.to_string()),
+                ("word", word.to_string()),
+                ("expected", expected.to_string()),
+                ("found", found.to_string()),
+            ],
+            TypeCheckError::DuplicatePatternBinding { def, name, branch: index, .. } => vec![
+                ("def", def.to_string()),
+                ("name", name.to_string()),
+                ("branch", branch(index)),
+            ],
+            TypeCheckError::UnderivableField { trait_name, constructor } => {
+                vec![("trait_name", trait_name.to_string()), ("constructor", constructor.to_string())]
+            }
+            TypeCheckError::TooManyPatterns { def, branch: index, patterns, inputs } => vec![
+                ("def", def.to_string()),
+                ("branch", branch(index)),
+                ("patterns", patterns.to_string()),
+                ("inputs", inputs.to_string()),
+            ],
+        };
+        catalog.text(self.code(), &arguments)
+    }
+}
+
+/// The message in English.
+impl fmt::Display for TypeCheckError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message(&Catalog::english()))
+    }
+}
+
+impl std::error::Error for TypeCheckError {}
+
+impl TypeCheckWarning {
+    /// The message of the warning, written with the text of `catalog`.
+    pub fn message(&self, catalog: &Catalog) -> String {
+        let arguments = match self {
+            TypeCheckWarning::UnreachableBranch { def, branch } => {
+                vec![("def", def.to_string()), ("branch", (branch + 1).to_string())]
+            }
+            TypeCheckWarning::UnusedDef { name, overload } => {
+                let overload = match overload {
+                    Some(ty) => catalog.text("W0004.overload", &[("type", ty.to_string())]),
+                    None => String::new(),
+                };
+                vec![("name", name.to_string()), ("overload", overload)]
+            }
+            TypeCheckWarning::ShadowedGlobal { name, .. }
+            | TypeCheckWarning::UninhabitedType(name)
+            | TypeCheckWarning::UnusedConstructor(name)
+            | TypeCheckWarning::UnusedLocal { name, .. } => vec![("name", name.to_string())],
+        };
+        catalog.text(self.code(), &arguments)
+    }
+}
+
+/// The message in English.
+impl fmt::Display for TypeCheckWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message(&Catalog::english()))
+    }
+}
+
+struct DefInfo {
+    /// The position of the def among the defs, in source order.
+    index: usize,
+    total: bool,
+    span: Span,
+}
+
+struct TraitInfo {
+    parameter: Symbol,
+    methods: Vec<Symbol>,
+}
+
+struct MethodInfo {
+    trait_name: Symbol,
+    /// The input whose type picks the impl.
+    input: usize,
+}
+
+struct ConstructorInfo {
+    type_name: Symbol,
+    /// The type variables of the constructor, those of the data type unless
+    /// it builds a more specific type.
+    parameters: Arc<[Symbol]>,
+    /// The argument types, in terms of `parameters`.
+    inputs: Arc<[Type]>,
+    /// The arguments of the type built, in terms of `parameters`.
+    output: Arc<[Type]>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Type {
+    /// A data type, applied to as many arguments as it has parameters.
+    Basic {
+        name: Symbol,
+        arguments: Arc<[Type]>,
+    },
+    /// A type variable. In the type of a global word it stands for any type
+    /// and is instantiated anew wherever the word is used.
+    Variable(Symbol),
+    /// A type the checker is yet to solve, only ever seen inside one def.
+    Unknown(u32),
+    /// Whatever a value left by an expression that didn't check would have
+    /// been. It unifies with any type, so the error isn't reported again.
+    Error,
+    Function {
+        inputs: Arc<[Type]>,
+        outputs: Arc<[Type]>,
+        /// The rows in `(..a inputs - ..b outputs)`. Without them the rest
+        /// of the stack is left alone.
+        rows: Option<(Row, Row)>,
+        /// Whether using the word performs IO, `!io` in source.
+        io: bool,
+    },
+    Quotation {
+        inputs: Arc<[Type]>,
+        outputs: Arc<[Type]>,
+        rows: Option<(Row, Row)>,
+        /// Whether unquoting it performs IO.
+        io: bool,
+        /// The type variables it is polymorphic over, instantiated anew
+        /// wherever it is unquoted.
+        forall: Arc<[Symbol]>,
+    },
+    Tuple(Arc<[Type]>),
+}
+
+/// Whatever is on the stack below the types a stack effect mentions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Row {
+    /// A row variable, `..name` in source. Instantiated like type variables.
+    Variable(Symbol),
+    /// A row the checker is yet to solve, only ever seen inside one def.
+    Unknown(u32),
+    /// What is below the part of the stack the def body, or the quotation
+    /// body at that depth, can see.
+    Rest(usize),
+    /// Whatever is left below the values following an expression that
+    /// didn't check, as many values of [`Type::Error`] as are consumed.
+    Error,
+}
+
+impl Type {
+    /// A data type without parameters.
+    pub fn basic(name: Symbol) -> Self {
+        Self::Basic {
+            name,
+            arguments: Arc::new([]),
+        }
+    }
+
+    pub fn int() -> Self {
+        Self::basic(Symbol::intern("Int"))
+    }
+
+    pub fn float() -> Self {
+        Self::basic(Symbol::intern("Float"))
+    }
+
+    pub fn string() -> Self {
+        Self::basic(Symbol::intern("String"))
+    }
+
+    pub fn char() -> Self {
+        Self::basic(Symbol::intern("Char"))
+    }
+
+    pub fn bool() -> Self {
+        Self::basic(Symbol::intern("Bool"))
+    }
+
+    /// The type without values, `absurd` is the only way to consume one.
+    pub fn void() -> Self {
+        Self::basic(Symbol::intern("Void"))
+    }
+
+    /// Rebuilds the type with the folder's replacements for its variables,
+    /// unknowns and rows.
+    fn fold(&self, folder: &mut impl Folder) -> Type {
+        fn fold_side(types: &[Type], row: Option<Row>, folder: &mut impl Folder) -> (Arc<[Type]>, Option<Row>) {
+            let Some(row) = row else {
+                return (types.iter().map(|ty| ty.fold(folder)).collect(), None);
+            };
+
+            let (row, mut prefix) = folder.fold_row(row);
+            prefix.extend(types.iter().map(|ty| ty.fold(folder)));
+            (prefix.into(), Some(row))
+        }
+
+        match self {
+            Type::Variable(_) | Type::Unknown(_) | Type::Error => folder.fold_leaf(self),
+            Type::Basic { name, arguments } => Type::Basic {
+                name: *name,
+                arguments: arguments.iter().map(|ty| ty.fold(folder)).collect(),
+            },
+            Type::Tuple(elements) => Type::Tuple(elements.iter().map(|ty| ty.fold(folder)).collect()),
+            Type::Function { inputs, outputs, rows, io } => {
+                let (inputs, input_row) = fold_side(inputs, rows.map(|(input_row, _)| input_row), folder);
+                let (outputs, output_row) = fold_side(outputs, rows.map(|(_, output_row)| output_row), folder);
+                Type::Function { inputs, outputs, rows: input_row.zip(output_row), io: *io }
+            }
+            Type::Quotation { inputs, outputs, rows, io, forall } => {
+                // The variables it is polymorphic over aren't the folder's.
+                let mut folder = Bound { folder, variables: forall };
+                let (inputs, input_row) = fold_side(inputs, rows.map(|(input_row, _)| input_row), &mut folder);
+                let (outputs, output_row) = fold_side(outputs, rows.map(|(_, output_row)| output_row), &mut folder);
+                Type::Quotation { inputs, outputs, rows: input_row.zip(output_row), io: *io, forall: forall.clone() }
+            }
+        }
+    }
+
+    /// Whether any variable or unknown of the type satisfies `leaf`, or any
+    /// of its rows satisfies `row`.
+    fn mentions(&self, leaf: &mut dyn FnMut(&Type) -> bool, row: &mut dyn FnMut(Row) -> bool) -> bool {
+        match self {
+            Type::Variable(_) | Type::Unknown(_) | Type::Error => leaf(self),
+            Type::Basic { arguments, .. } | Type::Tuple(arguments) => arguments.iter().any(|ty| ty.mentions(leaf, row)),
+            Type::Function { inputs, outputs, rows, .. } | Type::Quotation { inputs, outputs, rows, .. } => {
+                rows.is_some_and(|(input_row, output_row)| row(input_row) || row(output_row))
+                    || inputs.iter().chain(outputs.iter()).any(|ty| ty.mentions(leaf, row))
+            }
+        }
+    }
+}
+
+/// Types are shown as they are written in source, with `_` for the ones
+/// the checker didn't solve.
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fn side(f: &mut fmt::Formatter<'_>, row: Option<Row>, types: &[Type]) -> fmt::Result {
+            let mut separator = "";
+            if let Some(row) = row {
+                write!(f, "{row}")?;
+                separator = " ";
+            }
+            for ty in types {
+                write!(f, "{separator}{ty}")?;
+                separator = " ";
+            }
+            Ok(())
+        }
+
+        fn effect(f: &mut fmt::Formatter<'_>, inputs: &[Type], outputs: &[Type], rows: Option<(Row, Row)>, io: bool) -> fmt::Result {
+            side(f, rows.map(|(input_row, _)| input_row), inputs)?;
+            if !inputs.is_empty() || rows.is_some() {
+                f.write_str(" ")?;
+            }
+            f.write_str("-")?;
+            if !outputs.is_empty() || rows.is_some() {
+                f.write_str(" ")?;
+            }
+            side(f, rows.map(|(_, output_row)| output_row), outputs)?;
+            if io {
+                f.write_str(" !io")?;
+            }
+            Ok(())
+        }
+
+        match self {
+            Type::Basic { name, arguments } if arguments.is_empty() => write!(f, "{name}"),
+            Type::Basic { name, arguments } => {
+                write!(f, "{name}(")?;
+                side(f, None, arguments)?;
+                f.write_str(")")
+            }
+            Type::Variable(name) => write!(f, "{name}"),
+            Type::Unknown(_) | Type::Error => f.write_str("_"),
+            Type::Function { inputs, outputs, rows, io } => {
+                f.write_str("(")?;
+                effect(f, inputs, outputs, *rows, *io)?;
+                f.write_str(")")
+            }
+            Type::Quotation { inputs, outputs, rows, io, forall } => {
+                f.write_str("[")?;
+                for variable in forall.iter() {
+                    write!(f, "{variable} ")?;
+                }
+                if !forall.is_empty() {
+                    f.write_str("| ")?;
+                }
+                effect(f, inputs, outputs, *rows, *io)?;
+                f.write_str("]")
+            }
+            Type::Tuple(elements) => {
+                f.write_str("(")?;
+                for (index, ty) in elements.iter().enumerate() {
+                    if index > 0 {
+                        f.write_str(", ")?;
+                    }
+                    write!(f, "{ty}")?;
+                }
+                f.write_str(")")
+            }
+        }
+    }
+}
+
+impl fmt::Display for Row {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Row::Variable(name) => write!(f, "..{name}"),
+            Row::Unknown(_) | Row::Rest(_) | Row::Error => f.write_str(".._"),
+        }
+    }
+}
@@ -0,0 +1,386 @@
+//! Solving the unknowns of one def by unification.
+
+use std::{collections::HashMap, sync::Arc};
+
+use super::{Row, Type};
+use crate::symbol::Symbol;
+
+/// Types or stacks that can't be made the same, which whoever tried to
+/// turns into an error saying what they are of.
+pub(super) struct Mismatch;
+
+/// What the unknowns of one def were solved to.
+#[derive(Clone, Default)]
+pub(super) struct Unifier {
+    /// What each `Type::Unknown` was solved to, if it was.
+    unknowns: Vec<Option<Type>>,
+    /// What each `Row::Unknown` was solved to: another row with some types
+    /// on top of it.
+    rows: Vec<Option<(Row, Arc<[Type]>)>>,
+    /// How many type variables were made up to stand for those of
+    /// polymorphic quotations.
+    skolems: usize,
+}
+
+impl Unifier {
+    pub(super) fn fresh(&mut self) -> Type {
+        self.unknowns.push(None);
+        Type::Unknown(self.unknowns.len() as u32 - 1)
+    }
+
+    pub(super) fn fresh_row(&mut self) -> Row {
+        self.rows.push(None);
+        Row::Unknown(self.rows.len() as u32 - 1)
+    }
+
+    /// `ty` with every solved unknown replaced by its solution.
+    pub(super) fn resolve(&self, ty: &Type) -> Type {
+        ty.fold(&mut Resolution(self))
+    }
+
+    /// Follows `row` through the rows it was solved to, collecting the
+    /// types they put below `types`.
+    pub(super) fn resolve_stack(&self, mut row: Row, mut types: Vec<Type>) -> (Row, Vec<Type>) {
+        while let Row::Unknown(unknown) = row {
+            let Some((solution, prefix)) = &self.rows[unknown as usize] else {
+                break;
+            };
+            types.splice(0..0, prefix.iter().cloned());
+            row = *solution;
+        }
+        (row, types)
+    }
+
+    /// Solves unknowns so that `left` and `right` become the same type.
+    pub(super) fn unify(&mut self, left: &Type, right: &Type) -> Result<(), Mismatch> {
+        match (self.resolve(left), self.resolve(right)) {
+            (Type::Unknown(left), Type::Unknown(right)) if left == right => Ok(()),
+            (Type::Unknown(unknown), ty) | (ty, Type::Unknown(unknown)) => {
+                if ty.mentions(&mut |leaf| *leaf == Type::Unknown(unknown), &mut |_| false) {
+                    return Err(Mismatch);
+                }
+                self.unknowns[unknown as usize] = Some(ty);
+                Ok(())
+            }
+            (Type::Error, _) | (_, Type::Error) => Ok(()),
+            (Type::Variable(left), Type::Variable(right)) if left == right => Ok(()),
+            (Type::Basic { name: left_name, arguments: left_arguments },
+             Type::Basic { name: right_name, arguments: right_arguments }) if left_name == right_name => {
+                self.unify_all(&left_arguments, &right_arguments)
+            }
+            (Type::Tuple(left_elements), Type::Tuple(right_elements)) => self.unify_all(&left_elements, &right_elements),
+            (Type::Quotation { inputs: left_inputs, outputs: left_outputs, rows: left_rows, io: left_io, forall: left_forall },
+             Type::Quotation { inputs: right_inputs, outputs: right_outputs, rows: right_rows, io: right_io, forall: right_forall })
+                if !left_forall.is_empty() || !right_forall.is_empty() => {
+                if !left_forall.is_empty() && !right_forall.is_empty() && left_forall.len() != right_forall.len() {
+                    return Err(Mismatch);
+                }
+
+                // A quotation polymorphic over some variables has to work
+                // for any types they stand for, so they are replaced with
+                // variables unlike any other type. Being polymorphic over
+                // them too is the only way for the other one to match.
+                let names = if left_forall.is_empty() { &right_forall } else { &left_forall };
+                let skolems: Vec<_> = names.iter().map(|name| self.skolem(*name)).collect();
+                let skolemize = |inputs: Arc<[Type]>, outputs: Arc<[Type]>, rows, io, forall: Arc<[Symbol]>| {
+                    let mut instance: HashMap<_, _> = forall.iter().copied().zip(skolems.iter().cloned()).collect();
+                    Type::Quotation {
+                        inputs: inputs.iter().map(|ty| ty.fold(&mut instance)).collect(),
+                        outputs: outputs.iter().map(|ty| ty.fold(&mut instance)).collect(),
+                        rows,
+                        io,
+                        forall: Arc::new([]),
+                    }
+                };
+                let left = skolemize(left_inputs, left_outputs, left_rows, left_io, left_forall);
+                let right = skolemize(right_inputs, right_outputs, right_rows, right_io, right_forall);
+                self.unify(&left, &right)
+            }
+            (Type::Function { inputs: left_inputs, outputs: left_outputs, rows: left_rows, io: left_io },
+             Type::Function { inputs: right_inputs, outputs: right_outputs, rows: right_rows, io: right_io })
+            | (Type::Quotation { inputs: left_inputs, outputs: left_outputs, rows: left_rows, io: left_io, .. },
+               Type::Quotation { inputs: right_inputs, outputs: right_outputs, rows: right_rows, io: right_io, .. }) => {
+                if left_io != right_io {
+                    return Err(Mismatch);
+                }
+
+                if left_rows.is_none() && right_rows.is_none() {
+                    self.unify_all(&left_inputs, &right_inputs)?;
+                    return self.unify_all(&left_outputs, &right_outputs);
+                }
+
+                // An effect without rows leaves the rest of the stack alone,
+                // which is the same as having one fresh row on both sides.
+                let (left_input_row, left_output_row) = left_rows.unwrap_or_else(|| {
+                    let row = self.fresh_row();
+                    (row, row)
+                });
+                let (right_input_row, right_output_row) = right_rows.unwrap_or_else(|| {
+                    let row = self.fresh_row();
+                    (row, row)
+                });
+                self.unify_stacks((left_input_row, left_inputs.to_vec()), (right_input_row, right_inputs.to_vec()))?;
+                self.unify_stacks((left_output_row, left_outputs.to_vec()), (right_output_row, right_outputs.to_vec()))
+            }
+            _ => Err(Mismatch),
+        }
+    }
+
+    /// A type variable standing for the variable `name` of a polymorphic
+    /// quotation, distinct from every other and from any in source.
+    fn skolem(&mut self, name: Symbol) -> Type {
+        self.skolems += 1;
+        Type::Variable(Symbol::intern(&format!("{name}[{}]", self.skolems)))
+    }
+
+    pub(super) fn unify_all(&mut self, left: &[Type], right: &[Type]) -> Result<(), Mismatch> {
+        if left.len() != right.len() {
+            return Err(Mismatch);
+        }
+
+        for (left, right) in left.iter().zip(right) {
+            self.unify(left, right)?;
+        }
+        Ok(())
+    }
+
+    /// Solves unknowns so that a value of type `actual` can be used where
+    /// one of type `expected` is. A quotation without rows may perform IO
+    /// only if that is expected, be more polymorphic than expected and
+    /// consume fewer inputs than expected if it leaves the rest alone. Its
+    /// inputs are checked the other way round, tuples are checked element by
+    /// element and anything else has to be the same type.
+    pub(super) fn subsume(&mut self, actual: &Type, expected: &Type) -> Result<(), Mismatch> {
+        match (self.resolve(actual), self.resolve(expected)) {
+            (Type::Tuple(actual), Type::Tuple(expected)) => self.subsume_all(&actual, &expected),
+            (Type::Quotation { inputs: actual_inputs, outputs: actual_outputs, rows: None, io: actual_io, forall: actual_forall },
+             Type::Quotation { inputs: expected_inputs, outputs: expected_outputs, rows: None, io: expected_io, forall: expected_forall }) => {
+                if actual_io && !expected_io {
+                    return Err(Mismatch);
+                }
+
+                let mut actual_instance: HashMap<_, _> = actual_forall.iter().map(|variable| (*variable, self.fresh())).collect();
+                let mut expected_instance: HashMap<_, _> = expected_forall.iter().map(|variable| (*variable, self.skolem(*variable))).collect();
+                let [actual_inputs, actual_outputs] = [actual_inputs, actual_outputs]
+                    .map(|types| types.iter().map(|ty| ty.fold(&mut actual_instance)).collect::<Vec<_>>());
+                let [expected_inputs, expected_outputs] = [expected_inputs, expected_outputs]
+                    .map(|types| types.iter().map(|ty| ty.fold(&mut expected_instance)).collect::<Vec<_>>());
+
+                // The expected inputs it doesn't consume stay where they are.
+                let Some(passed) = expected_inputs.len().checked_sub(actual_inputs.len()) else {
+                    return Err(Mismatch);
+                };
+                if expected_outputs.len() != actual_outputs.len() + passed {
+                    return Err(Mismatch);
+                }
+                self.unify_all(&expected_inputs[..passed], &expected_outputs[..passed])?;
+                self.subsume_all(&expected_inputs[passed..], &actual_inputs)?;
+                self.subsume_all(&actual_outputs, &expected_outputs[passed..])
+            }
+            (actual, expected) => self.unify(&actual, &expected),
+        }
+    }
+
+    pub(super) fn subsume_all(&mut self, actual: &[Type], expected: &[Type]) -> Result<(), Mismatch> {
+        if actual.len() != expected.len() {
+            return Err(Mismatch);
+        }
+
+        for (actual, expected) in actual.iter().zip(expected) {
+            self.subsume(actual, expected)?;
+        }
+        Ok(())
+    }
+
+    /// Solves unknowns so that two stacks, each a row with types on top of
+    /// it, become the same. They are matched from the top down, whatever one
+    /// has left over once the other runs out has to be what the other's row
+    /// stands for.
+    pub(super) fn unify_stacks(&mut self, left: (Row, Vec<Type>), right: (Row, Vec<Type>)) -> Result<(), Mismatch> {
+        let (mut left, mut right) = (left, right);
+        loop {
+            let (left_row, mut left_types) = self.resolve_stack(left.0, left.1);
+            let (right_row, mut right_types) = self.resolve_stack(right.0, right.1);
+            match (left_types.last(), right_types.last()) {
+                (Some(left_type), Some(right_type)) => {
+                    let (left_type, right_type) = (left_type.clone(), right_type.clone());
+                    self.unify(&left_type, &right_type)?;
+                    left_types.pop();
+                    right_types.pop();
+                }
+                (None, _) => return self.bind_row(left_row, right_row, right_types),
+                (_, None) => return self.bind_row(right_row, left_row, left_types),
+            }
+            left = (left_row, left_types);
+            right = (right_row, right_types);
+        }
+    }
+
+    /// Solves `row` to `types` on top of `bottom`, unless it already is that.
+    pub(super) fn bind_row(&mut self, row: Row, bottom: Row, types: Vec<Type>) -> Result<(), Mismatch> {
+        if row == bottom && types.is_empty() {
+            return Ok(());
+        }
+
+        let (unknown, bottom, types) = match (row, bottom) {
+            (Row::Unknown(unknown), _) => (unknown, bottom, types),
+            (_, Row::Unknown(unknown)) if types.is_empty() => (unknown, row, types),
+            (Row::Error, _) | (_, Row::Error) => return Ok(()),
+            _ => return Err(Mismatch),
+        };
+
+        let occurs = bottom == Row::Unknown(unknown) || types.iter().any(|ty| {
+            self.resolve(ty).mentions(&mut |_| false, &mut |row| row == Row::Unknown(unknown))
+        });
+        if occurs {
+            return Err(Mismatch);
+        }
+        self.rows[unknown as usize] = Some((bottom, types.into()));
+        Ok(())
+    }
+}
+
+/// Replaces the variables and unknowns making up a type, see [`Type::fold`].
+pub(super) trait Folder {
+    fn fold_leaf(&mut self, leaf: &Type) -> Type;
+
+    /// What replaces `row`: another row, with some types on top of it.
+    fn fold_row(&mut self, row: Row) -> (Row, Vec<Type>);
+}
+
+/// Replaces the type variables and rows of a global word's type with
+/// unknowns that are fresh for this use of the word.
+pub(super) struct Instantiation<'unifier> {
+    unifier: &'unifier mut Unifier,
+    types: HashMap<Symbol, Type>,
+    rows: HashMap<Symbol, Row>,
+}
+
+impl<'unifier> Instantiation<'unifier> {
+    pub(super) fn new(unifier: &'unifier mut Unifier) -> Self {
+        Self {
+            unifier,
+            types: HashMap::new(),
+            rows: HashMap::new(),
+        }
+    }
+
+    /// What every type variable met was instantiated to.
+    pub(super) fn into_types(self) -> HashMap<Symbol, Type> {
+        self.types
+    }
+}
+
+impl Folder for Instantiation<'_> {
+    fn fold_leaf(&mut self, leaf: &Type) -> Type {
+        match leaf {
+            Type::Variable(variable) => self.types
+                .entry(*variable)
+                .or_insert_with(|| self.unifier.fresh())
+                .clone(),
+            _ => leaf.clone(),
+        }
+    }
+
+    fn fold_row(&mut self, row: Row) -> (Row, Vec<Type>) {
+        match row {
+            Row::Variable(variable) => (*self.rows.entry(variable).or_insert_with(|| self.unifier.fresh_row()), vec![]),
+            _ => (row, vec![]),
+        }
+    }
+}
+
+/// Leaves the type variables a quotation is polymorphic over alone,
+/// folding anything else with `folder`.
+pub(super) struct Bound<'folder> {
+    pub(super) folder: &'folder mut dyn Folder,
+    pub(super) variables: &'folder [Symbol],
+}
+
+impl Folder for Bound<'_> {
+    fn fold_leaf(&mut self, leaf: &Type) -> Type {
+        match leaf {
+            Type::Variable(variable) if self.variables.contains(variable) => leaf.clone(),
+            _ => self.folder.fold_leaf(leaf),
+        }
+    }
+
+    fn fold_row(&mut self, row: Row) -> (Row, Vec<Type>) {
+        self.folder.fold_row(row)
+    }
+}
+
+struct Resolution<'unifier>(&'unifier Unifier);
+
+impl Folder for Resolution<'_> {
+    fn fold_leaf(&mut self, leaf: &Type) -> Type {
+        match leaf {
+            Type::Unknown(unknown) => match &self.0.unknowns[*unknown as usize] {
+                Some(ty) => ty.fold(self),
+                None => leaf.clone(),
+            },
+            _ => leaf.clone(),
+        }
+    }
+
+    fn fold_row(&mut self, row: Row) -> (Row, Vec<Type>) {
+        let (row, types) = self.0.resolve_stack(row, vec![]);
+        (row, types.iter().map(|ty| ty.fold(self)).collect())
+    }
+}
+
+/// Names the unknowns left in an inferred type, making them variables.
+#[derive(Default)]
+pub(super) struct Generalization {
+    types: HashMap<u32, Type>,
+    rows: HashMap<u32, Row>,
+}
+
+impl Folder for Generalization {
+    fn fold_leaf(&mut self, leaf: &Type) -> Type {
+        let Type::Unknown(unknown) = leaf else {
+            return leaf.clone();
+        };
+
+        let count = self.types.len();
+        self.types
+            .entry(*unknown)
+            .or_insert_with(|| Type::Variable(Symbol::intern(&variable_name("", count))))
+            .clone()
+    }
+
+    fn fold_row(&mut self, row: Row) -> (Row, Vec<Type>) {
+        let Row::Unknown(unknown) = row else {
+            return (row, vec![]);
+        };
+
+        let count = self.rows.len();
+        let row = *self.rows
+            .entry(unknown)
+            .or_insert_with(|| Row::Variable(Symbol::intern(&variable_name("r", count))));
+        (row, vec![])
+    }
+}
+
+/// `a` to `z` and then `a1`, `b1` and so on, prefixed with `prefix`.
+fn variable_name(prefix: &str, index: usize) -> String {
+    let letter = (b'a' + (index % 26) as u8) as char;
+    match index / 26 {
+        0 => format!("{prefix}{letter}"),
+        round => format!("{prefix}{letter}{round}"),
+    }
+}
+
+/// Substitutes the arguments of a generic data type for its parameters.
+impl Folder for HashMap<Symbol, Type> {
+    fn fold_leaf(&mut self, leaf: &Type) -> Type {
+        match leaf {
+            Type::Variable(variable) => self.get(variable).cloned().unwrap_or_else(|| leaf.clone()),
+            _ => leaf.clone(),
+        }
+    }
+
+    fn fold_row(&mut self, row: Row) -> (Row, Vec<Type>) {
+        (row, vec![])
+    }
+}
@@ -0,0 +1,271 @@
+//! Whether a row of patterns matches anything the rows before it don't,
+//! which is what exhaustiveness and reachability of branches come down to.
+//!
+//! A `None` stands for a pattern matching anything. Each column comes with
+//! the type of what it matches, so that constructors building some other
+//! type than it are known not to be missing.
+
+use std::{collections::HashMap, fmt};
+
+use super::{
+    unify::{Folder, Unifier},
+    ConstructorInfo, Row as StackRow, Type, TypeChecker,
+};
+use crate::{
+    parser::{Pattern, PatternKind},
+    symbol::Symbol,
+};
+
+type Row<'pattern> = Vec<Option<&'pattern Pattern>>;
+
+/// A value no row matches, as the pattern matching only it. Parts of it
+/// any value could be are `_`.
+pub(super) enum Witness {
+    Any,
+    Constructor(Symbol, Vec<Witness>),
+    Tuple(Vec<Witness>),
+}
+
+impl fmt::Display for Witness {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Witness::Any => f.write_str("_"),
+            Witness::Constructor(name, arguments) if arguments.is_empty() => write!(f, "{name}"),
+            Witness::Constructor(name, arguments) => {
+                write!(f, "{name}(")?;
+                for (index, argument) in arguments.iter().enumerate() {
+                    if index > 0 {
+                        f.write_str(" ")?;
+                    }
+                    write!(f, "{argument}")?;
+                }
+                f.write_str(")")
+            }
+            Witness::Tuple(elements) => {
+                f.write_str("(")?;
+                for (index, element) in elements.iter().enumerate() {
+                    if index > 0 {
+                        f.write_str(", ")?;
+                    }
+                    write!(f, "{element}")?;
+                }
+                f.write_str(")")
+            }
+        }
+    }
+}
+
+/// What a pattern requires of the outermost shape of a value.
+#[derive(Clone, Copy, PartialEq)]
+enum Head {
+    Constructor(Symbol),
+    /// A tuple of so many elements, the only shape its type has.
+    Tuple(usize),
+    /// One of the characters, too many for every one to appear.
+    Char(char),
+}
+
+/// Stands for any type in place of the type variables and unknowns of a
+/// column's type, since a match may find out what they are.
+struct Anything;
+
+impl Folder for Anything {
+    fn fold_leaf(&mut self, _: &Type) -> Type {
+        Type::Error
+    }
+
+    fn fold_row(&mut self, _: StackRow) -> (StackRow, Vec<Type>) {
+        (StackRow::Error, vec![])
+    }
+}
+
+impl TypeChecker {
+    /// Whether some values of `types` are matched by `row` but by none of
+    /// `rows`.
+    pub(super) fn is_useful(&self, rows: &[Row], row: &[Option<&Pattern>], types: &[Type]) -> bool {
+        let Some((head, tail)) = row.split_first() else {
+            return rows.is_empty();
+        };
+
+        match head.and_then(Self::head) {
+            Some(head) => {
+                let specialized_row: Row = Self::arguments(row[0], self.arity(head)).into_iter().chain(tail.iter().copied()).collect();
+                self.is_useful(&self.specialize(rows, head), &specialized_row, &self.specialize_types(types, head))
+            }
+            // Anything matches, so it is useful for one of the shapes of the
+            // type, or for one that doesn't appear in the column.
+            None => match self.column_heads(rows, &types[0]) {
+                Some(heads) => heads.into_iter().any(|head| {
+                    let specialized_row: Row = std::iter::repeat_n(None, self.arity(head)).chain(tail.iter().copied()).collect();
+                    self.is_useful(&self.specialize(rows, head), &specialized_row, &self.specialize_types(types, head))
+                }),
+                None => {
+                    let default: Vec<Row> = rows
+                        .iter()
+                        .filter(|row| row[0].and_then(Self::head).is_none())
+                        .map(|row| row[1..].to_vec())
+                        .collect();
+                    self.is_useful(&default, tail, &types[1..])
+                }
+            },
+        }
+    }
+
+    /// Values of `types`, one for each column, that none of `rows` match,
+    /// if there are any. It takes the same steps as `is_useful` on a row of
+    /// wildcards.
+    pub(super) fn witness(&self, rows: &[Row], types: &[Type]) -> Option<Vec<Witness>> {
+        if types.is_empty() {
+            return rows.is_empty().then(Vec::new);
+        }
+
+        match self.column_heads(rows, &types[0]) {
+            Some(heads) => heads.into_iter().find_map(|head| {
+                let arity = self.arity(head);
+                let mut witness = self.witness(&self.specialize(rows, head), &self.specialize_types(types, head))?;
+                let arguments = witness.drain(..arity).collect();
+                witness.insert(0, match head {
+                    Head::Constructor(name) => Witness::Constructor(name, arguments),
+                    Head::Tuple(_) => Witness::Tuple(arguments),
+                    Head::Char(_) => unreachable!("characters are never all matched"),
+                });
+                Some(witness)
+            }),
+            None => {
+                let default: Vec<Row> = rows
+                    .iter()
+                    .filter(|row| row[0].and_then(Self::head).is_none())
+                    .map(|row| row[1..].to_vec())
+                    .collect();
+                let mut witness = self.witness(&default, &types[1..])?;
+                witness.insert(0, self.missing_constructor(rows, &types[0]));
+                Some(witness)
+            }
+        }
+    }
+
+    /// The types of the arguments of `constructor` in a value of type `ty`,
+    /// unless it builds another type than that one.
+    fn constructor_arguments(&self, constructor: Symbol, ty: &Type) -> Option<Vec<Type>> {
+        let ConstructorInfo { type_name, parameters, inputs, output } = &self.constructors[&constructor];
+        let mut unifier = Unifier::default();
+        let mut instance: HashMap<_, _> = parameters.iter().map(|parameter| (*parameter, unifier.fresh())).collect();
+        let constructs = Type::Basic {
+            name: *type_name,
+            arguments: output.iter().map(|ty| ty.fold(&mut instance)).collect(),
+        };
+        unifier.unify(&ty.fold(&mut Anything), &constructs).ok()?;
+        Some(inputs.iter().map(|input| unifier.resolve(&input.fold(&mut instance)).fold(&mut Anything)).collect())
+    }
+
+    /// The constructors of `type_name` that can build a value of type `ty`.
+    fn possible_constructors(&self, type_name: Symbol, ty: &Type) -> impl Iterator<Item = Symbol> + '_ {
+        let ty = ty.clone();
+        self.types[&type_name]
+            .iter()
+            .copied()
+            .filter(move |constructor| self.constructor_arguments(*constructor, &ty).is_some())
+    }
+
+    /// The types of the columns `types` become once the first one is
+    /// specialized to `head`.
+    fn specialize_types(&self, types: &[Type], head: Head) -> Vec<Type> {
+        let arguments = match (head, &types[0]) {
+            (Head::Constructor(constructor), ty) => self
+                .constructor_arguments(constructor, ty)
+                .unwrap_or_else(|| vec![Type::Error; self.arity(head)]),
+            (Head::Tuple(arity), Type::Tuple(elements)) if elements.len() == arity => elements.to_vec(),
+            (Head::Tuple(arity), _) => vec![Type::Error; arity],
+            (Head::Char(_), _) => vec![],
+        };
+        arguments.into_iter().chain(types[1..].iter().cloned()).collect()
+    }
+
+    /// A constructor of the type `ty` of the first column that doesn't
+    /// appear in it, applied to wildcards, or a wildcard when no constructor
+    /// does.
+    fn missing_constructor(&self, rows: &[Row], ty: &Type) -> Witness {
+        let seen: Vec<_> = rows
+            .iter()
+            .filter_map(|row| match row[0].and_then(Self::head) {
+                Some(Head::Constructor(name)) => Some(name),
+                _ => None,
+            })
+            .collect();
+        let Some(first) = seen.first() else {
+            return Witness::Any;
+        };
+        let type_name = self.constructors[first].type_name;
+        self.possible_constructors(type_name, ty)
+            .find(|constructor| !seen.contains(constructor))
+            .map_or(Witness::Any, |constructor| {
+                let arguments = (0..self.constructors[&constructor].inputs.len()).map(|_| Witness::Any).collect();
+                Witness::Constructor(constructor, arguments)
+            })
+    }
+
+    fn head(pattern: &Pattern) -> Option<Head> {
+        match &pattern.kind {
+            PatternKind::All(_) => None,
+            PatternKind::Constructor { name, .. } => Some(Head::Constructor(*name)),
+            PatternKind::Tuple(elements) => Some(Head::Tuple(elements.len())),
+            PatternKind::Char(ch) => Some(Head::Char(*ch)),
+        }
+    }
+
+    fn arity(&self, head: Head) -> usize {
+        match head {
+            Head::Constructor(constructor) => self.constructors[&constructor].inputs.len(),
+            Head::Tuple(arity) => arity,
+            Head::Char(_) => 0,
+        }
+    }
+
+    /// The patterns nested in `pattern`, or as many wildcards if it matches
+    /// anything.
+    fn arguments(pattern: Option<&Pattern>, arity: usize) -> Row<'_> {
+        match pattern.map(|pattern| &pattern.kind) {
+            Some(PatternKind::Constructor { arguments, .. } | PatternKind::Tuple(arguments)) => arguments.iter().map(Some).collect(),
+            Some(PatternKind::All(_) | PatternKind::Char(_)) | None => vec![None; arity],
+        }
+    }
+
+    /// The rows matching `head` in the first column, with the patterns
+    /// nested in it in place of that column.
+    fn specialize<'pattern>(&self, rows: &[Row<'pattern>], head: Head) -> Vec<Row<'pattern>> {
+        let arity = self.arity(head);
+        rows.iter()
+            .filter_map(|row| {
+                let (first, tail) = row.split_first().unwrap();
+                if first.and_then(Self::head).is_some_and(|first| first != head) {
+                    return None;
+                }
+                Some(Self::arguments(*first, arity).into_iter().chain(tail.iter().copied()).collect())
+            })
+            .collect()
+    }
+
+    /// Every shape a value of `ty`, the type matched in the first column,
+    /// can have, if all of them appear in it.
+    fn column_heads(&self, rows: &[Row], ty: &Type) -> Option<Vec<Head>> {
+        let mut type_name = None;
+        let mut seen = vec![];
+        for row in rows {
+            match row[0].and_then(Self::head) {
+                Some(Head::Constructor(name)) => {
+                    type_name = Some(self.constructors[&name].type_name);
+                    seen.push(name);
+                }
+                Some(tuple @ Head::Tuple(_)) => return Some(vec![tuple]),
+                Some(Head::Char(_)) => return None,
+                None => (),
+            }
+        }
+
+        let constructors: Vec<_> = self.possible_constructors(type_name?, ty).collect();
+        constructors
+            .iter()
+            .all(|constructor| seen.contains(constructor))
+            .then(|| constructors.into_iter().map(Head::Constructor).collect())
+    }
+}
@@ -0,0 +1,89 @@
+//! Running programs that checked.
+
+use std::{collections::HashMap, sync::Arc};
+
+use sl::{
+    engine::Engine,
+    evaluator::{RuntimeError, RuntimeResult, Value},
+    symbol::Symbol,
+    type_checker::Derivable,
+};
+
+fn run(source: &str) -> RuntimeResult<()> {
+    let engine = Engine::new();
+    let program = engine.check(source).unwrap_or_else(|diagnostics| panic!("{diagnostics:?}"));
+    engine.run(&program)
+}
+
+mod prelude {
+    use super::*;
+
+    #[test]
+    fn division() {
+        assert_eq!(run("def main(- Int Int)\n| = 7 2 / -7 2 mod\n"), Ok(()));
+    }
+
+    #[test]
+    fn division_by_zero() {
+        assert_eq!(run("def main(- Int)\n| = 1 0 /\n"), Err(RuntimeError::DivisionByZero));
+    }
+
+    #[test]
+    fn remainder_of_division_by_zero() {
+        assert_eq!(run("def main(- Int)\n| = 1 0 mod\n"), Err(RuntimeError::DivisionByZero));
+    }
+
+    #[test]
+    fn division_overflowing() {
+        assert_eq!(run("def main(- Int)\n| = -9223372036854775808 -1 /\n"), Ok(()));
+    }
+}
+
+mod main {
+    use super::*;
+
+    #[test]
+    fn missing() {
+        assert_eq!(run("def one(- Int)\n| = 1\n"), Err(RuntimeError::NoMain));
+    }
+
+    #[test]
+    fn taking_inputs() {
+        assert_eq!(run("def main(Int - Int)\n| n = n 1 +\n"), Err(RuntimeError::MainTakesInputs));
+    }
+
+    #[test]
+    fn more_than_one_branch() {
+        assert_eq!(run("def main(- Int)\n| = 1\n| = 2\n"), Ok(()));
+    }
+
+    #[test]
+    fn leaving_the_rest_of_the_stack_alone() {
+        assert_eq!(run("def main(..a - ..a Int)\n| = 1\n"), Ok(()));
+    }
+}
+
+mod debug {
+    use super::*;
+
+    #[test]
+    fn words() {
+        let method = Value::Method { name: Symbol::intern("show"), depth: 1, implementations: HashMap::new() };
+        let derived = Value::Derived { derivable: Derivable::Show, method: Symbol::intern("show") };
+        let values = [Value::Function(vec![]), Value::Builtin(Arc::new(|_| Ok(()))), Value::Constructor(2), method, derived];
+        assert_eq!(
+            format!("{values:?}"),
+            "[<def>, <builtin>, <constructor of 2>, <method show>, <derived Show using show>]",
+        );
+    }
+
+    #[test]
+    fn data() {
+        let values = [
+            Value::Basic { constructor: Symbol::intern("Succ"), values: vec![Value::bool(true)] },
+            Value::Tuple(vec![Value::Int(1), Value::Char('a')]),
+            Value::String("one".into()),
+        ];
+        assert_eq!(format!("{values:?}"), r#"[Succ[true], (1, 'a'), "one"]"#);
+    }
+}
@@ -0,0 +1,53 @@
+//! The example every explanation gives reports the diagnostic explained.
+
+use sl::{
+    diagnostics::{explain::explain, Diagnostic},
+    engine::Engine,
+};
+
+/// The codes explained with an example the default engine reports them
+/// for. Not T0001, which only traced defs report, E0010, only reported when
+/// shadowing is forbidden, nor E0011 and E0106, too deep for an example.
+fn codes() -> impl Iterator<Item = String> {
+    let errors = (1..=50).map(|number| format!("E{number:04}"));
+    let syntax_errors = (101..=114).map(|number| format!("E{number:04}"));
+    let warnings = (1..=6).map(|number| format!("W{number:04}"));
+    errors
+        .chain(syntax_errors)
+        .chain(warnings)
+        .filter(|code| !["E0010", "E0011", "E0106"].contains(&code.as_str()))
+}
+
+/// The first indented block of `explanation`, with the indentation removed.
+fn example(explanation: &str) -> String {
+    explanation
+        .lines()
+        .skip_while(|line| !line.starts_with("    "))
+        .take_while(|line| line.starts_with("    ") || line.is_empty())
+        .map(|line| line.strip_prefix("    ").unwrap_or(line))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[test]
+fn every_example_reports_its_code() {
+    let mut failures = vec![];
+    for code in codes() {
+        let explanation = explain(&code).unwrap_or_else(|| panic!("{code} isn't explained"));
+        let source = example(explanation);
+        let mut diagnostics: Vec<Diagnostic> = vec![];
+        Engine::new().check_reporting(&source, &mut diagnostics);
+        let reported: Vec<_> = diagnostics.iter().map(|diagnostic| diagnostic.code).collect();
+        if !reported.contains(&code.as_str()) {
+            failures.push(format!("{code} reported {reported:?} for\n{source}"));
+        }
+    }
+    assert!(failures.is_empty(), "{}", failures.join("\n\n"));
+}
+
+#[test]
+fn diagnostics_without_examples_are_explained() {
+    for code in ["T0001", "E0010", "E0011", "E0106"] {
+        assert!(explain(code).is_some(), "{code} isn't explained");
+    }
+}
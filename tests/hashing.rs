@@ -0,0 +1,97 @@
+//! The hashing words, run on the stack by themselves and used in programs.
+
+use sl::{
+    engine::{Engine, WordPack},
+    evaluator::{RuntimeError, RuntimeResult, Value},
+    hashing::Hashing,
+    symbol::Symbol,
+};
+
+/// What the word `name` leaves given `input`.
+fn run(name: &str, input: Value) -> RuntimeResult<Value> {
+    let builtin = Hashing.builtins().into_iter().find(|builtin| builtin.name == name).unwrap();
+    let mut stack = vec![input];
+    (builtin.run)(&mut stack)?;
+    Ok(stack.pop().unwrap())
+}
+
+fn hash(value: Value) -> i64 {
+    let Ok(Value::Int(hash)) = run("hash", value) else {
+        unreachable!()
+    };
+    hash
+}
+
+fn pair(left: &str, right: &str) -> Value {
+    Value::Tuple(vec![Value::String(left.into()), Value::String(right.into())])
+}
+
+#[test]
+fn the_same_on_every_run() {
+    assert_eq!(hash(Value::Int(1)), 8184434590310759885);
+    assert_eq!(hash(Value::String("one".into())), -6983699272914476357);
+}
+
+#[test]
+fn equal_values() {
+    let succ = |value| Value::Basic { constructor: Symbol::intern("Succ"), values: vec![value] };
+    assert_eq!(hash(succ(Value::bool(true))), hash(succ(Value::bool(true))));
+    assert_eq!(hash(pair("a", "bc")), hash(pair("a", "bc")));
+}
+
+#[test]
+fn values_built_from_the_same_bytes() {
+    assert_ne!(hash(pair("a", "bc")), hash(pair("ab", "c")));
+    assert_ne!(hash(Value::Int(97)), hash(Value::Char('a')));
+    assert_ne!(hash(Value::bool(true)), hash(Value::bool(false)));
+}
+
+#[test]
+fn quotations() {
+    let quotation = Value::Quotation { quotation: vec![], closure: vec![] };
+    assert_eq!(run("hash", Value::Tuple(vec![Value::Int(1), quotation])).err(), Some(RuntimeError::Unhashable));
+}
+
+#[test]
+fn used_in_a_program() {
+    let engine = Engine::new().word_pack(&Hashing);
+    let Ok(program) = engine.check("
+data Nat
+| Zero
+| Succ(Nat)
+
+def main(- Int Int)
+| = Zero Succ hash (1, 'c') hash
+") else {
+        panic!("doesn't check");
+    };
+    assert_eq!(engine.run(&program), Ok(()));
+}
+
+#[cfg(feature = "sha256")]
+mod sha256 {
+    use super::*;
+
+    fn sha256(message: &str) -> String {
+        let Ok(Value::String(digest)) = run("sha256", Value::String(message.into())) else {
+            unreachable!()
+        };
+        digest.to_string()
+    }
+
+    #[test]
+    fn digests() {
+        assert_eq!(sha256(""), "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855");
+        assert_eq!(sha256("abc"), "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad");
+        // Long enough for the padding to take a second block.
+        assert_eq!(
+            sha256("abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq"),
+            "248d6a61d20638b8e5c026930c3e6039a33ce45964ff2167f6ecedd419db06c1",
+        );
+    }
+
+    #[test]
+    fn of_the_utf_8_bytes() {
+        assert_eq!(sha256("é"), "4a99557e4033c3539de2eb65472017cad5f9557f7a0625a09f1c3f6e2ba69c4c");
+    }
+}
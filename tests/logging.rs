@@ -0,0 +1,65 @@
+//! What the logging words send to the host.
+
+use std::sync::{Arc, Mutex};
+
+use sl::{
+    engine::Engine,
+    logging::{LogLevel, LogRecord},
+};
+
+/// What running `source` logs at `level` or above, as level, target and
+/// message.
+fn logged(level: LogLevel, source: &str) -> Vec<(LogLevel, String, String)> {
+    let records = Arc::new(Mutex::new(vec![]));
+    let sink = records.clone();
+    let engine = Engine::new().logging(level, move |record: &LogRecord| sink.lock().unwrap().push(record.clone()));
+    let Ok(program) = engine.check(source) else {
+        panic!("{source} doesn't check");
+    };
+    engine.run(&program).unwrap();
+    let records = records.lock().unwrap();
+    records.iter().map(|record| (record.level, record.target.to_string(), record.message.to_string())).collect()
+}
+
+const EVERY_LEVEL: &str = "
+def main(- !io)
+| = \"app\" \"one\" log_debug \"app\" \"two\" log_info \"db\" \"three\" log_warn \"db\" \"four\" log_error
+";
+
+#[test]
+fn every_level() {
+    assert_eq!(logged(LogLevel::Debug, EVERY_LEVEL), [
+        (LogLevel::Debug, "app".into(), "one".into()),
+        (LogLevel::Info, "app".into(), "two".into()),
+        (LogLevel::Warn, "db".into(), "three".into()),
+        (LogLevel::Error, "db".into(), "four".into()),
+    ]);
+}
+
+#[test]
+fn filtered_by_level() {
+    assert_eq!(logged(LogLevel::Warn, EVERY_LEVEL), [
+        (LogLevel::Warn, "db".into(), "three".into()),
+        (LogLevel::Error, "db".into(), "four".into()),
+    ]);
+}
+
+#[test]
+fn logging_is_io() {
+    let engine = Engine::new().logging(LogLevel::Info, |_: &LogRecord| ());
+    let Err(diagnostics) = engine.check("
+def main(- )
+| = \"app\" \"one\" log_info
+") else {
+        panic!("checked");
+    };
+    assert_eq!(diagnostics[0].code, "E0034");
+}
+
+#[test]
+fn level_names() {
+    for level in [LogLevel::Debug, LogLevel::Info, LogLevel::Warn, LogLevel::Error] {
+        assert_eq!(LogLevel::from_name(level.name()), Some(level));
+    }
+    assert_eq!(LogLevel::from_name("trace"), None);
+}
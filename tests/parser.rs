@@ -0,0 +1,210 @@
+//! Parsing programs, and going on after syntax errors.
+
+use sl::{
+    diagnostics::Diagnostic,
+    parser::{Parser, TopLevel},
+    source_map::SourceMap,
+    tokens::Tokens,
+};
+
+/// The top levels of `source`, or the codes and lines of its syntax errors.
+fn parse(source: &str) -> Result<Vec<TopLevel>, Vec<(&'static str, usize)>> {
+    let mut diagnostics: Vec<Diagnostic> = vec![];
+    let top_levels = Parser::new(Tokens::new(source)).top_levels(&mut diagnostics);
+    let mut source_map = SourceMap::new();
+    let file = source_map.add("main.sl", source);
+    top_levels.ok_or_else(|| {
+        diagnostics
+            .iter()
+            .map(|diagnostic| (diagnostic.code, source_map.location(file, diagnostic.span.start).line))
+            .collect()
+    })
+}
+
+mod recovery {
+    use super::*;
+
+    #[test]
+    fn an_error_for_each_top_level() {
+        let source = "
+def f(Int - Int))
+| x = x
+
+def g(Int - Int))
+| x = x
+";
+        assert_eq!(parse(source).unwrap_err(), [("E0101", 2), ("E0101", 5)]);
+    }
+
+    #[test]
+    fn after_an_error_reading_the_next_top_level() {
+        // The signature of `f` isn't closed, so its outputs go on to `def g`.
+        let source = "
+def f(Int - Int
+
+def g(Int - Int))
+| x = x
+
+def h(Int - Int))
+| x = x
+";
+        assert_eq!(parse(source).unwrap_err(), [("E0101", 4), ("E0101", 4), ("E0101", 7)]);
+    }
+
+    #[test]
+    fn after_an_unclosed_quotation() {
+        let source = "
+def f(- [- Int])
+| = [1
+
+def g(Int - Int))
+| x = x
+";
+        assert_eq!(parse(source).unwrap_err(), [("E0101", 5), ("E0101", 5)]);
+    }
+
+    #[test]
+    fn after_an_error_in_an_attribute() {
+        let source = "
+#[cfg(feature = 1)]
+def f(- Int)
+| = 1
+
+def g(Int - Int))
+| x = x
+";
+        assert_eq!(parse(source).unwrap_err(), [("E0101", 2), ("E0101", 6)]);
+    }
+}
+
+mod names {
+    use sl::parser::{PatternKind, TypeExprKind};
+
+    use super::*;
+
+    #[test]
+    fn starting_with_a_caseless_letter() {
+        let top_levels = parse("
+def id(数 - 数)
+| 值 = 值
+").unwrap();
+        let TopLevel::Def { signature: Some(signature), branches, .. } = &top_levels[0] else {
+            unreachable!()
+        };
+        assert!(matches!(&signature.inputs[0].kind, TypeExprKind::Word(word) if word.as_str() == "数"));
+        assert!(matches!(&branches[0].patterns[0].kind, PatternKind::All(name) if name.as_str() == "值"));
+    }
+
+    #[test]
+    fn of_a_constructor_starting_with_a_caseless_letter() {
+        assert_eq!(parse("\ndata Nat\n| 零\n").unwrap_err(), [("E0103", 3)]);
+    }
+
+    #[test]
+    fn of_symbols() {
+        assert!(parse("\ndef →(Int - Int)\n| x = x\n\ndef main(- Int)\n| = 1 → !io+\n").is_ok());
+    }
+
+    #[test]
+    fn with_combining_accents() {
+        assert!(parse("\ndef main(- Int)\n| = 1 cafe\u{301}\n").is_ok());
+    }
+
+    #[test]
+    fn that_are_not_names() {
+        for word in ["1st", "x+", "²", "a→b"] {
+            let source = format!("\ndef main(- Int)\n| = 1 {word}\n");
+            assert_eq!(parse(&source).unwrap_err(), [("E0114", 3)], "{word}");
+        }
+    }
+}
+
+mod syntax {
+    use sl::{cfg::Cfg, parser::ExprKind};
+
+    use super::*;
+
+    /// The body of the only branch of the only def in `source`.
+    fn body(source: &str) -> Vec<ExprKind> {
+        let mut top_levels = parse(source).unwrap();
+        let Some(TopLevel::Def { mut branches, .. }) = top_levels.pop() else {
+            unreachable!()
+        };
+        branches.remove(0).body.into_iter().map(|expr| expr.kind).collect()
+    }
+
+    #[test]
+    fn comments() {
+        let source = "
+-- A line comment.
+{- A block comment {- nested -} -}
+def main(- Int)
+| = 1 {- inline -} 2 + -- trailing
+";
+        assert_eq!(body(source).len(), 3);
+    }
+
+    #[test]
+    fn unterminated_comment() {
+        assert_eq!(parse("\n{- outer {- inner -}\ndef main(- Int)\n| = 1\n").unwrap_err(), [("E0111", 2)]);
+    }
+
+    #[test]
+    fn literals() {
+        let body = body("\ndef main(- Int Int Float String Char)\n| = 1 -2 -0.5 \"a\\tb\" '\\n'\n");
+        assert!(matches!(
+            &body[..],
+            [ExprKind::IntLit(1), ExprKind::IntLit(-2), ExprKind::FloatLit(-0.5), ExprKind::StringLit(string), ExprKind::CharLit('\n')]
+                if string.as_str() == "a\tb"
+        ));
+    }
+
+    #[test]
+    fn subtraction() {
+        assert!(matches!(
+            &body("\ndef main(- Int)\n| = 3 2 - 1 -\n")[..],
+            [ExprKind::IntLit(3), ExprKind::IntLit(2), ExprKind::Word(minus), ExprKind::IntLit(1), ExprKind::Word(_)]
+                if minus.as_str() == "-"
+        ));
+    }
+
+    #[test]
+    fn most_negative_int() {
+        assert!(matches!(body("\ndef main(- Int)\n| = -9223372036854775808\n")[..], [ExprKind::IntLit(i64::MIN)]));
+        assert_eq!(parse("\ndef main(- Int)\n| = 9223372036854775808\n").unwrap_err(), [("E0108", 3)]);
+    }
+
+    #[test]
+    fn invalid_literals() {
+        assert_eq!(parse("\ndef main(- String)\n| = \"a\\qb\"\n").unwrap_err(), [("E0112", 3)]);
+        assert_eq!(parse("\ndef main(- Char)\n| = 'ab'\n").unwrap_err(), [("E0113", 3)]);
+    }
+
+    #[test]
+    fn cfg_attributes() {
+        let source = "
+#[cfg(feature = \"fast\")]
+def main(- Int)
+| = 1
+
+#[cfg(not(feature = \"fast\"))]
+def main(- Int)
+| = 2
+";
+        let main = |cfg: Cfg| {
+            let top_levels = Parser::new(Tokens::new(source)).with_cfg(cfg).top_levels(&mut vec![]).unwrap();
+            let [TopLevel::Def { branches, .. }] = &top_levels[..] else {
+                unreachable!()
+            };
+            branches[0].body[0].kind.clone()
+        };
+        assert!(matches!(main(Cfg::new().feature("fast")), ExprKind::IntLit(1)));
+        assert!(matches!(main(Cfg::new()), ExprKind::IntLit(2)));
+    }
+
+    #[test]
+    fn nested_too_deeply() {
+        let source = format!("\ndef main(- Int)\n| = {}1{}\n", "[".repeat(300), "]".repeat(300));
+        assert_eq!(parse(&source).unwrap_err().first().map(|(code, _)| *code), Some("E0106"));
+    }
+}
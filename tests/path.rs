@@ -0,0 +1,87 @@
+//! The path words, run on the stack by themselves and used in programs.
+
+use std::path::MAIN_SEPARATOR_STR;
+
+use sl::{
+    engine::{Engine, WordPack},
+    evaluator::Value,
+    path::Paths,
+};
+
+/// What the word `name` leaves given the Strings `inputs`, bottom first.
+fn run(name: &str, inputs: &[&str]) -> String {
+    let builtin = Paths.builtins().into_iter().find(|builtin| builtin.name == name).unwrap();
+    let mut stack: Vec<_> = inputs.iter().map(|input| Value::String((*input).into())).collect();
+    (builtin.run)(&mut stack).unwrap();
+    let [Value::String(output)] = &stack[..] else {
+        panic!("{stack:?}");
+    };
+    output.to_string()
+}
+
+/// `path` with `/` replaced by the separator of the platform.
+fn native(path: &str) -> String {
+    path.replace('/', MAIN_SEPARATOR_STR)
+}
+
+#[test]
+fn join() {
+    assert_eq!(run("path_join", &["notes", "todo.txt"]), native("notes/todo.txt"));
+    assert_eq!(run("path_join", &["", "todo.txt"]), "todo.txt");
+}
+
+#[cfg(unix)]
+#[test]
+fn join_with_an_absolute_path() {
+    assert_eq!(run("path_join", &["notes", "/etc"]), "/etc");
+}
+
+#[test]
+fn parent() {
+    assert_eq!(run("path_parent", &["notes/drafts/todo.txt"]), native("notes/drafts"));
+    assert_eq!(run("path_parent", &["todo.txt"]), "");
+    assert_eq!(run("path_parent", &["/"]), "");
+}
+
+#[test]
+fn extension() {
+    assert_eq!(run("path_extension", &["notes/todo.txt"]), "txt");
+    assert_eq!(run("path_extension", &["archive.tar.gz"]), "gz");
+    assert_eq!(run("path_extension", &[".bashrc"]), "");
+    assert_eq!(run("path_extension", &["notes"]), "");
+}
+
+#[test]
+fn normalize() {
+    assert_eq!(run("path_normalize", &["notes/./drafts/../todo.txt"]), native("notes/todo.txt"));
+    assert_eq!(run("path_normalize", &["../notes/../../todo.txt"]), native("../../todo.txt"));
+    assert_eq!(run("path_normalize", &["notes/.."]), ".");
+}
+
+#[cfg(unix)]
+#[test]
+fn normalize_above_the_root() {
+    assert_eq!(run("path_normalize", &["/../notes"]), "/notes");
+}
+
+#[test]
+fn used_in_a_program() {
+    let engine = Engine::new().word_pack(&Paths);
+    let program = engine.check("
+def main(- String)
+| = \"notes\" \"todo.txt\" path_join path_extension
+").unwrap();
+    assert_eq!(engine.run(&program), Ok(()));
+}
+
+#[test]
+fn given_something_else_than_a_string() {
+    let engine = Engine::new().word_pack(&Paths);
+    let Err(diagnostics) = engine.check("
+def main(- String)
+| = 'c' path_parent
+") else {
+        panic!("checked");
+    };
+    assert_eq!(diagnostics[0].code, "E0002");
+}
@@ -0,0 +1,35 @@
+//! Words the lexer reads as names and those it doesn't.
+
+use sl::{
+    symbol::Symbol,
+    tokens::{Token, Tokens},
+};
+
+fn token(word: &str) -> Token {
+    let tokens: Vec<_> = Tokens::new(word).collect();
+    let [token] = &tokens[..] else {
+        panic!("`{word}` isn't one token: {tokens:?}");
+    };
+    token.clone()
+}
+
+#[test]
+fn names_in_scripts_with_combining_marks() {
+    // Devanagari and Tamil have vowel signs and viramas, Thai has vowel
+    // and tone marks, none of which are letters.
+    for word in ["हिन्दी", "தமிழ்", "ภาษาไทย", "x_٣"] {
+        assert_eq!(token(word), Token::Word(Symbol::intern(word)), "{word}");
+    }
+}
+
+#[test]
+fn names_with_connectors() {
+    assert_eq!(token("a‿b"), Token::Word(Symbol::intern("a‿b")));
+}
+
+#[test]
+fn words_with_symbols_after_a_letter() {
+    for word in ["x+", "a→b", "x²"] {
+        assert_eq!(token(word), Token::InvalidName, "{word}");
+    }
+}
@@ -0,0 +1,867 @@
+//! Programs the type checker accepts and rejects, by feature.
+
+use sl::{
+    diagnostics::{Diagnostic, DiagnosticKind},
+    engine::Engine,
+    type_checker::TypeCheckError,
+};
+
+/// The diagnostics checking `source` reports.
+fn diagnostics(source: &str) -> Vec<Diagnostic> {
+    let mut diagnostics: Vec<Diagnostic> = vec![];
+    Engine::new().check_reporting(source, &mut diagnostics);
+    diagnostics
+}
+
+/// The codes of the errors checking `source` reports, empty if it checks.
+fn errors(source: &str) -> Vec<&'static str> {
+    diagnostics(source)
+        .iter()
+        .filter(|diagnostic| diagnostic.is_error())
+        .map(|diagnostic| diagnostic.code)
+        .collect()
+}
+
+fn warnings(source: &str) -> Vec<&'static str> {
+    diagnostics(source)
+        .iter()
+        .filter(|diagnostic| !diagnostic.is_error())
+        .map(|diagnostic| diagnostic.code)
+        .collect()
+}
+
+fn accepts(source: &str) {
+    assert_eq!(errors(source), Vec::<&str>::new(), "{source}");
+}
+
+fn rejects(source: &str, code: &str) {
+    assert!(errors(source).contains(&code), "expected {code}, got {:?} for {source}", errors(source));
+}
+
+const NAT: &str = "
+data Nat
+| Zero
+| Succ(Nat)
+";
+
+mod totality {
+    use super::*;
+
+    #[test]
+    fn recursion_on_a_smaller_input() {
+        accepts(&format!("{NAT}
+total def double(Nat - Nat)
+| Zero = Zero
+| Succ(n) = n double Succ Succ
+"));
+    }
+
+    #[test]
+    fn recursion_shrinking_the_same_input_of_two() {
+        accepts(&format!("{NAT}
+total def add(Nat Nat - Nat)
+| Zero y = y
+| Succ(x) y = x y Succ add
+"));
+    }
+
+    #[test]
+    fn recursion_on_an_input_that_isnt_smaller() {
+        rejects(&format!("{NAT}
+total def grow(Nat - Nat)
+| n = n Succ grow
+"), "E0030");
+    }
+
+    #[test]
+    fn recursion_shrinking_inputs_in_turn() {
+        // `Zero Succ Zero Succ f` would run forever.
+        rejects(&format!("{NAT}
+total def f(Nat Nat - Nat)
+| Succ(a) y = a y Succ Succ f
+| Zero Succ(b) = Zero Succ b f
+| Zero Zero = Zero
+"), "E0030");
+    }
+
+    #[test]
+    fn recursion_from_a_quotation() {
+        rejects(&format!("{NAT}
+total def loop(Nat - Nat)
+| Succ(n) = [n loop] &
+| Zero = Zero
+"), "E0030");
+    }
+
+    #[test]
+    fn use_of_a_partial_def() {
+        rejects(&format!("{NAT}
+def id(Nat - Nat)
+| n = n
+
+total def f(Nat - Nat)
+| n = n id
+"), "E0029");
+    }
+}
+
+mod traits {
+    use super::*;
+
+    const SHOW: &str = "
+trait Show(a)
+| show(a - String)
+
+impl Show(Int)
+| show = show_int
+
+def show_int(Int - String)
+| _ = \"int\"
+";
+
+    #[test]
+    fn method_on_a_type_with_an_impl() {
+        accepts(&format!("{SHOW}
+def main(- !io)
+| = 1 show print
+"));
+    }
+
+    #[test]
+    fn method_on_a_type_without_an_impl() {
+        rejects(&format!("{SHOW}
+def main(- !io)
+| = 1.5 show print
+"), "E0046");
+    }
+
+    #[test]
+    fn method_on_a_tuple_of_types_with_impls() {
+        // There is no impl for the tuple to dispatch to.
+        rejects(&format!("{SHOW}
+def main(- !io)
+| = (1, 2) show print
+"), "E0046");
+    }
+
+    #[test]
+    fn derived_impl_with_a_tuple_field() {
+        accepts(&format!("{SHOW}
+data Pair derives (Show)
+| Pair((Int, Int))
+
+def main(- !io)
+| = (1, 2) Pair show print
+"));
+    }
+
+    #[test]
+    fn derived_impl_with_a_field_without_an_impl() {
+        rejects(&format!("{SHOW}
+data Pair derives (Show)
+| Pair((Int, Float))
+"), "E0048");
+    }
+}
+
+mod quotations {
+    use super::*;
+
+    #[test]
+    fn annotated_with_types() {
+        accepts("
+def main(- Int)
+| = 2 [Int - 1 +] &
+");
+    }
+
+    #[test]
+    fn annotated_with_type_variables_of_the_signature() {
+        accepts("
+def keep(a - [a - a])
+| _ = [a -]
+
+def main(- Int)
+| = 1 2 keep &
+");
+    }
+
+    #[test]
+    fn starting_with_a_subtraction_of_locals() {
+        accepts("
+def sub(Int Int - Int)
+| a b = [a b -] &
+
+def main(- Int)
+| = 5 3 sub
+");
+    }
+
+    #[test]
+    fn starting_with_a_subtraction_of_a_tuple() {
+        rejects("
+def main(- Int)
+| = 1 [(x, y) -] &
+", "E0001");
+    }
+
+    #[test]
+    fn annotated_with_an_undeclared_type() {
+        rejects("
+def main(- Int)
+| = 1 [Box(Int) - ] &
+", "E0031");
+    }
+
+    #[test]
+    fn unquoted_on_the_wrong_stack() {
+        rejects("
+def main(- Int)
+| = \"one\" [Int - 1 +] &
+", "E0002");
+    }
+}
+
+mod names {
+    use super::*;
+
+    #[test]
+    fn locals_and_type_variables_starting_with_a_caseless_letter() {
+        accepts("
+def 同じ(数 - 数)
+| 値 = 値
+
+def main(- Int)
+| = 1 同じ
+");
+    }
+
+    #[test]
+    fn type_parameter_starting_with_a_caseless_letter() {
+        accepts("
+data Box(数)
+| Box(数)
+
+def main(- Box(Int))
+| = 1 Box
+");
+    }
+}
+
+mod unification {
+    use super::*;
+
+    #[test]
+    fn polymorphic_def_used_at_two_types() {
+        accepts("
+def swap(a b - b a)
+| x y = y x
+
+def main(- String Int Int Char)
+| = 1 \"one\" swap 'a' 2 swap
+");
+    }
+
+    #[test]
+    fn type_variable_bound_to_two_types() {
+        rejects("
+def same(a a - a)
+| x _ = x
+
+def main(- Char)
+| = 'c' \"one\" same
+", "E0002");
+    }
+
+    #[test]
+    fn rows_passing_the_rest_of_the_stack_through() {
+        accepts("
+def apply(..a [..a - ..b] - ..b)
+| q = q &
+
+def main(- String Int)
+| = \"two\" 1 2 [+] apply
+");
+    }
+
+    #[test]
+    fn quotations_of_different_stack_effects() {
+        rejects("
+def twice([Int - Int] Int - Int)
+| f n = n f & f &
+
+def main(- Int)
+| = [Int - \"one\"] 1 twice
+", "E0002");
+    }
+
+    #[test]
+    fn too_few_values() {
+        rejects("
+def main(- Int)
+| = 1 +
+", "E0003");
+    }
+
+    #[test]
+    fn outputs_not_left() {
+        rejects("
+def main(- Int)
+| = 1 2
+", "E0004");
+    }
+}
+
+mod inference {
+    use super::*;
+
+    #[test]
+    fn def_without_a_signature() {
+        accepts("
+def double
+| n = n n +
+
+def main(- Int)
+| = 2 double
+");
+    }
+
+    #[test]
+    fn generalized_def_used_at_two_types() {
+        accepts("
+def dup
+| x = x x
+
+def main(- Int Int String String)
+| = 1 dup \"one\" dup
+");
+    }
+
+    #[test]
+    fn def_using_an_inferred_def() {
+        accepts("
+def double
+| n = n n +
+
+def quadruple
+| n = n double double
+
+def main(- Int)
+| = 1 quadruple
+");
+    }
+
+    #[test]
+    fn mutually_recursive_defs_without_signatures() {
+        rejects("
+def even
+| n = n odd
+
+def odd
+| n = n even
+", "E0016");
+    }
+
+    #[test]
+    fn numeric_literal_defaulting_to_int() {
+        accepts("
+def main(- Int)
+| = 1 2 +
+");
+    }
+
+    #[test]
+    fn numeric_literal_as_a_float() {
+        accepts("
+def main(- Float)
+| = 1 2.5 +.
+");
+    }
+
+    #[test]
+    fn numeric_literal_shown_as_an_int_in_errors() {
+        let diagnostics = diagnostics("
+def main(- String)
+| = [1 +] 1 \"s\"
+");
+        let DiagnosticKind::TypeCheck(TypeCheckError::OutputMismatch { stack, .. }) = &diagnostics[0].kind else {
+            panic!("{:?}", diagnostics[0].kind);
+        };
+        let stack: Vec<_> = stack.iter().map(ToString::to_string).collect();
+        assert_eq!(stack, ["[Int - Int]", "Int", "String"]);
+    }
+}
+
+mod usefulness {
+    use super::*;
+
+    #[test]
+    fn every_constructor_matched() {
+        accepts(&format!("{NAT}
+def pred(Nat - Nat)
+| Zero = Zero
+| Succ(n) = n
+"));
+    }
+
+    #[test]
+    fn nested_constructors_matched() {
+        accepts(&format!("{NAT}
+def half(Nat - Nat)
+| Zero = Zero
+| Succ(Zero) = Zero
+| Succ(Succ(n)) = n half Succ
+"));
+    }
+
+    #[test]
+    fn nested_constructor_left_out() {
+        rejects(&format!("{NAT}
+def half(Nat - Nat)
+| Zero = Zero
+| Succ(Succ(n)) = n half Succ
+"), "E0015");
+    }
+
+    #[test]
+    fn witness_of_what_is_left_out() {
+        let source = format!("{NAT}
+def half(Nat - Nat)
+| Zero = Zero
+| Succ(Succ(n)) = n half Succ
+");
+        let diagnostics = diagnostics(&source);
+        assert!(diagnostics[0].message.contains("Succ(Zero)"), "{}", diagnostics[0].message);
+    }
+
+    #[test]
+    fn bools_and_tuples_matched() {
+        accepts("
+def and((Bool, Bool) - Bool)
+| (true, true) = true
+| (_, false) = false
+| (false, _) = false
+");
+    }
+
+    #[test]
+    fn chars_without_a_wildcard() {
+        rejects("
+def vowel(Char - Bool)
+| 'a' = true
+| 'e' = true
+", "E0015");
+    }
+
+    #[test]
+    fn chars_with_a_wildcard() {
+        accepts("
+def vowel(Char - Bool)
+| 'a' = true
+| 'e' = true
+| _ = false
+");
+    }
+
+    #[test]
+    fn branch_after_a_wildcard() {
+        assert_eq!(warnings("
+def main(Int - Int)
+| _ = 1
+| n = n
+"), ["W0001"]);
+    }
+
+    #[test]
+    fn branches_on_several_inputs() {
+        accepts(&format!("{NAT}
+def min(Nat Nat - Nat)
+| Zero _ = Zero
+| _ Zero = Zero
+| Succ(a) Succ(b) = a b min Succ
+"));
+    }
+}
+
+mod bindings {
+    use super::*;
+
+    const EQ: &str = "
+trait Eq(a)
+| eq(a a - Bool)
+
+def int_eq(Int Int - Bool)
+| a b = a b =
+
+impl Eq(Int)
+| eq = int_eq
+";
+
+    #[test]
+    fn name_bound_twice_compared_with_eq() {
+        accepts(&format!("{EQ}
+def same(Int Int - Bool)
+| a a = true
+| _ _ = false
+"));
+    }
+
+    #[test]
+    fn name_bound_twice_without_eq() {
+        rejects("
+def same(Int Int - Bool)
+| a a = true
+| _ _ = false
+", "E0023");
+    }
+
+    #[test]
+    fn name_bound_twice_to_different_types() {
+        rejects(&format!("{EQ}
+def same(Int String - Bool)
+| a a = true
+| _ _ = false
+"), "E0022");
+    }
+
+    #[test]
+    fn global_word_shadowed() {
+        assert!(warnings("
+def main(Int - Int)
+| print = print
+").contains(&"W0002"));
+    }
+}
+
+mod linearity {
+    use super::*;
+
+    const FILE: &str = "
+linear data File
+| File(Int)
+";
+
+    #[test]
+    fn local_used_once() {
+        accepts(&format!("{FILE}
+def pass(File - File)
+| f = f
+
+def close(File - Int)
+| File(n) = n
+"));
+    }
+
+    #[test]
+    fn local_used_twice() {
+        rejects(&format!("{FILE}
+def twice(File - File File)
+| f = f f
+"), "E0025");
+    }
+
+    #[test]
+    fn local_dropped() {
+        rejects(&format!("{FILE}
+def forget(File - Int)
+| _ = 1
+"), "E0026");
+    }
+
+    #[test]
+    fn local_captured() {
+        rejects(&format!("{FILE}
+def later(File - [- File])
+| f = [- f]
+"), "E0027");
+    }
+
+    #[test]
+    fn given_to_a_type_variable() {
+        rejects(&format!("{FILE}
+def drop(a - )
+| _ =
+
+def main(- )
+| = 1 File drop
+"), "E0028");
+    }
+
+    #[test]
+    fn field_of_a_type_that_isnt_linear() {
+        rejects(&format!("{FILE}
+data Box
+| Box(File)
+"), "E0024");
+    }
+}
+
+mod outputs {
+    use super::*;
+
+    const EXPR: &str = "
+data Expr(a)
+| IntLit(Int - Expr(Int))
+| BoolLit(Bool - Expr(Bool))
+";
+
+    #[test]
+    fn matching_refines_the_type() {
+        accepts(&format!("{EXPR}
+def eval(Expr(a) - a)
+| IntLit(n) = n
+| BoolLit(b) = b
+
+def main(- Int)
+| = 1 IntLit eval
+"));
+    }
+
+    #[test]
+    fn constructors_of_an_other_type_need_no_branch() {
+        accepts(&format!("{EXPR}| If(Expr(Bool) Expr(a) Expr(a) - Expr(a))
+
+def int(Expr(Int) - Int)
+| IntLit(n) = n
+| If(_ t _) = t int
+
+def main(- Int)
+| = 1 IntLit int
+"));
+    }
+
+    #[test]
+    fn nested_constructors_of_an_other_type_need_no_branch() {
+        let source = format!("{EXPR}| If(Expr(Bool) Expr(a) Expr(a) - Expr(a))
+
+def int(Expr(Int) - Int)
+| IntLit(n) = n
+| If(BoolLit(_) t _) = t int
+| If(If(_ _ _) t _) = t int
+| _ = 0
+
+def main(- Int)
+| = 1 IntLit int
+");
+        accepts(&source);
+        assert_eq!(warnings(&source), ["W0001"]);
+    }
+
+    #[test]
+    fn constructors_of_the_type_still_need_a_branch() {
+        let source = format!("{EXPR}| If(Expr(Bool) Expr(a) Expr(a) - Expr(a))
+
+def int(Expr(Int) - Int)
+| IntLit(n) = n
+| If(BoolLit(_) t _) = t int
+
+def main(- Int)
+| = 1 IntLit int
+");
+        let diagnostics = diagnostics(&source);
+        assert_eq!(diagnostics[0].code, "E0015");
+        assert!(diagnostics[0].message.contains("`If(If(_ _ _) _ _)`"), "{}", diagnostics[0].message);
+    }
+
+    #[test]
+    fn output_of_an_other_type() {
+        rejects(&format!("{NAT}
+data Box(a)
+| Box(a - Nat)
+"), "E0017");
+    }
+
+    #[test]
+    fn built_at_the_wrong_type() {
+        rejects(&format!("{EXPR}
+def main(- Expr(Bool))
+| = 1 IntLit
+"), "E0004");
+    }
+}
+
+mod rank_two {
+    use super::*;
+
+    #[test]
+    fn polymorphic_quotation_used_at_two_types() {
+        accepts("
+def both([a | a - a] Int String - Int String)
+| f n s = n f & s f &
+
+def id(a - a)
+| x = x
+
+def main(- Int String)
+| = [id] 1 \"one\" both
+");
+    }
+
+    #[test]
+    fn monomorphic_quotation_given_for_a_polymorphic_one() {
+        rejects("
+def both([a | a - a] Int String - Int String)
+| f n s = n f & s f &
+
+def main(- Int String)
+| = [Int - 1 +] 1 \"one\" both
+", "E0002");
+    }
+}
+
+mod effects {
+    use super::*;
+
+    #[test]
+    fn io_declared() {
+        accepts("
+def main(- !io)
+| = \"hello\" print
+");
+    }
+
+    #[test]
+    fn io_undeclared() {
+        rejects("
+def main(- )
+| = \"hello\" print
+", "E0034");
+    }
+
+    #[test]
+    fn io_in_a_quotation_not_unquoted() {
+        accepts("
+def later(- [- !io])
+| = [- \"hello\" print]
+");
+    }
+
+    #[test]
+    fn io_in_a_quotation_unquoted() {
+        rejects("
+def now(- )
+| = [- \"hello\" print] &
+", "E0034");
+    }
+}
+
+mod overloads {
+    use super::*;
+
+    const SIZE: &str = "
+def size(Int - Int)
+| n = n
+
+def size(String - Int)
+| _ = 1
+";
+
+    #[test]
+    fn resolved_by_the_stack() {
+        accepts(&format!("{SIZE}
+def main(- Int Int)
+| = 2 size \"two\" size
+"));
+    }
+
+    #[test]
+    fn no_overload_fits() {
+        rejects(&format!("{SIZE}
+def main(- Int)
+| = 'c' size
+"), "E0005");
+    }
+
+    #[test]
+    fn overlapping() {
+        rejects("
+def size(Int - Int)
+| n = n
+
+def size(a - Int)
+| _ = 1
+", "E0007");
+    }
+
+    #[test]
+    fn unused_one_named_with_its_type() {
+        let diagnostics = diagnostics(&format!("{SIZE}
+def main(- Int)
+| = 2 size
+"));
+        let unused: Vec<_> = diagnostics.iter().filter(|diagnostic| diagnostic.code == "W0004").collect();
+        assert_eq!(unused.len(), 1);
+        assert_eq!(unused[0].message, "the def `size`, the overload of type `(String - Int)`, is never used");
+    }
+}
+
+mod aliases {
+    use super::*;
+
+    #[test]
+    fn alias_of_a_tuple() {
+        accepts("
+type Point = (Int, Int)
+
+def origin(- Point)
+| = (0, 0)
+");
+    }
+
+    #[test]
+    fn cyclic_alias() {
+        rejects("
+type Loop = [Loop - ]
+", "E0033");
+    }
+}
+
+mod holes {
+    use super::*;
+
+    #[test]
+    fn hole_left_in_a_branch() {
+        rejects("
+def main(- Int)
+| = 1 ? +
+", "E0035");
+    }
+}
+
+mod limits {
+    use sl::{parser::Parser, tokens::Tokens, type_checker::TypeChecker};
+
+    fn check_with(checker: TypeChecker, source: &str) -> Vec<&'static str> {
+        let mut diagnostics: Vec<sl::diagnostics::Diagnostic> = vec![];
+        let top_levels = Parser::new(Tokens::new(source)).top_levels(&mut diagnostics).unwrap();
+        checker.type_check(top_levels, &mut diagnostics);
+        diagnostics.iter().map(|diagnostic| diagnostic.code).collect()
+    }
+
+    #[test]
+    fn shadowing_forbidden() {
+        let checker = TypeChecker::builder().forbid_shadowing(true).build();
+        assert!(check_with(checker, "
+def id(Int - Int)
+| id = id
+").contains(&"E0010"));
+    }
+
+    #[test]
+    fn quotations_nested_past_the_limit() {
+        let checker = TypeChecker::builder().recursion_limit(2).build();
+        assert!(check_with(checker, "
+def main(- [- [- [- Int]]])
+| = [- [- [- 1]]]
+").contains(&"E0011"));
+    }
+
+    #[test]
+    fn quotations_nested_within_the_limit() {
+        let checker = TypeChecker::builder().recursion_limit(3).build();
+        assert_eq!(check_with(checker, "
+def main(- [- [- [- Int]]])
+| = [- [- [- 1]]]
+"), Vec::<&str>::new());
+    }
+}